@@ -0,0 +1,8 @@
+ALTER TABLE "level"
+    DROP COLUMN "sort_direction";
+
+ALTER TABLE "level"
+    DROP COLUMN "score_unit";
+
+ALTER TABLE "level"
+    DROP COLUMN "aggregation";
@@ -0,0 +1,8 @@
+ALTER TABLE "level"
+    ADD COLUMN "sort_direction" VARCHAR(20) NOT NULL DEFAULT 'higher_is_better';
+
+ALTER TABLE "level"
+    ADD COLUMN "score_unit" VARCHAR(20) NOT NULL DEFAULT 'points';
+
+ALTER TABLE "level"
+    ADD COLUMN "aggregation" VARCHAR(20) NOT NULL DEFAULT 'all_submissions';
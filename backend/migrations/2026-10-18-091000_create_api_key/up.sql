@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS "api_key"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "name" VARCHAR(50) NOT NULL,
+    "prefix" VARCHAR(16) NOT NULL,
+    "key_hash" VARCHAR(64) NOT NULL UNIQUE,
+    "game_id" uuid NOT NULL,
+    "revoked_at" TIMESTAMP,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMP,
+    CONSTRAINT "fk_game_api_key"
+        FOREIGN KEY ("game_id")
+            REFERENCES "game" ("id")
+            ON DELETE CASCADE
+);
+
+SELECT diesel_manage_updated_at('api_key');
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS "submission_nonce";
+
+ALTER TABLE "game"
+    DROP COLUMN "signing_secret";
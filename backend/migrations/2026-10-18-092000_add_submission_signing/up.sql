@@ -0,0 +1,14 @@
+ALTER TABLE "game"
+    ADD COLUMN "signing_secret" VARCHAR(128);
+
+CREATE TABLE IF NOT EXISTS "submission_nonce"
+(
+    "game_id" uuid NOT NULL,
+    "nonce" VARCHAR(64) NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("game_id", "nonce"),
+    CONSTRAINT "fk_game_submission_nonce"
+        FOREIGN KEY ("game_id")
+            REFERENCES "game" ("id")
+            ON DELETE CASCADE
+);
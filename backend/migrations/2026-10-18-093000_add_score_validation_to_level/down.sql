@@ -0,0 +1,13 @@
+DROP INDEX IF EXISTS "idx_score_level_user_created_at";
+
+ALTER TABLE "level"
+    DROP COLUMN "min_score";
+
+ALTER TABLE "level"
+    DROP COLUMN "max_score";
+
+ALTER TABLE "level"
+    DROP COLUMN "max_submissions";
+
+ALTER TABLE "level"
+    DROP COLUMN "submission_window";
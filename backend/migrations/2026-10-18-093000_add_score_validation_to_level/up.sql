@@ -0,0 +1,14 @@
+ALTER TABLE "level"
+    ADD COLUMN "min_score" INTEGER;
+
+ALTER TABLE "level"
+    ADD COLUMN "max_score" INTEGER;
+
+ALTER TABLE "level"
+    ADD COLUMN "max_submissions" INTEGER;
+
+ALTER TABLE "level"
+    ADD COLUMN "submission_window" INTEGER;
+
+CREATE INDEX IF NOT EXISTS "idx_score_level_user_created_at"
+    ON "score" ("level_id", "user_id", "created_at");
@@ -0,0 +1,9 @@
+DROP TABLE IF EXISTS "refresh_token";
+
+DROP INDEX IF EXISTS "idx_user_game_device_id_hash";
+
+ALTER TABLE "user"
+    DROP COLUMN "password_hash";
+
+ALTER TABLE "user"
+    DROP COLUMN "device_id_hash";
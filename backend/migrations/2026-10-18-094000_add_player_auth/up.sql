@@ -0,0 +1,25 @@
+ALTER TABLE "user"
+    ADD COLUMN "password_hash" VARCHAR(255);
+
+ALTER TABLE "user"
+    ADD COLUMN "device_id_hash" VARCHAR(64);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "idx_user_game_device_id_hash"
+    ON "user" ("game_id", "device_id_hash");
+
+CREATE TABLE IF NOT EXISTS "refresh_token"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "token_hash" VARCHAR(64) NOT NULL UNIQUE,
+    "user_id" uuid NOT NULL,
+    "expires_at" TIMESTAMP NOT NULL,
+    "revoked_at" TIMESTAMP,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMP,
+    CONSTRAINT "fk_user_refresh_token"
+        FOREIGN KEY ("user_id")
+            REFERENCES "user" ("id")
+            ON DELETE CASCADE
+);
+
+SELECT diesel_manage_updated_at('refresh_token');
@@ -0,0 +1,2 @@
+ALTER TABLE "user"
+    ADD COLUMN "is_guest" BOOLEAN NOT NULL DEFAULT FALSE;
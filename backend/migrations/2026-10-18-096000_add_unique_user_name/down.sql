@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS "idx_user_game_canonical_name";
+
+ALTER TABLE "user"
+    DROP COLUMN "canonical_name";
+
+DROP INDEX IF EXISTS "idx_user_game_lower_name";
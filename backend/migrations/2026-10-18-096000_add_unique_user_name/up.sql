@@ -0,0 +1,30 @@
+-- Names that only differ in case within a game cannot be renamed safely here, so the migration stops and lists
+-- them. Rename the users and run the migration again.
+DO
+$$
+    DECLARE
+        conflicts TEXT;
+    BEGIN
+        SELECT STRING_AGG(FORMAT('game %s: %s', "game_id", "names"), E'\n')
+        INTO conflicts
+        FROM (SELECT "game_id", STRING_AGG(FORMAT('''%s'' (%s)', "name", "id"), ', ' ORDER BY "created_at") AS "names"
+              FROM "user"
+              GROUP BY "game_id", LOWER("name")
+              HAVING COUNT(*) > 1) AS "duplicate";
+
+        IF conflicts IS NOT NULL THEN
+            RAISE EXCEPTION E'Users with names that only differ in case must be renamed first:\n%', conflicts;
+        END IF;
+    END
+$$;
+
+CREATE UNIQUE INDEX IF NOT EXISTS "idx_user_game_lower_name"
+    ON "user" ("game_id", LOWER("name"));
+
+-- The canonical name is the lower case confusable skeleton of the name, computed by the application. Existing
+-- users are filled in by the application before the canonical name becomes required.
+ALTER TABLE "user"
+    ADD COLUMN "canonical_name" TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS "idx_user_game_canonical_name"
+    ON "user" ("game_id", "canonical_name");
@@ -0,0 +1,21 @@
+-- The entity is not a foreign key, so the log is kept when the entity is deleted.
+CREATE TABLE IF NOT EXISTS "audit_log"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "actor" VARCHAR(255) NOT NULL,
+    "action" VARCHAR(20) NOT NULL,
+    "entity_type" VARCHAR(20) NOT NULL,
+    "entity_id" uuid NOT NULL,
+    "before" JSONB,
+    "after" JSONB,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "idx_audit_log_created_at"
+    ON "audit_log" ("created_at", "id");
+
+CREATE INDEX IF NOT EXISTS "idx_audit_log_entity"
+    ON "audit_log" ("entity_type", "entity_id");
+
+CREATE INDEX IF NOT EXISTS "idx_audit_log_actor"
+    ON "audit_log" ("actor");
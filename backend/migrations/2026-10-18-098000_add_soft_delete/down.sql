@@ -0,0 +1,16 @@
+DELETE FROM "score" WHERE "deleted_at" IS NOT NULL;
+DELETE FROM "user" WHERE "deleted_at" IS NOT NULL;
+DELETE FROM "level" WHERE "deleted_at" IS NOT NULL;
+DELETE FROM "game" WHERE "deleted_at" IS NOT NULL;
+
+ALTER TABLE "score"
+    DROP COLUMN "deleted_at";
+
+ALTER TABLE "user"
+    DROP COLUMN "deleted_at";
+
+ALTER TABLE "level"
+    DROP COLUMN "deleted_at";
+
+ALTER TABLE "game"
+    DROP COLUMN "deleted_at";
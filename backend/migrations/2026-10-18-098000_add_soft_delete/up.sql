@@ -0,0 +1,18 @@
+-- Deleted rows are kept until they are purged after the retention period. Children that are deleted together with
+-- their parent get the same moment of deletion, so restoring the parent restores exactly those children.
+ALTER TABLE "game"
+    ADD COLUMN "deleted_at" TIMESTAMP;
+
+ALTER TABLE "level"
+    ADD COLUMN "deleted_at" TIMESTAMP;
+
+ALTER TABLE "user"
+    ADD COLUMN "deleted_at" TIMESTAMP;
+
+ALTER TABLE "score"
+    ADD COLUMN "deleted_at" TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS "idx_game_deleted_at" ON "game" ("deleted_at") WHERE "deleted_at" IS NOT NULL;
+CREATE INDEX IF NOT EXISTS "idx_level_deleted_at" ON "level" ("deleted_at") WHERE "deleted_at" IS NOT NULL;
+CREATE INDEX IF NOT EXISTS "idx_user_deleted_at" ON "user" ("deleted_at") WHERE "deleted_at" IS NOT NULL;
+CREATE INDEX IF NOT EXISTS "idx_score_deleted_at" ON "score" ("deleted_at") WHERE "deleted_at" IS NOT NULL;
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS "idx_score_level_created_at";
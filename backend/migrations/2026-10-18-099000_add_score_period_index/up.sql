@@ -0,0 +1,4 @@
+-- Daily, weekly and monthly leaderboards rank the scores of a level submitted within a period.
+CREATE INDEX IF NOT EXISTS "idx_score_level_created_at"
+    ON "score" ("level_id", "created_at")
+    WHERE "deleted_at" IS NULL;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS "season_standing";
+DROP TABLE IF EXISTS "season";
@@ -0,0 +1,61 @@
+-- A season is archived once it has ended, after which the live leaderboards only rank the scores submitted
+-- after the end of the season. The scores themselves are kept.
+CREATE TABLE IF NOT EXISTS "season"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "name" VARCHAR(50) NOT NULL,
+    "game_id" uuid NOT NULL,
+    "starts_at" TIMESTAMP NOT NULL,
+    "ends_at" TIMESTAMP NOT NULL,
+    "archived_at" TIMESTAMP,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMP,
+    CONSTRAINT "chk_season_period"
+        CHECK ("ends_at" > "starts_at"),
+    CONSTRAINT "fk_game_season"
+        FOREIGN KEY ("game_id")
+            REFERENCES "game" ("id")
+            ON DELETE CASCADE
+);
+
+SELECT diesel_manage_updated_at('season');
+
+CREATE INDEX IF NOT EXISTS "idx_season_game_starts_at"
+    ON "season" ("game_id", "starts_at");
+
+CREATE INDEX IF NOT EXISTS "idx_season_pending"
+    ON "season" ("ends_at")
+    WHERE "archived_at" IS NULL;
+
+-- The name and score are copied, so the standings stay the same when a user or score changes afterwards.
+CREATE TABLE IF NOT EXISTS "season_standing"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "season_id" uuid NOT NULL,
+    "level_id" uuid NOT NULL,
+    "rank" BIGINT NOT NULL,
+    "score_id" uuid,
+    "user_id" uuid,
+    "name" VARCHAR(50),
+    "score" INTEGER NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "fk_season_season_standing"
+        FOREIGN KEY ("season_id")
+            REFERENCES "season" ("id")
+            ON DELETE CASCADE,
+    CONSTRAINT "fk_level_season_standing"
+        FOREIGN KEY ("level_id")
+            REFERENCES "level" ("id")
+            ON DELETE CASCADE,
+    CONSTRAINT "fk_score_season_standing"
+        FOREIGN KEY ("score_id")
+            REFERENCES "score" ("id")
+            ON DELETE SET NULL,
+    CONSTRAINT "fk_user_season_standing"
+        FOREIGN KEY ("user_id")
+            REFERENCES "user" ("id")
+            ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS "idx_season_standing_season_level_rank"
+    ON "season_standing" ("season_id", "level_id", "rank");
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS "tournament_prize";
+DROP TABLE IF EXISTS "tournament_entrant";
+DROP TABLE IF EXISTS "tournament_level";
+DROP TABLE IF EXISTS "tournament";
@@ -0,0 +1,70 @@
+-- A tournament ranks the scores its entrants submit on its levels during its time window. The scores themselves
+-- are the regular scores of the levels.
+CREATE TABLE IF NOT EXISTS "tournament"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "name" VARCHAR(50) NOT NULL,
+    "game_id" uuid NOT NULL,
+    "starts_at" TIMESTAMP NOT NULL,
+    "ends_at" TIMESTAMP NOT NULL,
+    "max_entrants" INTEGER,
+    "scoring" VARCHAR(20) NOT NULL DEFAULT 'sum',
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMP,
+    CONSTRAINT "chk_tournament_period"
+        CHECK ("ends_at" > "starts_at"),
+    CONSTRAINT "chk_tournament_max_entrants"
+        CHECK ("max_entrants" > 0),
+    CONSTRAINT "fk_game_tournament"
+        FOREIGN KEY ("game_id")
+            REFERENCES "game" ("id")
+            ON DELETE CASCADE
+);
+
+SELECT diesel_manage_updated_at('tournament');
+
+CREATE INDEX IF NOT EXISTS "idx_tournament_game_starts_at"
+    ON "tournament" ("game_id", "starts_at");
+
+CREATE TABLE IF NOT EXISTS "tournament_level"
+(
+    "tournament_id" uuid NOT NULL,
+    "level_id" uuid NOT NULL,
+    PRIMARY KEY ("tournament_id", "level_id"),
+    CONSTRAINT "fk_tournament_tournament_level"
+        FOREIGN KEY ("tournament_id")
+            REFERENCES "tournament" ("id")
+            ON DELETE CASCADE,
+    CONSTRAINT "fk_level_tournament_level"
+        FOREIGN KEY ("level_id")
+            REFERENCES "level" ("id")
+            ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "tournament_entrant"
+(
+    "tournament_id" uuid NOT NULL,
+    "user_id" uuid NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("tournament_id", "user_id"),
+    CONSTRAINT "fk_tournament_tournament_entrant"
+        FOREIGN KEY ("tournament_id")
+            REFERENCES "tournament" ("id")
+            ON DELETE CASCADE,
+    CONSTRAINT "fk_user_tournament_entrant"
+        FOREIGN KEY ("user_id")
+            REFERENCES "user" ("id")
+            ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS "tournament_prize"
+(
+    "tournament_id" uuid NOT NULL,
+    "rank" INTEGER NOT NULL,
+    "description" VARCHAR(255) NOT NULL,
+    PRIMARY KEY ("tournament_id", "rank"),
+    CONSTRAINT "fk_tournament_tournament_prize"
+        FOREIGN KEY ("tournament_id")
+            REFERENCES "tournament" ("id")
+            ON DELETE CASCADE
+);
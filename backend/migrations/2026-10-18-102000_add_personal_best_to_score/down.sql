@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS "idx_score_personal_best";
+
+ALTER TABLE "score"
+    DROP COLUMN IF EXISTS "is_personal_best";
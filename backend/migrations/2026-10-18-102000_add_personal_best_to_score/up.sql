@@ -0,0 +1,36 @@
+ALTER TABLE "score"
+    ADD COLUMN "is_personal_best" BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- A personal best submission only replaced the best score of the user, so the other scores of the user remained
+-- next to it. On levels that rank only the best score per user, the best score of every user becomes the personal
+-- best. The other scores of the user are kept as regular scores, and the moment the scores were last updated is
+-- kept as well.
+ALTER TABLE "score" DISABLE TRIGGER "set_updated_at";
+
+WITH "ranked" AS (
+    SELECT "score"."id", ROW_NUMBER() OVER (
+        PARTITION BY "score"."level_id", "score"."user_id"
+        ORDER BY
+            CASE WHEN "level"."sort_direction" = 'lower_is_better' THEN "score"."score" END ASC,
+            CASE WHEN "level"."sort_direction" = 'higher_is_better' THEN "score"."score" END DESC,
+            "score"."created_at" ASC,
+            "score"."id" ASC
+    ) AS "position"
+    FROM "score"
+    JOIN "level" ON "level"."id" = "score"."level_id"
+    WHERE "level"."aggregation" = 'best_per_user'
+        AND "score"."user_id" IS NOT NULL
+        AND "score"."deleted_at" IS NULL
+)
+UPDATE "score"
+SET "is_personal_best" = TRUE
+FROM "ranked"
+WHERE "score"."id" = "ranked"."id"
+    AND "ranked"."position" = 1;
+
+ALTER TABLE "score" ENABLE TRIGGER "set_updated_at";
+
+-- A user has a single personal best on a level.
+CREATE UNIQUE INDEX IF NOT EXISTS "idx_score_personal_best"
+    ON "score" ("level_id", "user_id")
+    WHERE "is_personal_best" AND "deleted_at" IS NULL;
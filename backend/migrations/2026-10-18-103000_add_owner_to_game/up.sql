@@ -0,0 +1,4 @@
+-- The subject of the dashboard user that owns the game. Game owners can only manage the games they own, so the
+-- existing games can only be managed by admins until an admin assigns their owner.
+ALTER TABLE "game"
+    ADD COLUMN "owner" VARCHAR(255);
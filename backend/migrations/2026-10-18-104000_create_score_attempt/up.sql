@@ -0,0 +1,30 @@
+-- Every submission is an attempt, also the personal best submissions that don't store a new score, so the
+-- submission limit of a level is counted on the attempts instead of the stored scores.
+CREATE TABLE IF NOT EXISTS "score_attempt"
+(
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    "level_id" uuid NOT NULL,
+    "user_id" uuid,
+    "username" VARCHAR(50),
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "fk_level_score_attempt"
+        FOREIGN KEY ("level_id")
+            REFERENCES "level" ("id")
+            ON DELETE CASCADE,
+    CONSTRAINT "fk_user_score_attempt"
+        FOREIGN KEY ("user_id")
+            REFERENCES "user" ("id")
+            ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "idx_score_attempt_level_created_at"
+    ON "score_attempt" ("level_id", "created_at");
+
+-- The scores submitted within the current window of their level are the attempts made so far, so the limits keep
+-- applying right after the migration.
+INSERT INTO "score_attempt" ("level_id", "user_id", "username", "created_at")
+SELECT "score"."level_id", "score"."user_id", "score"."username", "score"."created_at"
+FROM "score"
+         JOIN "level" ON "level"."id" = "score"."level_id"
+WHERE "level"."submission_window" IS NOT NULL
+  AND "score"."created_at" > CURRENT_TIMESTAMP - make_interval(secs => "level"."submission_window");
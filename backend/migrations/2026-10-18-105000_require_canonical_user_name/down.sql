@@ -0,0 +1,2 @@
+ALTER TABLE "user"
+    ALTER COLUMN "canonical_name" DROP NOT NULL;
@@ -0,0 +1,4 @@
+-- The application fills in the canonical names of existing users before this migration runs, so users whose name
+-- looks like another user in the same game are reported instead of escaping the unique index.
+ALTER TABLE "user"
+    ALTER COLUMN "canonical_name" SET NOT NULL;
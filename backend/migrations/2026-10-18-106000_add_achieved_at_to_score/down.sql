@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS "idx_score_level_achieved_at";
+CREATE INDEX IF NOT EXISTS "idx_score_level_created_at"
+    ON "score" ("level_id", "created_at")
+    WHERE "deleted_at" IS NULL;
+
+ALTER TABLE "score"
+    DROP COLUMN IF EXISTS "achieved_at";
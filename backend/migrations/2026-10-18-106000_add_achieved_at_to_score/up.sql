@@ -0,0 +1,17 @@
+-- The moment a score was achieved, which is the moment it was submitted, or the moment a personal best was last
+-- improved. Periods are ranked on this moment, so a personal best improved within a period is ranked in it.
+ALTER TABLE "score"
+    ADD COLUMN "achieved_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+-- A personal best was only updated when it was improved. The moment the scores were last updated is kept.
+ALTER TABLE "score" DISABLE TRIGGER "set_updated_at";
+
+UPDATE "score"
+SET "achieved_at" = CASE WHEN "is_personal_best" THEN COALESCE("updated_at", "created_at") ELSE "created_at" END;
+
+ALTER TABLE "score" ENABLE TRIGGER "set_updated_at";
+
+DROP INDEX IF EXISTS "idx_score_level_created_at";
+CREATE INDEX IF NOT EXISTS "idx_score_level_achieved_at"
+    ON "score" ("level_id", "achieved_at")
+    WHERE "deleted_at" IS NULL;
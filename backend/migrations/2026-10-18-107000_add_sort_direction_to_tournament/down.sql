@@ -0,0 +1,2 @@
+ALTER TABLE "tournament"
+    DROP COLUMN IF EXISTS "sort_direction";
@@ -0,0 +1,14 @@
+-- The levels of a tournament share their sort direction, which is stored on the tournament when it is saved. The
+-- standings keep their order when the sort direction of one of the levels is changed later on.
+ALTER TABLE "tournament"
+    ADD COLUMN "sort_direction" VARCHAR(20) NOT NULL DEFAULT 'higher_is_better';
+
+ALTER TABLE "tournament" DISABLE TRIGGER "set_updated_at";
+
+UPDATE "tournament"
+SET "sort_direction" = "level"."sort_direction"
+FROM "tournament_level"
+JOIN "level" ON "level"."id" = "tournament_level"."level_id"
+WHERE "tournament_level"."tournament_id" = "tournament"."id";
+
+ALTER TABLE "tournament" ENABLE TRIGGER "set_updated_at";
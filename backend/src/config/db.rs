@@ -1,27 +1,114 @@
-use diesel::{PgConnection, r2d2::{self, ConnectionManager}};
+use diesel::{Connection as _, PgConnection, r2d2::{self, ConnectionManager}};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use log::info;
+use log::{error, info};
+use tokio::task::spawn_blocking;
+
+use crate::{config::Config, response::AppError, service::name_service};
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
 
+/// The version of the migration that makes the canonical name of users required. Canonical names are computed by
+/// the application, so the names of existing users are canonicalized right before this migration runs.
+const REQUIRE_CANONICAL_NAME: &str = "20261018105000";
+
 pub type Connection = PgConnection;
 pub type Pool = r2d2::Pool<ConnectionManager<Connection>>;
 
-/// Creates a new connection pool to the database using the given connection url. 
-pub fn init_db_pool(url: &str) -> Pool {
+/// Creates a new connection pool to the database using the connection url and pool settings of the given
+/// configuration.
+pub fn init_db_pool(config: &Config) -> Pool {
     info!("Configuring database pool");
 
-    let manager = ConnectionManager::<Connection>::new(url);
-    let pool = Pool::builder()
+    let manager = ConnectionManager::<Connection>::new(&config.database_url);
+    Pool::builder()
+        .max_size(config.database_max_connections)
+        .min_idle(config.database_min_idle)
+        .connection_timeout(config.database_connection_timeout)
+        .idle_timeout(Some(config.database_idle_timeout))
         .build(manager)
-        .expect("Failed to create pool.");
-
-    pool
+        .expect("Failed to create pool.")
 }
 
 /// Runs the migration scripts to create, update or delete database related content.
-pub fn  run_migration(conn: &mut Connection) {
+///
+/// # Errors
+/// - If a migration fails.
+/// - If existing users have names that look like another user in the same game.
+pub fn run_migration(conn: &mut Connection) -> Result<(), String> {
     info!("Running migrations");
 
-    conn.run_pending_migrations(MIGRATIONS).unwrap();
-}
\ No newline at end of file
+    let pending = conn
+        .pending_migrations(MIGRATIONS)
+        .map_err(|err| format!("Cannot find pending migrations, reason {}", err))?;
+    for migration in pending {
+        if migration.name().version().to_string() == REQUIRE_CANONICAL_NAME {
+            name_service::backfill_canonical_names(conn)?;
+        }
+        conn.run_migration(&migration)
+            .map_err(|err| format!("Cannot run migration {}, reason {}", migration.name(), err))?;
+    }
+
+    Ok(())
+}
+
+/// Checks out a connection from the pool and runs the given function with it on a thread where blocking is
+/// allowed, so the database queries don't block the async runtime. The same connection is used for every query
+/// in the function.
+///
+/// # Errors
+/// - If no connection is available before the timeout of the pool, in which case a 503 is returned.
+/// - If the given function fails.
+pub async fn with_connection<F, T>(pool: Pool, f: F) -> Result<T, AppError>
+where
+    F: FnOnce(&mut Connection) -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    let result = spawn_blocking(move || {
+        let mut conn = pool.get().map_err(|err| {
+            error!("Could not get a database connection: {}", err);
+            AppError::ServiceUnavailable("Database is unavailable, try again later".to_string())
+        })?;
+
+        f(&mut conn)
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(err) => {
+            error!("Database task failed: {}", err);
+            Err(AppError::Internal("Internal server error".to_string()))
+        }
+    }
+}
+
+/// Runs the given function in a database transaction, which is rolled back when the function fails.
+///
+/// # Errors
+/// - If the given function fails.
+pub fn transaction<F, T>(conn: &mut Connection, f: F) -> Result<T, AppError>
+where
+    F: FnOnce(&mut Connection) -> Result<T, AppError>,
+{
+    conn.transaction(f)
+}
+
+/// Opens a connection to the database in `TEST_DATABASE_URL` for the tests that need a database. The migrations
+/// are run once per test run, and every query of the test runs in a transaction that is never committed. The tests
+/// that need a database are ignored by default, and run with `cargo test -- --ignored`.
+///
+/// # Panics
+/// - If no test database is configured, or it cannot be migrated.
+#[cfg(test)]
+pub fn test_connection() -> Connection {
+    use std::sync::Once;
+
+    static MIGRATE: Once = Once::new();
+
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set to run database tests");
+    let mut conn = Connection::establish(&url).expect("Cannot connect to the test database");
+    MIGRATE.call_once(|| run_migration(&mut conn).expect("Cannot migrate the test database"));
+    conn.begin_test_transaction().expect("Cannot start the test transaction");
+
+    conn
+}
@@ -1 +1,309 @@
-pub mod db;
\ No newline at end of file
+use std::{collections::HashMap, env, fmt, fs, net::SocketAddr, str::FromStr, time::Duration};
+
+use axum::http::HeaderValue;
+use jsonwebtoken::Algorithm;
+
+pub mod db;
+
+/// The path of the optional configuration file, which can be changed with the `CONFIG_FILE` environment variable.
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// The configuration of the application. The configuration is loaded once at startup from the optional TOML
+/// file, after which every value can be overridden by an environment variable with the same name in upper case,
+/// e.g. `database_max_connections` in the file and `DATABASE_MAX_CONNECTIONS` in the environment.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub app_host: String,
+    pub app_port: u16,
+    pub database_url: String,
+    /// The maximum number of connections in the database pool.
+    pub database_max_connections: u32,
+    /// The minimum number of idle connections kept in the database pool, defaults to the maximum.
+    pub database_min_idle: Option<u32>,
+    /// The time to wait for a free connection before a request fails with a 503.
+    pub database_connection_timeout: Duration,
+    /// The time after which an idle connection is closed.
+    pub database_idle_timeout: Duration,
+    /// The issuer of the access tokens. When set, the JWKS url and the allowed algorithms are discovered from the
+    /// OpenID Connect discovery document of the issuer, and the `iss` claim of every token is validated.
+    pub oauth_issuer_url: Option<String>,
+    /// The url of the JWKS, only required when no issuer is set.
+    pub oauth_jwks_url: Option<String>,
+    pub oauth_client_id: String,
+    /// The algorithms access tokens can be signed with. When empty, the algorithms of the discovery document are
+    /// used, or `RS256` when no issuer is set.
+    pub oauth_algorithms: Vec<Algorithm>,
+    /// The claim of the access token that contains the roles of the user, see
+    /// [`auth_middleware::verify_token`](crate::middleware::auth_middleware::verify_token), defaults to `roles`.
+    /// A dashboard user without roles in the claim can't access anything.
+    pub oauth_roles_claim: String,
+    /// The interval at which the JWKS of the authorization server is fetched.
+    pub jwks_refresh_interval: Duration,
+    /// The origins that are allowed to call the api, every origin is allowed when the list is empty.
+    pub cors_allowed_origins: Vec<HeaderValue>,
+    /// The secret the access tokens of players are signed with. A random secret is generated at startup when
+    /// empty, which signs out every player when the server restarts.
+    pub player_token_secret: Option<String>,
+    /// The time a player access token is valid.
+    pub player_token_ttl: Duration,
+    /// The time a player refresh token is valid.
+    pub player_refresh_token_ttl: Duration,
+    /// Words that cannot be used in player names and usernames of scores.
+    pub name_blocklist: Vec<String>,
+    /// A file with a blocked word on every line, added to `name_blocklist`.
+    pub name_blocklist_file: Option<String>,
+    /// The time deleted games, levels, users and scores can be restored, after which they are removed for good.
+    pub deleted_retention: Duration,
+    /// The interval at which data deleted longer than `deleted_retention` ago is removed.
+    pub purge_interval: Duration,
+    /// The interval at which the seasons that have ended are archived.
+    pub season_interval: Duration,
+    /// The directory the front end is served from.
+    pub static_dir: String,
+    /// The default log level, the `RUST_LOG` environment variable takes precedence.
+    pub log_level: String,
+}
+
+/// The error returned when the configuration could not be loaded, listing every invalid value.
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            writeln!(f, "- {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Loads the configuration from the configuration file and the environment.
+    ///
+    /// # Errors
+    /// - If the configuration file exists but cannot be parsed.
+    /// - If a required value is missing or a value is invalid.
+    pub fn load() -> Result<Config, ConfigError> {
+        let path = env::var("CONFIG_FILE").ok();
+        let mut values = match read_file(path.as_deref().unwrap_or(DEFAULT_CONFIG_FILE), path.is_some()) {
+            Ok(values) => values,
+            Err(err) => return Err(ConfigError(vec![err])),
+        };
+        values.extend(env::vars().map(|(key, value)| (key.to_lowercase(), value)));
+
+        let mut source = Source {
+            values,
+            errors: Vec::new(),
+        };
+
+        let config = Config {
+            app_host: source.required("app_host"),
+            app_port: source.parse_required("app_port"),
+            database_url: source.required("database_url"),
+            database_max_connections: source.parse("database_max_connections").unwrap_or(10),
+            database_min_idle: source.parse("database_min_idle"),
+            database_connection_timeout: source.seconds("database_connection_timeout", 30),
+            database_idle_timeout: source.seconds("database_idle_timeout", 600),
+            oauth_issuer_url: source.parse("oauth_issuer_url"),
+            oauth_jwks_url: source.parse("oauth_jwks_url"),
+            oauth_client_id: source.required("oauth_client_id"),
+            oauth_algorithms: source.list("oauth_algorithms"),
+            oauth_roles_claim: source.optional("oauth_roles_claim", "roles"),
+            jwks_refresh_interval: source.seconds("jwks_refresh_interval", 604_800),
+            cors_allowed_origins: source.origins("cors_allowed_origins"),
+            player_token_secret: source.parse("player_token_secret"),
+            player_token_ttl: source.seconds("player_token_ttl", 900),
+            player_refresh_token_ttl: source.seconds("player_refresh_token_ttl", 2_592_000),
+            name_blocklist: source.list("name_blocklist"),
+            name_blocklist_file: source.parse("name_blocklist_file"),
+            deleted_retention: source.seconds("deleted_retention", 2_592_000),
+            purge_interval: source.seconds("purge_interval", 3600),
+            season_interval: source.seconds("season_interval", 60),
+            static_dir: source.optional("static_dir", "./dist"),
+            log_level: source.optional("log_level", "info"),
+        };
+
+        config.validate(&mut source.errors);
+        if source.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(source.errors))
+        }
+    }
+
+    /// Returns the address the server listens on.
+    pub fn address(&self) -> Result<SocketAddr, ConfigError> {
+        format!("{}:{}", self.app_host, self.app_port)
+            .parse()
+            .map_err(|_| ConfigError(vec!["app_host and app_port must form a valid socket address".to_string()]))
+    }
+
+    /// Checks the values that depend on each other.
+    fn validate(&self, errors: &mut Vec<String>) {
+        if self.database_max_connections == 0 {
+            errors.push("database_max_connections must be at least 1".to_string());
+        }
+        if self
+            .database_min_idle
+            .is_some_and(|min_idle| min_idle > self.database_max_connections)
+        {
+            errors.push("database_min_idle cannot be larger than database_max_connections".to_string());
+        }
+        if self.database_connection_timeout.is_zero() {
+            errors.push("database_connection_timeout must be at least 1 second".to_string());
+        }
+        if self.oauth_issuer_url.is_none() && self.oauth_jwks_url.is_none() {
+            errors.push("oauth_issuer_url or oauth_jwks_url must be set".to_string());
+        }
+        if self
+            .oauth_algorithms
+            .iter()
+            .any(|algorithm| matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512))
+        {
+            errors.push("oauth_algorithms can only contain asymmetric algorithms".to_string());
+        }
+        if self
+            .player_token_secret
+            .as_ref()
+            .is_some_and(|secret| secret.len() < 32)
+        {
+            errors.push("player_token_secret must be at least 32 characters".to_string());
+        }
+        if self.player_token_ttl.is_zero() || self.player_refresh_token_ttl.is_zero() {
+            errors.push("player_token_ttl and player_refresh_token_ttl must be at least 1 second".to_string());
+        }
+        if self.jwks_refresh_interval.is_zero() {
+            errors.push("jwks_refresh_interval must be at least 1 second".to_string());
+        }
+        if self.purge_interval.is_zero() {
+            errors.push("purge_interval must be at least 1 second".to_string());
+        }
+        if self.season_interval.is_zero() {
+            errors.push("season_interval must be at least 1 second".to_string());
+        }
+        if !self.app_host.is_empty() && self.app_port != 0 && self.address().is_err() {
+            errors.push("app_host and app_port must form a valid socket address".to_string());
+        }
+    }
+}
+
+/// The raw configuration values, together with the errors found while parsing them.
+struct Source {
+    values: HashMap<String, String>,
+    errors: Vec<String>,
+}
+
+impl Source {
+    fn required(&mut self, key: &str) -> String {
+        match self.values.get(key).filter(|value| !value.is_empty()) {
+            Some(value) => value.clone(),
+            None => {
+                self.errors.push(format!("{} must be set", key));
+                String::new()
+            }
+        }
+    }
+
+    fn optional(&mut self, key: &str, default: &str) -> String {
+        match self.values.get(key).filter(|value| !value.is_empty()) {
+            Some(value) => value.clone(),
+            None => default.to_string(),
+        }
+    }
+
+    /// Parses the value with the given key, returning `None` when the value is missing or invalid.
+    fn parse<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        let value = self.values.get(key).filter(|value| !value.is_empty())?;
+
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.errors.push(format!("{} has an invalid value '{}'", key, value));
+                None
+            }
+        }
+    }
+
+    fn parse_required<T: FromStr + Default>(&mut self, key: &str) -> T {
+        if self.values.get(key).is_none_or(|value| value.is_empty()) {
+            self.errors.push(format!("{} must be set", key));
+        }
+
+        self.parse(key).unwrap_or_default()
+    }
+
+    fn seconds(&mut self, key: &str, default: u64) -> Duration {
+        Duration::from_secs(self.parse(key).unwrap_or(default))
+    }
+
+    /// Parses a comma separated list of values.
+    fn list<T: FromStr>(&mut self, key: &str) -> Vec<T> {
+        let value = self.optional(key, "");
+
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .filter_map(|item| match item.parse() {
+                Ok(item) => Some(item),
+                Err(_) => {
+                    self.errors.push(format!("{} contains an invalid value '{}'", key, item));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Parses a comma separated list of origins, `*` allows every origin.
+    fn origins(&mut self, key: &str) -> Vec<HeaderValue> {
+        let value = self.optional(key, "*");
+
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty() && *origin != "*")
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(origin) => Some(origin),
+                Err(_) => {
+                    self.errors.push(format!("{} contains an invalid origin '{}'", key, origin));
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Reads the values of the TOML configuration file. A missing file is only an error when its path is set
+/// explicitly.
+fn read_file(path: &str, is_required: bool) -> Result<HashMap<String, String>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) if !is_required => return Ok(HashMap::new()),
+        Err(err) => return Err(format!("Cannot read configuration file '{}': {}", path, err)),
+    };
+
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|err| format!("Cannot parse configuration file '{}': {}", path, err))?;
+
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Array(values) => values
+                    .iter()
+                    .map(|value| value.as_str().map(str::to_string).unwrap_or(value.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                value => value.to_string(),
+            };
+
+            (key.to_lowercase(), value)
+        })
+        .collect())
+}
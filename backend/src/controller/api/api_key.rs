@@ -0,0 +1,199 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        api_key::{ApiKey, ApiKeyForm, CreatedApiKey},
+        audit_log::{Actor, AuditEntity},
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{api_key_service, audit_service, game_service},
+    SharedState,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(index, store, rotate, destroy),
+    components(schemas(ApiKey, ApiKeyForm, CreatedApiKey, ApiKeysResponseBody, CreatedApiKeyResponseBody))
+)]
+pub struct ApiKeyApi;
+
+/// The structure of the response body where there are multiple API keys returned. This struct is primarily used
+/// for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct ApiKeysResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<ApiKey>,
+}
+
+/// The structure of the response body where a newly generated API key is returned. This struct is primarily
+/// used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct CreatedApiKeyResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: CreatedApiKey,
+}
+
+#[utoipa::path(
+    get,
+    path = "/game/{gameId}",
+    tag = "ApiKey",
+    operation_id = "api_key_index",
+    params(
+        ("gameId", Path, description = "Unique id of a Game")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "API keys fetched successfully", body = ApiKeysResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No game found by game id", body = ErrorResponse)
+    )
+)]
+pub async fn index(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(game_id): Path<Uuid>,
+) -> Result<ResponseBody<Vec<ApiKey>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        game_service::authorize_owner(&actor, game_id, conn)?;
+        api_key_service::find_by_game(game_id, conn)
+    })
+    .await;
+
+    match result {
+        Ok(keys) => Ok(ResponseBody::ok("API keys fetched", keys)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/game/{gameId}",
+    tag = "ApiKey",
+    operation_id = "api_key_store",
+    request_body = ApiKeyForm,
+    params(
+        ("gameId", Path, description = "Unique id of a Game")
+    ),
+    responses(
+        (status = StatusCode::CREATED, description = "API key created successfully", body = CreatedApiKeyResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No game found by game id", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn store(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(game_id): Path<Uuid>,
+    ValidatedJson(form): ValidatedJson<ApiKeyForm>,
+) -> Result<ResponseBody<CreatedApiKey>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            game_service::authorize_owner(&actor, game_id, conn)?;
+            let created = api_key_service::insert(game_id, form, conn)?;
+            audit_service::created(&actor, AuditEntity::ApiKey, created.api_key.id, &created.api_key, conn)?;
+            Ok(created)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(api_key) => Ok(ResponseBody::created("API key created", api_key)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/rotate",
+    tag = "ApiKey",
+    operation_id = "api_key_rotate",
+    params(
+        ("id", Path, description = "Unique id of an API key")
+    ),
+    responses(
+        (status = StatusCode::CREATED, description = "API key rotated successfully", body = CreatedApiKeyResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "API key is already revoked", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No API key found by id", body = ErrorResponse)
+    )
+)]
+pub async fn rotate(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<CreatedApiKey>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let created = audit_service::record_update(
+                &actor,
+                AuditEntity::ApiKey,
+                id,
+                |conn| api_key_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.game_id, conn)?;
+                    api_key_service::rotate(id, conn)
+                },
+                conn,
+            )?;
+            audit_service::created(&actor, AuditEntity::ApiKey, created.api_key.id, &created.api_key, conn)?;
+            Ok(created)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(api_key) => Ok(ResponseBody::created("API key rotated", api_key)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "ApiKey",
+    operation_id = "api_key_destroy",
+    params(
+        ("id", Path, description = "Unique id of an API key")
+    ),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "API key revoked successfully"),
+        (status = StatusCode::NOT_FOUND, description = "No API key found by id", body = ErrorResponse)
+    )
+)]
+pub async fn destroy(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::ApiKey,
+                id,
+                |conn| api_key_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.game_id, conn)?;
+                    api_key_service::revoke(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err(err),
+    }
+}
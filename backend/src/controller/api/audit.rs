@@ -0,0 +1,66 @@
+use axum::extract::{Query, State};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    config::db,
+    models::{
+        audit_log::{AuditAction, AuditEntity, AuditFilter, AuditLog, AuditSort},
+        pagination::{PageQuery, Pagination},
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::audit_service,
+    SharedState,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(index),
+    components(schemas(AuditLog, AuditAction, AuditEntity, AuditSort, AuditLogResponseBody, Pagination))
+)]
+pub struct AuditApi;
+
+/// The structure of the response body where there are multiple audit log entries returned. This struct is
+/// primarily used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct AuditLogResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<AuditLog>,
+    pub pagination: Pagination,
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "Audit",
+    operation_id = "audit_index",
+    params(
+        ("actor", Query, description = "Only changes made by the dashboard user with this subject"),
+        ("action", Query, description = "Only changes of this type, one of 'create', 'update' or 'delete'"),
+        ("entity_type", Query, description = "Only changes of this entity type, one of 'game', 'level', 'score', 'user' or 'api_key'"),
+        ("entity_id", Query, description = "Only changes of the entity with this id"),
+        ("from", Query, description = "Only changes made at or after this moment, e.g. '2026-01-01T00:00:00'"),
+        ("to", Query, description = "Only changes made before this moment"),
+        ("limit", Query, description = "Maximum number of items to return, defaults to 50 with a maximum of 500"),
+        ("cursor", Query, description = "Cursor of the next page, as returned in the pagination metadata of the previous page"),
+        ("sort", Query, description = "Field to sort on, only 'created_at' (default)"),
+        ("order", Query, description = "Sort order, either 'asc' (default) or 'desc'")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Audit log fetched successfully", body = AuditLogResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid filter or cursor", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Only admins can read the audit log", body = ErrorResponse)
+    )
+)]
+pub async fn index(
+    State(app_state): State<SharedState>,
+    Query(filter): Query<AuditFilter>,
+    Query(page): Query<PageQuery<AuditSort>>,
+) -> Result<ResponseBody<Vec<AuditLog>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+
+    match db::with_connection(pool, move |conn| audit_service::find_all(&filter, &page, conn)).await {
+        Ok(entries) => Ok(ResponseBody::page("Audit log fetched", entries)),
+        Err(err) => Err(err),
+    }
+}
@@ -1,22 +1,64 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header::CONTENT_DISPOSITION, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::json;
 use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    models::game::{Game, GameDTO},
-    response::{ErrorResponse, ResponseBody},
-    service::game_service,
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        archive::{
+            ArchiveExportQuery, ArchiveIds, ArchiveImportQuery, ArchivedGame, ArchivedLevel, ArchivedScore,
+            ArchivedUser, GameArchive, NameConflict,
+        },
+        audit_log::{Actor, AuditEntity},
+        auth::Role,
+        game::{Game, GameDTO, GameSort},
+        pagination::{PageQuery, Pagination},
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{archive_service, audit_service, export_service, game_service, signature_service},
     SharedState,
 };
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(index, show, store, update, destroy),
-    components(schemas(Game, GameDTO, GameResponseBody, GamesResponseBody))
+    paths(
+        index,
+        show,
+        store,
+        update,
+        destroy,
+        restore,
+        generate_signing_secret,
+        remove_signing_secret,
+        export,
+        import
+    ),
+    components(schemas(
+        ArchivedGame,
+        ArchivedLevel,
+        ArchivedScore,
+        ArchivedUser,
+        ArchiveIds,
+        Game,
+        GameArchive,
+        GameDTO,
+        GameResponseBody,
+        GamesResponseBody,
+        GameSort,
+        NameConflict,
+        Pagination,
+        SigningSecret,
+        SigningSecretResponseBody
+    ))
 )]
 pub struct GameApi;
 
@@ -36,6 +78,22 @@ pub struct GamesResponseBody {
     pub message: String,
     pub status: String,
     pub data: Vec<Game>,
+    pub pagination: Pagination,
+}
+
+/// A newly generated secret to sign score submissions with. The secret is returned once, when it is generated.
+#[derive(Serialize, ToSchema)]
+pub struct SigningSecret {
+    pub secret: String,
+}
+
+/// The structure of the response body where a signing secret is returned. This struct is primarily used for
+/// the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct SigningSecretResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: SigningSecret,
 }
 
 #[utoipa::path(
@@ -43,17 +101,25 @@ pub struct GamesResponseBody {
     path = "",
     tag = "Game",
     operation_id = "game_index",
+    params(
+        ("limit", Query, description = "Maximum number of items to return, defaults to 50 with a maximum of 500"),
+        ("cursor", Query, description = "Cursor of the next page, as returned in the pagination metadata of the previous page"),
+        ("sort", Query, description = "Field to sort on, one of 'name' or 'created_at' (default)"),
+        ("order", Query, description = "Sort order, either 'asc' (default) or 'desc'")
+    ),
     responses(
-        (status = StatusCode::OK, description = "Games fetched successfully", body = GamesResponseBody)
+        (status = StatusCode::OK, description = "Games fetched successfully", body = GamesResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid cursor", body = ErrorResponse)
     )
 )]
 pub async fn index(
     State(app_state): State<SharedState>,
-) -> Result<ResponseBody<Vec<Game>>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    Query(page): Query<PageQuery<GameSort>>,
+) -> Result<ResponseBody<Vec<Game>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
 
-    match game_service::find_all(pool) {
-        Ok(games) => Ok(ResponseBody::ok("Games fetched", games)),
+    match db::with_connection(pool, move |conn| game_service::find_all(&page, conn)).await {
+        Ok(games) => Ok(ResponseBody::page("Games fetched", games)),
         Err(err) => Err(err),
     }
 }
@@ -74,10 +140,10 @@ pub async fn index(
 pub async fn show(
     State(app_state): State<SharedState>,
     Path(id): Path<Uuid>,
-) -> Result<ResponseBody<Game>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+) -> Result<ResponseBody<Game>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
 
-    match game_service::find_by_id(id, pool) {
+    match db::with_connection(pool, move |conn| game_service::find_by_id(id, conn)).await {
         Ok(game) => Ok(ResponseBody::ok("Game fetched", game)),
         Err(err) => Err(err),
     }
@@ -91,16 +157,28 @@ pub async fn show(
     request_body = GameDTO,
     responses(
         (status = StatusCode::CREATED, description = "Game created successfully", body = GameResponseBody),
-        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse)
+        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Game name already in use", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body", body = ErrorResponse)
     )
 )]
 pub async fn store(
     State(app_state): State<SharedState>,
-    Json(new_game): Json<GameDTO>,
-) -> Result<ResponseBody<Game>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    actor: Actor,
+    ValidatedJson(mut new_game): ValidatedJson<GameDTO>,
+) -> Result<ResponseBody<Game>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            game_service::assign_owner(&actor, &mut new_game, true)?;
+            let game = game_service::insert(new_game, conn)?;
+            audit_service::created(&actor, AuditEntity::Game, game.id, &game, conn)?;
+            Ok(game)
+        })
+    })
+    .await;
 
-    match game_service::insert(new_game, pool) {
+    match result {
         Ok(game) => Ok(ResponseBody::created("Game created", game)),
         Err(err) => Err(err),
     }
@@ -118,17 +196,37 @@ pub async fn store(
     responses(
         (status = StatusCode::OK, description = "Game updated successfully", body = GameResponseBody),
         (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
-        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse)
+        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Game name already in use", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body", body = ErrorResponse)
     )
 )]
 pub async fn update(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-    Json(updated_game): Json<GameDTO>,
-) -> Result<ResponseBody<Game>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    ValidatedJson(mut updated_game): ValidatedJson<GameDTO>,
+) -> Result<ResponseBody<Game>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Game,
+                id,
+                |conn| game_service::find_by_id(id, conn),
+                |_, conn| {
+                    game_service::authorize_owner(&actor, id, conn)?;
+                    game_service::assign_owner(&actor, &mut updated_game, false)?;
+                    game_service::update(id, updated_game, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
 
-    match game_service::update(id, updated_game, pool) {
+    match result {
         Ok(game) => Ok(ResponseBody::ok("Game updated", game)),
         Err(err) => Err(err),
     }
@@ -149,12 +247,245 @@ pub async fn update(
 )]
 pub async fn destroy(
     State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_delete(
+                &actor,
+                AuditEntity::Game,
+                id,
+                |conn| game_service::find_by_id(id, conn),
+                |_, conn| game_service::delete(id, conn),
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    tag = "Game",
+    operation_id = "game_restore",
+    params(
+        ("id", Path, description = "Unique id of a deleted Game")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Game restored successfully", body = GameResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No deleted game found by id", body = ErrorResponse)
+    )
+)]
+pub async fn restore(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<Game>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let game = game_service::restore(id, conn)?;
+            audit_service::restored(&actor, AuditEntity::Game, id, &game, conn)?;
+            Ok(game)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(game) => Ok(ResponseBody::ok("Game restored", game)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/signing-secret",
+    tag = "Game",
+    operation_id = "game_generate_signing_secret",
+    params(
+        ("id", Path, description = "Unique id of a Game")
+    ),
+    responses(
+        (status = StatusCode::CREATED, description = "Signing secret generated, replacing the previous secret", body = SigningSecretResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse)
+    )
+)]
+pub async fn generate_signing_secret(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<SigningSecret>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            // The secret itself is never recorded, only whether the game has one.
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Game,
+                id,
+                |conn| signing_secret_state(id, conn),
+                |_, conn| {
+                    game_service::authorize_owner(&actor, id, conn)?;
+                    signature_service::generate_secret(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(secret) => Ok(ResponseBody::created("Signing secret generated", SigningSecret { secret })),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/signing-secret",
+    tag = "Game",
+    operation_id = "game_remove_signing_secret",
+    params(
+        ("id", Path, description = "Unique id of a Game")
+    ),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "Signing secret removed, submissions no longer need to be signed"),
+        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse)
+    )
+)]
+pub async fn remove_signing_secret(
+    State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Game,
+                id,
+                |conn| signing_secret_state(id, conn),
+                |_, conn| {
+                    game_service::authorize_owner(&actor, id, conn)?;
+                    signature_service::disable(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
 
-    match game_service::delete(id, pool) {
+    match result {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(err) => Err(err),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/export",
+    tag = "Game",
+    operation_id = "game_export",
+    params(
+        ("id", Path, description = "Unique id of a Game"),
+        ("include_secrets", Query, description = "Include the signing secret and the credentials of the players, defaults to false, only for admins")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Archive of the game, its levels, users and scores", body = GameArchive),
+        (status = StatusCode::FORBIDDEN, description = "Only admins and the owner of the game can export it", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse)
+    )
+)]
+pub async fn export(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ArchiveExportQuery>,
+) -> Result<Response, AppError> {
+    let Actor::User(user) = &actor else {
+        return Err(AppError::Forbidden("Only dashboard users can export games".to_string()));
+    };
+    if query.include_secrets && !user.has_any_role(&[Role::Admin]) {
+        return Err(AppError::Forbidden("Only admins can export the secrets of a game".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let archive = db::with_connection(pool, move |conn| {
+        game_service::authorize_owner(&actor, id, conn)?;
+        archive_service::export_game(id, query.include_secrets, conn)
+    })
+    .await?;
+    let file_name = format!(
+        "{}-backup-{}.json",
+        export_service::slugify(&archive.game.name),
+        Utc::now().format("%Y%m%d")
+    );
+
+    Ok((
+        [(CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name))],
+        Json(archive),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "Game",
+    operation_id = "game_import",
+    request_body = GameArchive,
+    params(
+        ("ids", Query, description = "Either 'new' (default) to give every row a new id, or 'preserve' to keep the ids of the archive"),
+        ("on_conflict", Query, description = "What to do when a game with the same name exists, one of 'fail' (default), 'rename' or 'replace'")
+    ),
+    responses(
+        (status = StatusCode::CREATED, description = "Game imported successfully", body = GameResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Archive references missing levels or users", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Only dashboard users can import games", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Game name or ids already in use", body = ErrorResponse),
+        (status = StatusCode::PAYLOAD_TOO_LARGE, description = "Archive is too large", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid or unsupported archive", body = ErrorResponse)
+    )
+)]
+pub async fn import(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Query(query): Query<ArchiveImportQuery>,
+    ValidatedJson(archive): ValidatedJson<GameArchive>,
+) -> Result<ResponseBody<Game>, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can import games".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let (game, replaced) = archive_service::import_game(archive, &query, conn)?;
+            if let Some(replaced) = replaced {
+                audit_service::deleted(&actor, AuditEntity::Game, replaced.id, &replaced, conn)?;
+            }
+            audit_service::created(&actor, AuditEntity::Game, game.id, &game, conn)?;
+            Ok(game)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(game) => Ok(ResponseBody::created("Game imported", game)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Fetches whether the game has a signing secret, as it is recorded in the audit log instead of the secret itself.
+fn signing_secret_state(id: Uuid, conn: &mut db::Connection) -> Result<serde_json::Value, AppError> {
+    game_service::find_by_id(id, conn).map(|game| json!({ "signing_secret": game.signing_secret.is_some() }))
+}
@@ -1,22 +1,37 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Json,
 };
 use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    models::level::{Level, LevelForm},
-    response::{ErrorResponse, ResponseBody},
-    service::level_service,
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        audit_log::{Actor, AuditEntity},
+        level::{Aggregation, Level, LevelForm, LevelSort, ScoreUnit, SortDirection},
+        pagination::{PageQuery, Pagination},
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{audit_service, game_service, level_service},
     SharedState,
 };
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(index, store, update, destroy),
-    components(schemas(Level, LevelForm, LevelResponseBody, LevelsResponseBody))
+    paths(index, store, update, destroy, restore),
+    components(schemas(
+        Level,
+        LevelForm,
+        LevelResponseBody,
+        LevelsResponseBody,
+        SortDirection,
+        ScoreUnit,
+        Aggregation,
+        LevelSort,
+        Pagination
+    ))
 )]
 pub struct LevelApi;
 
@@ -36,6 +51,7 @@ pub struct LevelsResponseBody {
     pub message: String,
     pub status: String,
     pub data: Vec<Level>,
+    pub pagination: Pagination,
 }
 
 #[utoipa::path(
@@ -45,20 +61,26 @@ pub struct LevelsResponseBody {
     operation_id = "level_games",
     params(
         ("gameId", Path, description = "Unique id of a Game"),
+        ("limit", Query, description = "Maximum number of items to return, defaults to 50 with a maximum of 500"),
+        ("cursor", Query, description = "Cursor of the next page, as returned in the pagination metadata of the previous page"),
+        ("sort", Query, description = "Field to sort on, one of 'name' or 'created_at' (default)"),
+        ("order", Query, description = "Sort order, either 'asc' (default) or 'desc'")
     ),
     responses(
         (status = StatusCode::OK, description = "Levels fetched by game successfully", body = LevelsResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid cursor", body = ErrorResponse),
         (status = StatusCode::NOT_FOUND, description = "No Game found by game id", body = ErrorResponse)
     )
 )]
 pub async fn index(
     State(app_state): State<SharedState>,
     Path(game_id): Path<Uuid>,
-) -> Result<ResponseBody<Vec<Level>>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    Query(page): Query<PageQuery<LevelSort>>,
+) -> Result<ResponseBody<Vec<Level>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
 
-    match level_service::find_by_game(game_id, pool) {
-        Ok(levels) => Ok(ResponseBody::ok("Levels fetched", levels)),
+    match db::with_connection(pool, move |conn| level_service::find_by_game(game_id, &page, conn)).await {
+        Ok(levels) => Ok(ResponseBody::page("Levels fetched", levels)),
         Err(err) => Err(err),
     }
 }
@@ -71,16 +93,27 @@ pub async fn index(
     request_body = LevelForm,
     responses(
         (status = StatusCode::CREATED, description = "Level created successfully", body = LevelsResponseBody),
-        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse)
+        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or validation rules", body = ErrorResponse)
     )
 )]
 pub async fn store(
     State(app_state): State<SharedState>,
-    Json(new_level): Json<LevelForm>,
-) -> Result<ResponseBody<Level>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    actor: Actor,
+    ValidatedJson(new_level): ValidatedJson<LevelForm>,
+) -> Result<ResponseBody<Level>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            game_service::authorize_owner(&actor, new_level.game_id, conn)?;
+            let level = level_service::insert(new_level, conn)?;
+            audit_service::created(&actor, AuditEntity::Level, level.id, &level, conn)?;
+            Ok(level)
+        })
+    })
+    .await;
 
-    match level_service::insert(new_level, pool) {
+    match result {
         Ok(level) => Ok(ResponseBody::created("Level created", level)),
         Err(error) => Err(error),
     }
@@ -98,17 +131,36 @@ pub async fn store(
     responses(
         (status = StatusCode::OK, description = "Level updated successfully", body = LevelsResponseBody),
         (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
-        (status = StatusCode::NOT_FOUND, description = "No level found by id", body = ErrorResponse)
+        (status = StatusCode::NOT_FOUND, description = "No level found by id", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or validation rules", body = ErrorResponse)
     )
 )]
 pub async fn update(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-    Json(updated_level): Json<LevelForm>,
-) -> Result<ResponseBody<Level>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    ValidatedJson(updated_level): ValidatedJson<LevelForm>,
+) -> Result<ResponseBody<Level>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Level,
+                id,
+                |conn| level_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.game_id, conn)?;
+                    game_service::authorize_owner(&actor, updated_level.game_id, conn)?;
+                    level_service::update(id, updated_level, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
 
-    match level_service::update(id, updated_level, pool) {
+    match result {
         Ok(level) => Ok(ResponseBody::ok("Level updated", level)),
         Err(error) => Err(error),
     }
@@ -129,12 +181,65 @@ pub async fn update(
 )]
 pub async fn destroy(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_delete(
+                &actor,
+                AuditEntity::Level,
+                id,
+                |conn| level_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.game_id, conn)?;
+                    level_service::delete(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
 
-    match level_service::delete(id, pool) {
+    match result {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(err) => Err(err),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    tag = "Level",
+    operation_id = "level_restore",
+    params(
+        ("id", Path, description = "Unique id of a deleted Level")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Level restored successfully", body = LevelResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No deleted level found by id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "The game of the level is deleted", body = ErrorResponse)
+    )
+)]
+pub async fn restore(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<Level>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let level = level_service::restore(id, conn)?;
+            game_service::authorize_owner(&actor, level.game_id, conn)?;
+            audit_service::restored(&actor, AuditEntity::Level, id, &level, conn)?;
+            Ok(level)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(level) => Ok(ResponseBody::ok("Level restored", level)),
+        Err(err) => Err(err),
+    }
+}
@@ -1,17 +1,109 @@
-use axum::{routing::{get, post, put}, Router};
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put, MethodRouter},
+    Router,
+};
 
-use crate::SharedState;
+use crate::{
+    middleware::auth_middleware::{self, RoutePolicy},
+    models::auth::{Role, ALL_ROLES},
+    service::{archive_service, import_service},
+    SharedState,
+};
 
+pub mod api_key;
+pub mod audit;
 pub mod game;
 pub mod level;
+pub mod player;
 pub mod score;
+pub mod season;
 pub mod stats;
+pub mod tournament;
 pub mod user;
 
+/// Only admins can delete games, game owners can create games and update the games they own.
+const GAME_POLICY: RoutePolicy = RoutePolicy {
+    read: ALL_ROLES,
+    create: &[Role::Admin, Role::GameOwner],
+    update: &[Role::Admin, Role::GameOwner],
+    delete: &[Role::Admin],
+};
+
+/// Levels are managed by admins and the owners of their game.
+const MANAGEMENT_POLICY: RoutePolicy = RoutePolicy {
+    read: ALL_ROLES,
+    create: &[Role::Admin, Role::GameOwner],
+    update: &[Role::Admin, Role::GameOwner],
+    delete: &[Role::Admin, Role::GameOwner],
+};
+
+/// API keys are secrets of a game, so only admins and game owners can see them.
+const API_KEY_POLICY: RoutePolicy = RoutePolicy {
+    read: &[Role::Admin, Role::GameOwner],
+    ..MANAGEMENT_POLICY
+};
+
+/// The audit log can only be read, and only by admins.
+const AUDIT_POLICY: RoutePolicy = RoutePolicy {
+    read: &[Role::Admin],
+    create: &[],
+    update: &[],
+    delete: &[],
+};
+
+/// Scores and users can be moderated by moderators as well, on every game.
+const MODERATION_POLICY: RoutePolicy = RoutePolicy {
+    read: ALL_ROLES,
+    create: &[Role::Admin, Role::GameOwner],
+    update: &[Role::Admin, Role::GameOwner, Role::Moderator],
+    delete: &[Role::Admin, Role::GameOwner, Role::Moderator],
+};
+
+/// Backups contain every player of a game, so only admins and game owners can export them. Only admins can import
+/// them, because an import can replace an existing game.
+const BACKUP_POLICY: RoutePolicy = RoutePolicy {
+    read: &[Role::Admin, Role::GameOwner],
+    create: &[Role::Admin],
+    update: &[],
+    delete: &[],
+};
+
+/// Deleted data can be restored by the roles that can delete it. Restoring is a `POST` request, so the policy of
+/// the restore routes is checked against the delete roles of the given policy.
+const fn restore_policy(policy: RoutePolicy) -> RoutePolicy {
+    RoutePolicy {
+        create: policy.delete,
+        ..policy
+    }
+}
+
+pub fn api_key_routes() -> Router<SharedState> {
+    Router::new()
+        .route("/game/{gameId}", get(api_key::index).post(api_key::store))
+        .route("/{keyId}", delete(api_key::destroy))
+        .route("/{keyId}/rotate", post(api_key::rotate))
+        .route_layer(middleware::from_fn_with_state(API_KEY_POLICY, auth_middleware::authorize))
+}
+
+pub fn audit_routes() -> Router<SharedState> {
+    Router::new()
+        .route("/", get(audit::index))
+        .route_layer(middleware::from_fn_with_state(AUDIT_POLICY, auth_middleware::authorize))
+}
+
 pub fn game_routes() -> Router<SharedState> {
     Router::new()
         .route("/", get(game::index).post(game::store))
         .route("/{gameId}", get(game::show).put(game::update).delete(game::destroy))
+        .route(
+            "/{gameId}/signing-secret",
+            post(game::generate_signing_secret).delete(game::remove_signing_secret),
+        )
+        .route_layer(middleware::from_fn_with_state(GAME_POLICY, auth_middleware::authorize))
+        .merge(restore_routes("/{gameId}/restore", post(game::restore), GAME_POLICY))
+        .merge(backup_routes())
 }
 
 pub fn level_routes() -> Router<SharedState> {
@@ -19,21 +111,72 @@ pub fn level_routes() -> Router<SharedState> {
         .route("/", post(level::store))
         .route("/game/{gameId}", get(level::index))
         .route("/{levelId}", put(level::update).delete(level::destroy))
+        .route_layer(middleware::from_fn_with_state(MANAGEMENT_POLICY, auth_middleware::authorize))
+        .merge(restore_routes("/{levelId}/restore", post(level::restore), MANAGEMENT_POLICY))
+}
+
+/// Players are signed in by the game clients, admins and game owners can register players from the dashboard.
+pub fn player_routes() -> Router<SharedState> {
+    Router::new()
+        .route("/register", post(player::register))
+        .route("/guest", post(player::guest))
+        .route("/login", post(player::login))
+        .route("/refresh", post(player::refresh))
+        .route("/logout", post(player::logout))
+        .route("/upgrade", post(player::upgrade))
+        .route("/merge", post(player::merge))
+        .route_layer(middleware::from_fn_with_state(MODERATION_POLICY, auth_middleware::authorize))
 }
 
 pub fn score_routes() -> Router<SharedState> {
     Router::new()
         .route("/", post(score::store))
         .route("/game/{gameId}", get(score::index))
+        .route(
+            "/game/{gameId}/import",
+            post(score::import).layer(DefaultBodyLimit::max(import_service::MAX_IMPORT_SIZE)),
+        )
         .route("/{scoreId}", get(score::show).put(score::update).delete(score::destroy))
         .route("/level/{levelId}", get(score::level_scores))
+        .route("/level/{levelId}/leaderboard", get(score::leaderboard))
         .route("/user/{userId}", get(score::user_scores))
+        .route("/game/{gameId}/export", get(score::game_export))
+        .route("/level/{levelId}/export", get(score::level_export))
+        .route("/user/{userId}/export", get(score::user_export))
+        .route_layer(middleware::from_fn_with_state(MODERATION_POLICY, auth_middleware::authorize))
+        .merge(restore_routes("/{scoreId}/restore", post(score::restore), MODERATION_POLICY))
+}
+
+/// Seasons are managed by admins and game owners, game clients can fetch the seasons and their standings.
+pub fn season_routes() -> Router<SharedState> {
+    Router::new()
+        .route("/", post(season::store))
+        .route("/game/{gameId}", get(season::index))
+        .route("/{seasonId}", put(season::update).delete(season::destroy))
+        .route("/{seasonId}/standings", get(season::standings))
+        .route_layer(middleware::from_fn_with_state(MANAGEMENT_POLICY, auth_middleware::authorize))
 }
 
 pub fn stats_routes() -> Router<SharedState> {
     Router::new()
         .route("/all", get(stats::all))
         .route("/game/{gameId}", get(stats::game_stats))
+        .route_layer(middleware::from_fn_with_state(GAME_POLICY, auth_middleware::authorize))
+}
+
+/// Tournaments are managed by admins and game owners, game clients can fetch them and let players join.
+pub fn tournament_routes() -> Router<SharedState> {
+    Router::new()
+        .route("/", post(tournament::store))
+        .route("/game/{gameId}", get(tournament::index))
+        .route(
+            "/{tournamentId}",
+            get(tournament::show).put(tournament::update).delete(tournament::destroy),
+        )
+        .route("/{tournamentId}/join", post(tournament::join))
+        .route("/{tournamentId}/entrants", get(tournament::entrants))
+        .route("/{tournamentId}/standings", get(tournament::standings))
+        .route_layer(middleware::from_fn_with_state(MANAGEMENT_POLICY, auth_middleware::authorize))
 }
 
 pub fn user_routes() -> Router<SharedState> {
@@ -41,6 +184,25 @@ pub fn user_routes() -> Router<SharedState> {
         .route("/", post(user::store))
         .route("/game/{gameId}", get(user::index))
         .route("/{userId}", put(user::update).delete(user::destroy))
+        .route_layer(middleware::from_fn_with_state(MODERATION_POLICY, auth_middleware::authorize))
+        .merge(restore_routes("/{userId}/restore", post(user::restore), MODERATION_POLICY))
+}
+
+fn backup_routes() -> Router<SharedState> {
+    Router::new()
+        .route(
+            "/import",
+            post(game::import).layer(DefaultBodyLimit::max(archive_service::MAX_ARCHIVE_SIZE)),
+        )
+        .route("/{gameId}/export", get(game::export))
+        .route_layer(middleware::from_fn_with_state(BACKUP_POLICY, auth_middleware::authorize))
+}
+
+/// Creates the route that restores deleted data, authorized with the [`restore_policy`] of the given policy.
+fn restore_routes(path: &str, handler: MethodRouter<SharedState>, policy: RoutePolicy) -> Router<SharedState> {
+    Router::new()
+        .route(path, handler)
+        .route_layer(middleware::from_fn_with_state(restore_policy(policy), auth_middleware::authorize))
 }
 
 pub async fn healthcheck() -> &'static str {
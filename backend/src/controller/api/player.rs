@@ -0,0 +1,266 @@
+use axum::{extract::State, http::StatusCode, Extension};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        api_key::GameClient,
+        audit_log::Actor,
+        player::{
+            GuestForm, LoginForm, MergeForm, MergeResult, Player, PlayerSession, RefreshForm, RegisterForm,
+            UpgradeForm,
+        },
+        user::User,
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{game_service, player_service},
+    SharedState,
+};
+
+use super::user::UsersResponseBody;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(register, guest, login, refresh, logout, upgrade, merge),
+    components(schemas(
+        RegisterForm, GuestForm, LoginForm, RefreshForm, UpgradeForm, MergeForm, PlayerSession, MergeResult,
+        PlayerSessionResponseBody, MergeResponseBody
+    ))
+)]
+pub struct PlayerApi;
+
+/// The structure of the response body when a player is signed in. This struct is primarily used for the OpenAPI
+/// docs.
+#[derive(ToSchema)]
+pub struct PlayerSessionResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: PlayerSession,
+}
+
+/// The structure of the response body when a guest is merged into a player. This struct is primarily used for the
+/// OpenAPI docs.
+#[derive(ToSchema)]
+pub struct MergeResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: MergeResult,
+}
+
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "Player",
+    operation_id = "player_register",
+    request_body = RegisterForm,
+    responses(
+        (status = StatusCode::CREATED, description = "Player registered and signed in", body = PlayerSessionResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the game", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Name or device already registered", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or name", body = ErrorResponse)
+    )
+)]
+pub async fn register(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    client: Option<Extension<GameClient>>,
+    ValidatedJson(form): ValidatedJson<RegisterForm>,
+) -> Result<ResponseBody<PlayerSession>, AppError> {
+    let (pool, issuer, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.player_tokens.clone(), state.name_filter.clone())
+    };
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        game_service::authorize_owner(&actor, form.game_id, conn)?;
+        player_service::register(form, client.as_ref(), &issuer, &names, conn)
+    })
+    .await;
+
+    match result {
+        Ok(session) => Ok(ResponseBody::created("Player registered", session)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/guest",
+    tag = "Player",
+    operation_id = "player_guest",
+    request_body = GuestForm,
+    responses(
+        (status = StatusCode::CREATED, description = "Guest created and signed in, the device token is only returned once", body = PlayerSessionResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the game", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or unknown game", body = ErrorResponse)
+    )
+)]
+pub async fn guest(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    ValidatedJson(form): ValidatedJson<GuestForm>,
+) -> Result<ResponseBody<PlayerSession>, AppError> {
+    let (pool, issuer) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.player_tokens.clone())
+    };
+    let client = client.map(|Extension(client)| client);
+
+    let result = db::with_connection(pool, move |conn| {
+        player_service::create_guest(form, client.as_ref(), &issuer, conn)
+    })
+    .await;
+
+    match result {
+        Ok(session) => Ok(ResponseBody::created("Guest created", session)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "Player",
+    operation_id = "player_login",
+    request_body = LoginForm,
+    responses(
+        (status = StatusCode::OK, description = "Player signed in", body = PlayerSessionResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Missing credentials", body = ErrorResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Invalid credentials", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the game", body = ErrorResponse)
+    )
+)]
+pub async fn login(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    ValidatedJson(form): ValidatedJson<LoginForm>,
+) -> Result<ResponseBody<PlayerSession>, AppError> {
+    let (pool, issuer) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.player_tokens.clone())
+    };
+    let client = client.map(|Extension(client)| client);
+
+    match db::with_connection(pool, move |conn| player_service::login(form, client.as_ref(), &issuer, conn)).await {
+        Ok(session) => Ok(ResponseBody::ok("Player signed in", session)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/refresh",
+    tag = "Player",
+    operation_id = "player_refresh",
+    request_body = RefreshForm,
+    responses(
+        (status = StatusCode::OK, description = "Tokens refreshed, the used refresh token is revoked", body = PlayerSessionResponseBody),
+        (status = StatusCode::UNAUTHORIZED, description = "Invalid, expired or used refresh token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the player", body = ErrorResponse)
+    )
+)]
+pub async fn refresh(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    ValidatedJson(form): ValidatedJson<RefreshForm>,
+) -> Result<ResponseBody<PlayerSession>, AppError> {
+    let (pool, issuer) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.player_tokens.clone())
+    };
+    let client = client.map(|Extension(client)| client);
+
+    match db::with_connection(pool, move |conn| player_service::refresh(form, client.as_ref(), &issuer, conn)).await {
+        Ok(session) => Ok(ResponseBody::ok("Player tokens refreshed", session)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/logout",
+    tag = "Player",
+    operation_id = "player_logout",
+    request_body = RefreshForm,
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "Refresh token revoked"),
+        (status = StatusCode::UNAUTHORIZED, description = "Invalid, expired or used refresh token", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the player", body = ErrorResponse)
+    )
+)]
+pub async fn logout(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    ValidatedJson(form): ValidatedJson<RefreshForm>,
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+
+    match db::with_connection(pool, move |conn| player_service::logout(form, client.as_ref(), conn)).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/upgrade",
+    tag = "Player",
+    operation_id = "player_upgrade",
+    request_body = UpgradeForm,
+    responses(
+        (status = StatusCode::OK, description = "Guest upgraded to a named player", body = UsersResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "The signed in player is not a guest", body = ErrorResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing or invalid player token", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Name already registered", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or name", body = ErrorResponse)
+    )
+)]
+pub async fn upgrade(
+    State(app_state): State<SharedState>,
+    player: Option<Extension<Player>>,
+    ValidatedJson(form): ValidatedJson<UpgradeForm>,
+) -> Result<ResponseBody<User>, AppError> {
+    let (pool, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.name_filter.clone())
+    };
+    let Some(Extension(player)) = player else {
+        return Err(AppError::Unauthorized("A player token is required".to_string()));
+    };
+
+    match db::with_connection(pool, move |conn| player_service::upgrade(form, &player, &names, conn)).await {
+        Ok(user) => Ok(ResponseBody::ok("Guest upgraded", user)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/merge",
+    tag = "Player",
+    operation_id = "player_merge",
+    request_body = MergeForm,
+    responses(
+        (status = StatusCode::OK, description = "Scores of the guest moved to the signed in player", body = MergeResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "The signed in player is a guest", body = ErrorResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Missing or invalid player token", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No guest found with the device token", body = ErrorResponse)
+    )
+)]
+pub async fn merge(
+    State(app_state): State<SharedState>,
+    player: Option<Extension<Player>>,
+    ValidatedJson(form): ValidatedJson<MergeForm>,
+) -> Result<ResponseBody<MergeResult>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let Some(Extension(player)) = player else {
+        return Err(AppError::Unauthorized("A player token is required".to_string()));
+    };
+
+    match db::with_connection(pool, move |conn| player_service::merge(form, &player, conn)).await {
+        Ok(result) => Ok(ResponseBody::ok("Guest merged", result)),
+        Err(err) => Err(err),
+    }
+}
@@ -1,28 +1,98 @@
 use std::collections::HashMap;
 
 use axum::{
+    body::{Body, Bytes},
     extract::{Path, Query, State},
-    http::StatusCode,
-    Json,
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Extension,
 };
 use serde::Deserialize;
 use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    models::score::{ScoreDto, ScoreForm},
-    response::{ErrorResponse, ResponseBody},
-    service::score_service,
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        api_key::GameClient,
+        audit_log::{Actor, AuditEntity},
+        export::{ExportFormat, ExportQuery, ExportScope},
+        import::{ImportQuery, ImportReport, ImportRowResult, ImportStatus, ScoreImportRow},
+        leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardPeriod, LeaderboardQuery, PeriodWindow, RankingMode},
+        pagination::{PageQuery, Pagination},
+        player::Player,
+        score::{
+            ScoreDto, ScoreForm, ScoreSort, ScoreSubmission, ScoreSubmissionForm, SubmissionMode,
+            SubmissionQuery, SubmissionSignature,
+        },
+    },
+    response::{AppError, ErrorResponse, FieldError, ResponseBody},
+    service::{
+        api_key_service, audit_service, export_service,
+        import_service::{self, ImportFormat},
+        game_service, level_service, score_service,
+    },
     SharedState,
 };
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(index, show, level_scores, user_scores, store, update, destroy),
-    components(schemas(ScoreDto, ScoreForm, ScoreResponseBody, ScoresResponseBody))
+    paths(
+        index,
+        show,
+        level_scores,
+        leaderboard,
+        user_scores,
+        game_export,
+        level_export,
+        user_export,
+        store,
+        import,
+        update,
+        destroy,
+        restore
+    ),
+    components(schemas(
+        ScoreDto,
+        ScoreForm,
+        ScoreResponseBody,
+        ScoreSubmission,
+        ScoreSubmissionForm,
+        ScoreSubmissionResponseBody,
+        SubmissionSignature,
+        SubmissionMode,
+        ScoresResponseBody,
+        Leaderboard,
+        LeaderboardEntry,
+        LeaderboardPeriod,
+        LeaderboardResponseBody,
+        PeriodWindow,
+        RankingMode,
+        ScoreSort,
+        ExportFormat,
+        ScoreImportRow,
+        ImportReport,
+        ImportReportResponseBody,
+        ImportRowResult,
+        ImportStatus,
+        FieldError,
+        Pagination
+    ))
 )]
 pub struct ScoreApi;
 
+/// The structure of the response body of a score import. This struct is primarily used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct ImportReportResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: ImportReport,
+}
+
 /// The structure of the response body where there is a single score returned. This struct is primarily used for
 /// the OpenAPI docs.
 #[derive(ToSchema)]
@@ -32,6 +102,15 @@ pub struct ScoreResponseBody {
     pub data: ScoreDto,
 }
 
+/// The structure of the response body where a submitted score is returned. This struct is primarily used for
+/// the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct ScoreSubmissionResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: ScoreSubmission,
+}
+
 /// The structure of the response body where there are multiple scores returned. This struct is primarily used for
 /// the OpenAPI docs.
 #[derive(ToSchema)]
@@ -39,6 +118,16 @@ pub struct ScoresResponseBody {
     pub message: String,
     pub status: String,
     pub data: Vec<ScoreDto>,
+    pub pagination: Option<Pagination>,
+}
+
+/// The structure of the response body where a leaderboard is returned. This struct is primarily used for the
+/// OpenAPI docs.
+#[derive(ToSchema)]
+pub struct LeaderboardResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Leaderboard,
 }
 
 /// The structure of the query parameters that can be used in request related to fetching scores.
@@ -53,21 +142,30 @@ pub struct QueryParams {
     tag = "Score",
     operation_id = "score_index",
     params(
-        ("levelId", Path, description = "Unique id of the related Game"),
-        ("hidden", Query, description = "If hidden scores should also be fetched")
+        ("gameId", Path, description = "Unique id of the related Game"),
+        ("limit", Query, description = "Maximum number of items to return, defaults to 50 with a maximum of 500"),
+        ("cursor", Query, description = "Cursor of the next page, as returned in the pagination metadata of the previous page"),
+        ("sort", Query, description = "Field to sort on, one of 'score' or 'created_at' (default)"),
+        ("order", Query, description = "Sort order, either 'asc' (default) or 'desc'")
     ),
     responses(
-        (status = StatusCode::OK, description = "Scores fetched successfully", body = ScoresResponseBody)
+        (status = StatusCode::OK, description = "Scores fetched successfully", body = ScoresResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid cursor", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No Game found by game id", body = ErrorResponse)
     )
 )]
 pub async fn index(
     State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
     Path(game_id): Path<Uuid>,
-) -> Result<ResponseBody<Vec<ScoreDto>>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    Query(page): Query<PageQuery<ScoreSort>>,
+) -> Result<ResponseBody<Vec<ScoreDto>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    api_key_service::authorize_game(client.as_ref(), game_id)?;
 
-    match score_service::find_all(game_id, pool) {
-        Ok(scores) => Ok(ResponseBody::ok("Scores fetched", scores)),
+    match db::with_connection(pool, move |conn| score_service::find_all(game_id, &page, conn)).await {
+        Ok(scores) => Ok(ResponseBody::page("Scores fetched", scores)),
         Err(err) => Err(err),
     }
 }
@@ -87,11 +185,18 @@ pub async fn index(
 )]
 pub async fn show(
     State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
     Path(id): Path<Uuid>,
-) -> Result<ResponseBody<ScoreDto>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+) -> Result<ResponseBody<ScoreDto>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        api_key_service::authorize_score(client.as_ref(), id, conn)?;
+        score_service::find_by_id(id, conn)
+    })
+    .await;
 
-    match score_service::find_by_id(id, pool) {
+    match result {
         Ok(score) => Ok(ResponseBody::ok("Score fetched", score)),
         Err(err) => Err(err),
     }
@@ -104,7 +209,7 @@ pub async fn show(
     operation_id = "score_level_score",
     params(
         ("levelId", Path, description = "Unique id of a Level"),
-        ("hidden", Query, description = "If hidden scores should also be fetched")
+        ("hidden", Query, description = "If hidden scores should also be fetched, ignored for game clients")
     ),
     responses(
         (status = StatusCode::OK, description = "Score fetched by level successfully", body = ScoresResponseBody),
@@ -113,22 +218,76 @@ pub async fn show(
 )]
 pub async fn level_scores(
     State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
     Path(level_id): Path<Uuid>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<ResponseBody<Vec<ScoreDto>>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
-    let show_hidden = params
-        .get("hidden")
-        .unwrap_or(&"false".to_string())
-        .to_lowercase()
-        .eq("true");
-
-    match score_service::find_by_level(level_id, show_hidden, pool) {
+) -> Result<ResponseBody<Vec<ScoreDto>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    // Hidden scores are moderated, so only dashboard users can fetch them.
+    let show_hidden = client.is_none()
+        && params
+            .get("hidden")
+            .unwrap_or(&"false".to_string())
+            .to_lowercase()
+            .eq("true");
+    let result = db::with_connection(pool, move |conn| {
+        api_key_service::authorize_level(client.as_ref(), level_id, conn)?;
+        score_service::find_by_level(level_id, show_hidden, conn)
+    })
+    .await;
+
+    match result {
         Ok(scores) => Ok(ResponseBody::ok("Scores fetched", scores)),
         Err(err) => Err(err),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/level/{levelId}/leaderboard",
+    tag = "Score",
+    operation_id = "score_level_leaderboard",
+    params(
+        ("levelId", Path, description = "Unique id of a Level"),
+        ("hidden", Query, description = "If hidden scores should also be ranked, ignored for game clients"),
+        ("ranking", Query, description = "How tied scores are ranked, either 'competition' (default) or 'dense'"),
+        ("limit", Query, description = "Maximum number of entries to return, defaults to 25 with a maximum of 100"),
+        ("offset", Query, description = "Number of entries to skip, ignored when 'user_id' is given"),
+        ("user_id", Query, description = "Unique id of a User to centre the returned entries around"),
+        ("period", Query, description = "Period of which scores are ranked, one of 'all_time' (default), 'season', 'daily', 'weekly' or 'monthly'"),
+        ("period_key", Query, description = "Key of a past period like '2026-10-18', '2026-W41' or '2026-10', defaults to the current period"),
+        ("timezone", Query, description = "IANA timezone in which periods start at midnight, defaults to 'UTC'")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Leaderboard fetched successfully", body = LeaderboardResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid period key or timezone", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No level found by level id or user has no score", body = ErrorResponse)
+    )
+)]
+pub async fn leaderboard(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(level_id): Path<Uuid>,
+    Query(mut query): Query<LeaderboardQuery>,
+) -> Result<ResponseBody<Leaderboard>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    if client.is_some() {
+        query.hidden = false;
+    }
+    let result = db::with_connection(pool, move |conn| {
+        api_key_service::authorize_level(client.as_ref(), level_id, conn)?;
+        score_service::leaderboard(level_id, query, conn)
+    })
+    .await;
+
+    match result {
+        Ok(leaderboard) => Ok(ResponseBody::ok("Leaderboard fetched", leaderboard)),
+        Err(err) => Err(err),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/user/{userId}",
@@ -136,7 +295,7 @@ pub async fn level_scores(
     operation_id = "score_user_score",
     params(
         ("userId", Path, description = "Unique id of a User"),
-        ("hidden", Query, description = "If hidden scores should also be fetched")
+        ("hidden", Query, description = "If hidden scores should also be fetched, ignored for game clients")
     ),
     responses(
         (status = StatusCode::OK, description = "Score fetched by user successfully", body = ScoresResponseBody),
@@ -145,41 +304,289 @@ pub async fn level_scores(
 )]
 pub async fn user_scores(
     State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
     Path(user_id): Path<Uuid>,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<ResponseBody<Vec<ScoreDto>>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
-    let show_hidden = params
-        .get("hidden")
-        .unwrap_or(&"false".to_string())
-        .to_lowercase()
-        .eq("true");
-
-    match score_service::find_by_user(user_id, show_hidden, pool) {
+) -> Result<ResponseBody<Vec<ScoreDto>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    // Hidden scores are moderated, so only dashboard users can fetch them.
+    let show_hidden = client.is_none()
+        && params
+            .get("hidden")
+            .unwrap_or(&"false".to_string())
+            .to_lowercase()
+            .eq("true");
+    let result = db::with_connection(pool, move |conn| {
+        api_key_service::authorize_user(client.as_ref(), user_id, conn)?;
+        score_service::find_by_user(user_id, show_hidden, conn)
+    })
+    .await;
+
+    match result {
         Ok(scores) => Ok(ResponseBody::ok("Scores fetched", scores)),
         Err(err) => Err(err),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/game/{gameId}/export",
+    tag = "Score",
+    operation_id = "score_game_export",
+    params(
+        ("gameId", Path, description = "Unique id of a Game"),
+        ("format", Query, description = "Either 'csv' (default), 'ndjson' or 'excel'"),
+        ("from", Query, description = "Only scores submitted at or after this moment"),
+        ("to", Query, description = "Only scores submitted before this moment"),
+        ("hidden", Query, description = "If hidden scores should also be exported")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Scores of the game streamed as a file", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson")
+        )),
+        (status = StatusCode::FORBIDDEN, description = "Not allowed for game clients", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse)
+    )
+)]
+pub async fn game_export(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    export(app_state, actor, ExportScope::Game(id), query).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/level/{levelId}/export",
+    tag = "Score",
+    operation_id = "score_level_export",
+    params(
+        ("levelId", Path, description = "Unique id of a Level"),
+        ("format", Query, description = "Either 'csv' (default), 'ndjson' or 'excel'"),
+        ("from", Query, description = "Only scores submitted at or after this moment"),
+        ("to", Query, description = "Only scores submitted before this moment"),
+        ("hidden", Query, description = "If hidden scores should also be exported")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Scores of the level streamed as a file", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson")
+        )),
+        (status = StatusCode::FORBIDDEN, description = "Not allowed for game clients", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No level found by id", body = ErrorResponse)
+    )
+)]
+pub async fn level_export(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    export(app_state, actor, ExportScope::Level(id), query).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{userId}/export",
+    tag = "Score",
+    operation_id = "score_user_export",
+    params(
+        ("userId", Path, description = "Unique id of a User"),
+        ("format", Query, description = "Either 'csv' (default), 'ndjson' or 'excel'"),
+        ("from", Query, description = "Only scores submitted at or after this moment"),
+        ("to", Query, description = "Only scores submitted before this moment"),
+        ("hidden", Query, description = "If hidden scores should also be exported")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Scores of the user streamed as a file", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson")
+        )),
+        (status = StatusCode::FORBIDDEN, description = "Not allowed for game clients", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No user found by id", body = ErrorResponse)
+    )
+)]
+pub async fn user_export(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    export(app_state, actor, ExportScope::User(id), query).await
+}
+
+/// Streams the scores of the scope as a file. The file name is sent in the `Content-Disposition` header, so
+/// browsers download the export instead of showing it.
+async fn export(
+    app_state: SharedState,
+    actor: Actor,
+    scope: ExportScope,
+    query: ExportQuery,
+) -> Result<Response, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can export scores".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let format = query.format;
+    let file_name =
+        db::with_connection(pool.clone(), move |conn| export_service::file_name(scope, format, conn)).await?;
+    let stream = export_service::stream_scores(pool, scope, query);
+
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
 #[utoipa::path(
     post,
     path = "",
     tag = "Score",
     operation_id = "score_store",
-    request_body = ScoreForm,
+    request_body = ScoreSubmissionForm,
+    params(
+        ("mode", Query, description = "Either 'insert' (default) to store every submission, or 'personal_best' to only keep the best score of the user on the level")
+    ),
     responses(
-        (status = StatusCode::CREATED, description = "Score created successfully", body = ScoreResponseBody),
-        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse)
+        (status = StatusCode::CREATED, description = "Score created successfully", body = ScoreSubmissionResponseBody),
+        (status = StatusCode::OK, description = "Score is not a new personal best, the stored best is returned", body = ScoreSubmissionResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid input or missing signature", body = ErrorResponse),
+        (status = StatusCode::UNAUTHORIZED, description = "Invalid or expired signature, or nonce already used", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the level or user, or the user is a registered player and the request has no matching player token", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or username, or score outside the bounds of the level", body = ErrorResponse),
+        (status = StatusCode::TOO_MANY_REQUESTS, description = "Too many scores submitted on the level", body = ErrorResponse)
     )
 )]
 pub async fn store(
     State(app_state): State<SharedState>,
-    Json(new_score): Json<ScoreForm>,
-) -> Result<ResponseBody<ScoreDto>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    actor: Actor,
+    client: Option<Extension<GameClient>>,
+    player: Option<Extension<Player>>,
+    Query(query): Query<SubmissionQuery>,
+    ValidatedJson(submission): ValidatedJson<ScoreSubmissionForm>,
+) -> Result<ResponseBody<ScoreSubmission>, AppError> {
+    let (pool, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.name_filter.clone())
+    };
+    let client = client.map(|Extension(client)| client);
+    let player = player.map(|Extension(player)| player);
+    let result = db::with_connection(pool, move |conn| {
+        let mut new_score = submission.score;
+        let signature = submission.signature.as_ref();
+        api_key_service::authorize_level(client.as_ref(), new_score.level_id, conn)?;
+        if matches!(actor, Actor::User(_)) {
+            let level = level_service::find_by_id(new_score.level_id, conn)?;
+            game_service::authorize_moderator(&actor, level.game_id, conn)?;
+        }
+        if client.is_some() {
+            new_score.user_id = score_service::bind_player(new_score.user_id, player.as_ref(), conn)?;
+        }
+        if let Some(user_id) = new_score.user_id {
+            api_key_service::authorize_user(client.as_ref(), user_id, conn)?;
+        }
+
+        db::transaction(conn, |conn| {
+            let submission = match query.mode {
+                SubmissionMode::Insert => score_service::insert(new_score, signature, client.as_ref(), &names, conn)
+                    .map(|score| ScoreSubmission {
+                        score,
+                        personal_best: None,
+                    })?,
+                SubmissionMode::PersonalBest => {
+                    score_service::upsert_best(new_score, signature, client.as_ref(), &names, conn)?
+                }
+            };
+            if submission.personal_best != Some(false) {
+                let score = &submission.score;
+                audit_service::created(&actor, AuditEntity::Score, score.id, score, conn)?;
+            }
+
+            Ok(submission)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(submission) if submission.personal_best == Some(false) => {
+            Ok(ResponseBody::ok("Score is not a new personal best", submission))
+        }
+        Ok(submission) if submission.personal_best == Some(true) => {
+            Ok(ResponseBody::created("New personal best saved", submission))
+        }
+        Ok(submission) => Ok(ResponseBody::created("Score saved", submission)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/game/{gameId}/import",
+    tag = "Score",
+    operation_id = "score_import",
+    request_body(
+        description = "A CSV file with a header row or a JSON array, every row references its level and optional user by id or name",
+        content(
+            (Vec<ScoreImportRow> = "application/json"),
+            (String = "text/csv")
+        )
+    ),
+    params(
+        ("gameId", Path, description = "Unique id of a Game"),
+        ("dry_run", Query, description = "Only validate the rows and return the report, defaults to false")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Scores imported, with the result of every row", body = ImportReportResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Unsupported content type, malformed body or too many rows", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Not allowed for game clients", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No game found by id", body = ErrorResponse)
+    )
+)]
+pub async fn import(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<ImportQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<ResponseBody<ImportReport>, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can import scores".to_string()));
+    }
+
+    let content_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    let format = ImportFormat::from_content_type(content_type)?;
+    let (pool, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.name_filter.clone())
+    };
+    let result = db::with_connection(pool, move |conn| {
+        game_service::authorize_moderator(&actor, game_id, conn)?;
+        let rows = import_service::parse_rows(format, &body)?;
+
+        db::transaction(conn, |conn| {
+            let (report, scores) = import_service::import_scores(game_id, rows, query.dry_run, &names, conn)?;
+            for score in &scores {
+                audit_service::created(&actor, AuditEntity::Score, score.id, score, conn)?;
+            }
+
+            Ok(report)
+        })
+    })
+    .await;
 
-    match score_service::insert(new_score, pool) {
-        Ok(scores) => Ok(ResponseBody::created("Score saved", scores)),
+    match result {
+        Ok(report) if query.dry_run => Ok(ResponseBody::ok("Scores validated", report)),
+        Ok(report) => Ok(ResponseBody::ok("Scores imported", report)),
         Err(err) => Err(err),
     }
 }
@@ -196,17 +603,40 @@ pub async fn store(
     responses(
         (status = StatusCode::OK, description = "Score updated successfully", body = ScoreResponseBody),
         (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
-        (status = StatusCode::NOT_FOUND, description = "No score found by id", body = ErrorResponse)
+        (status = StatusCode::NOT_FOUND, description = "No score found by id", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or username, or score outside the bounds of the level", body = ErrorResponse)
     )
 )]
 pub async fn update(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-    Json(updated_score): Json<ScoreForm>,
-) -> Result<ResponseBody<ScoreDto>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    ValidatedJson(updated_score): ValidatedJson<ScoreForm>,
+) -> Result<ResponseBody<ScoreDto>, AppError> {
+    let (pool, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.name_filter.clone())
+    };
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Score,
+                id,
+                |conn| score_service::find_by_id(id, conn),
+                |before, conn| {
+                    score_service::authorize_moderator(&actor, before, conn)?;
+                    let level = level_service::find_by_id(updated_score.level_id, conn)?;
+                    game_service::authorize_moderator(&actor, level.game_id, conn)?;
+                    score_service::update(id, updated_score, &names, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
 
-    match score_service::update(id, updated_score, pool) {
+    match result {
         Ok(scores) => Ok(ResponseBody::ok("Score updated", scores)),
         Err(err) => Err(err),
     }
@@ -227,12 +657,72 @@ pub async fn update(
 )]
 pub async fn destroy(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<String>,
-) -> Result<StatusCode, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let scores: Vec<ScoreDto> = score_service::parse_ids(&id)
+                .into_iter()
+                .filter_map(|score_id| score_service::find_by_id(score_id, conn).ok())
+                .collect();
+            for score in &scores {
+                score_service::authorize_moderator(&actor, score, conn)?;
+            }
+            score_service::delete(id, conn)?;
+            for score in &scores {
+                audit_service::deleted(&actor, AuditEntity::Score, score.id, score, conn)?;
+            }
+
+            Ok(())
+        })
+    })
+    .await;
 
-    match score_service::delete(id, &pool) {
+    match result {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(err) => Err(err),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    tag = "Score",
+    operation_id = "score_restore",
+    params(
+        ("id", Path, description = "Unique id of a deleted Score")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Score restored successfully", body = ScoreResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No deleted score found by id", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Not allowed for game clients", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "The level or user of the score is deleted", body = ErrorResponse)
+    )
+)]
+pub async fn restore(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<ScoreDto>, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can restore deleted data".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let score = score_service::restore(id, conn)?;
+            score_service::authorize_moderator(&actor, &score, conn)?;
+            audit_service::restored(&actor, AuditEntity::Score, id, &score, conn)?;
+            Ok(score)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(score) => Ok(ResponseBody::ok("Score restored", score)),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,258 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension,
+};
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        api_key::GameClient,
+        audit_log::{Actor, AuditEntity},
+        season::{Season, SeasonForm, SeasonStanding, StandingQuery},
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{api_key_service, audit_service, game_service, season_service},
+    SharedState,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(index, store, update, destroy, standings),
+    components(schemas(
+        Season,
+        SeasonForm,
+        SeasonStanding,
+        SeasonResponseBody,
+        SeasonsResponseBody,
+        StandingsResponseBody
+    ))
+)]
+pub struct SeasonApi;
+
+/// The structure of the response body where there is a single season returned. This struct is primarily used for
+/// the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct SeasonResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Season,
+}
+
+/// The structure of the response body where there are multiple seasons returned. This struct is primarily used
+/// for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct SeasonsResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<Season>,
+}
+
+/// The structure of the response body where the standings of a season are returned. This struct is primarily
+/// used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct StandingsResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<SeasonStanding>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/game/{gameId}",
+    tag = "Season",
+    operation_id = "season_index",
+    params(
+        ("gameId", Path, description = "Unique id of a Game")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Seasons fetched successfully", body = SeasonsResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No game found by game id", body = ErrorResponse)
+    )
+)]
+pub async fn index(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(game_id): Path<Uuid>,
+) -> Result<ResponseBody<Vec<Season>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        api_key_service::authorize_game(client.as_ref(), game_id)?;
+        season_service::find_by_game(game_id, conn)
+    })
+    .await;
+
+    match result {
+        Ok(seasons) => Ok(ResponseBody::ok("Seasons fetched", seasons)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "Season",
+    operation_id = "season_store",
+    request_body = SeasonForm,
+    responses(
+        (status = StatusCode::CREATED, description = "Season created successfully", body = SeasonResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "Only dashboard users can create seasons", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No game found by game id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Season overlaps with another season", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn store(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    ValidatedJson(new_season): ValidatedJson<SeasonForm>,
+) -> Result<ResponseBody<Season>, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can create seasons".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            game_service::authorize_owner(&actor, new_season.game_id, conn)?;
+            let season = season_service::insert(new_season, conn)?;
+            audit_service::created(&actor, AuditEntity::Season, season.id, &season, conn)?;
+            Ok(season)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(season) => Ok(ResponseBody::created("Season created", season)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/{seasonId}",
+    tag = "Season",
+    operation_id = "season_update",
+    request_body = SeasonForm,
+    params(
+        ("seasonId", Path, description = "Unique id of a Season")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Season updated successfully", body = SeasonResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Season cannot be moved to another game", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No season found by id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Season is archived or overlaps with another season", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn update(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    ValidatedJson(updated_season): ValidatedJson<SeasonForm>,
+) -> Result<ResponseBody<Season>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Season,
+                id,
+                |conn| season_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.game_id, conn)?;
+                    season_service::update(id, updated_season, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(season) => Ok(ResponseBody::ok("Season updated", season)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{seasonId}",
+    tag = "Season",
+    operation_id = "season_destroy",
+    params(
+        ("seasonId", Path, description = "Unique id of a Season")
+    ),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "Season deleted successfully"),
+        (status = StatusCode::BAD_REQUEST, description = "The season has been archived", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No season found by id", body = ErrorResponse)
+    )
+)]
+pub async fn destroy(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_delete(
+                &actor,
+                AuditEntity::Season,
+                id,
+                |conn| season_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.game_id, conn)?;
+                    season_service::delete(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{seasonId}/standings",
+    tag = "Season",
+    operation_id = "season_standings",
+    params(
+        ("seasonId", Path, description = "Unique id of a Season"),
+        ("level_id", Query, description = "Unique id of a Level to only fetch the standings of")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Standings fetched successfully, empty while the season has not been archived", body = StandingsResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No season found by id", body = ErrorResponse)
+    )
+)]
+pub async fn standings(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<StandingQuery>,
+) -> Result<ResponseBody<Vec<SeasonStanding>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        let season = season_service::find_by_id(id, conn)?;
+        api_key_service::authorize_game(client.as_ref(), season.game_id)?;
+        season_service::standings(id, query, conn)
+    })
+    .await;
+
+    match result {
+        Ok(standings) => Ok(ResponseBody::ok("Standings fetched", standings)),
+        Err(err) => Err(err),
+    }
+}
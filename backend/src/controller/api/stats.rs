@@ -2,25 +2,25 @@ use axum::extract::{Path, State};
 use uuid::Uuid;
 
 use crate::{
+    config::db,
     models::stats::{GameStats, GlobalStats},
-    response::{ErrorResponse, ResponseBody},
+    response::{AppError, ResponseBody},
     service::stats_service,
     SharedState,
 };
 
 pub async fn all(
     State(app_state): State<SharedState>,
-) -> Result<ResponseBody<GlobalStats>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
-    let game_count = stats_service::count_games(&pool)?;
-    let score_count = stats_service::count_scores(None, &pool)?;
-    let user_count = stats_service::count_users(None, &pool)?;
-
-    let stats = GlobalStats {
-        games: game_count,
-        scores: score_count,
-        users: user_count,
-    };
+) -> Result<ResponseBody<GlobalStats>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let stats = db::with_connection(pool, |conn| {
+        Ok(GlobalStats {
+            games: stats_service::count_games(conn)?,
+            scores: stats_service::count_scores(None, conn)?,
+            users: stats_service::count_users(None, conn)?,
+        })
+    })
+    .await?;
 
     Ok(ResponseBody::ok("Global stats fetched", stats))
 }
@@ -28,15 +28,15 @@ pub async fn all(
 pub async fn game_stats(
     Path(game_id): Path<Uuid>,
     State(app_state): State<SharedState>,
-) -> Result<ResponseBody<GameStats>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
-    let score_count = stats_service::count_scores(Some(game_id), pool)?;
-    let user_count = stats_service::count_users(Some(game_id), pool)?;
-
-    let game_stats = GameStats {
-        scores: score_count,
-        users: user_count,
-    };
+) -> Result<ResponseBody<GameStats>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let game_stats = db::with_connection(pool, move |conn| {
+        Ok(GameStats {
+            scores: stats_service::count_scores(Some(game_id), conn)?,
+            users: stats_service::count_users(Some(game_id), conn)?,
+        })
+    })
+    .await?;
 
     Ok(ResponseBody::ok("Game stats fetched", game_stats))
 }
@@ -0,0 +1,380 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension,
+};
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        api_key::GameClient,
+        audit_log::{Actor, AuditEntity},
+        player::Player,
+        tournament::{
+            Tournament, TournamentDto, TournamentEntryForm, TournamentForm, TournamentPrize, TournamentScoring,
+            TournamentStanding,
+        },
+        user::User,
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{api_key_service, audit_service, game_service, tournament_service},
+    SharedState,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(index, show, store, update, destroy, join, entrants, standings),
+    components(schemas(
+        Tournament,
+        TournamentDto,
+        TournamentEntryForm,
+        TournamentForm,
+        TournamentPrize,
+        TournamentScoring,
+        TournamentStanding,
+        TournamentResponseBody,
+        TournamentsResponseBody,
+        EntrantsResponseBody,
+        TournamentStandingsResponseBody
+    ))
+)]
+pub struct TournamentApi;
+
+/// The structure of the response body where there is a single tournament returned. This struct is primarily used
+/// for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct TournamentResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: TournamentDto,
+}
+
+/// The structure of the response body where there are multiple tournaments returned. This struct is primarily
+/// used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct TournamentsResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<Tournament>,
+}
+
+/// The structure of the response body where the entrants of a tournament are returned. This struct is primarily
+/// used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct EntrantsResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<User>,
+}
+
+/// The structure of the response body where the standings of a tournament are returned. This struct is primarily
+/// used for the OpenAPI docs.
+#[derive(ToSchema)]
+pub struct TournamentStandingsResponseBody {
+    pub message: String,
+    pub status: String,
+    pub data: Vec<TournamentStanding>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/game/{gameId}",
+    tag = "Tournament",
+    operation_id = "tournament_index",
+    params(
+        ("gameId", Path, description = "Unique id of a Game")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Tournaments fetched successfully", body = TournamentsResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No game found by game id", body = ErrorResponse)
+    )
+)]
+pub async fn index(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(game_id): Path<Uuid>,
+) -> Result<ResponseBody<Vec<Tournament>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        api_key_service::authorize_game(client.as_ref(), game_id)?;
+        tournament_service::find_by_game(game_id, conn)
+    })
+    .await;
+
+    match result {
+        Ok(tournaments) => Ok(ResponseBody::ok("Tournaments fetched", tournaments)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{tournamentId}",
+    tag = "Tournament",
+    operation_id = "tournament_show",
+    params(
+        ("tournamentId", Path, description = "Unique id of a Tournament")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Tournament fetched successfully", body = TournamentResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No tournament found by id", body = ErrorResponse)
+    )
+)]
+pub async fn show(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<TournamentDto>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        let tournament = tournament_service::find_dto_by_id(id, conn)?;
+        api_key_service::authorize_game(client.as_ref(), tournament.tournament.game_id)?;
+        Ok(tournament)
+    })
+    .await;
+
+    match result {
+        Ok(tournament) => Ok(ResponseBody::ok("Tournament fetched", tournament)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "Tournament",
+    operation_id = "tournament_store",
+    request_body = TournamentForm,
+    responses(
+        (status = StatusCode::CREATED, description = "Tournament created successfully", body = TournamentResponseBody),
+        (status = StatusCode::FORBIDDEN, description = "Only dashboard users can create tournaments", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No game found by game id", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body, levels or prizes", body = ErrorResponse)
+    )
+)]
+pub async fn store(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    ValidatedJson(form): ValidatedJson<TournamentForm>,
+) -> Result<ResponseBody<TournamentDto>, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can create tournaments".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            game_service::authorize_owner(&actor, form.game_id, conn)?;
+            let tournament = tournament_service::insert(form, conn)?;
+            audit_service::created(&actor, AuditEntity::Tournament, tournament.tournament.id, &tournament, conn)?;
+            Ok(tournament)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(tournament) => Ok(ResponseBody::created("Tournament created", tournament)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/{tournamentId}",
+    tag = "Tournament",
+    operation_id = "tournament_update",
+    request_body = TournamentForm,
+    params(
+        ("tournamentId", Path, description = "Unique id of a Tournament")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Tournament updated successfully", body = TournamentResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Tournament cannot be moved to another game", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No tournament found by id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "More users joined than the maximum number of entrants", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body, levels or prizes", body = ErrorResponse)
+    )
+)]
+pub async fn update(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+    ValidatedJson(form): ValidatedJson<TournamentForm>,
+) -> Result<ResponseBody<TournamentDto>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::Tournament,
+                id,
+                |conn| tournament_service::find_dto_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.tournament.game_id, conn)?;
+                    tournament_service::update(id, form, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(tournament) => Ok(ResponseBody::ok("Tournament updated", tournament)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{tournamentId}",
+    tag = "Tournament",
+    operation_id = "tournament_destroy",
+    params(
+        ("tournamentId", Path, description = "Unique id of a Tournament")
+    ),
+    responses(
+        (status = StatusCode::NO_CONTENT, description = "Tournament deleted successfully"),
+        (status = StatusCode::NOT_FOUND, description = "No tournament found by id", body = ErrorResponse)
+    )
+)]
+pub async fn destroy(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_delete(
+                &actor,
+                AuditEntity::Tournament,
+                id,
+                |conn| tournament_service::find_dto_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_owner(&actor, before.tournament.game_id, conn)?;
+                    tournament_service::delete(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/{tournamentId}/join",
+    tag = "Tournament",
+    operation_id = "tournament_join",
+    request_body = TournamentEntryForm,
+    params(
+        ("tournamentId", Path, description = "Unique id of a Tournament")
+    ),
+    responses(
+        (status = StatusCode::CREATED, description = "User joined the tournament", body = TournamentResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "User does not belong to the game of the tournament", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "API key has no access to the tournament, or the user is a registered player and the request has no matching player token", body = ErrorResponse),
+        (status = StatusCode::NOT_FOUND, description = "No tournament or user found by id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Tournament has ended or is full, or the user already joined", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "No user given without a player token", body = ErrorResponse)
+    )
+)]
+pub async fn join(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    player: Option<Extension<Player>>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(entry): ValidatedJson<TournamentEntryForm>,
+) -> Result<ResponseBody<TournamentDto>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let player = player.map(|Extension(player)| player);
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            tournament_service::join(id, entry.user_id, client.as_ref(), player.as_ref(), conn)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(tournament) => Ok(ResponseBody::created("Joined tournament", tournament)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{tournamentId}/entrants",
+    tag = "Tournament",
+    operation_id = "tournament_entrants",
+    params(
+        ("tournamentId", Path, description = "Unique id of a Tournament")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Entrants fetched successfully", body = EntrantsResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No tournament found by id", body = ErrorResponse)
+    )
+)]
+pub async fn entrants(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<Vec<User>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        let tournament = tournament_service::find_by_id(id, conn)?;
+        api_key_service::authorize_game(client.as_ref(), tournament.game_id)?;
+        tournament_service::entrants(id, conn)
+    })
+    .await;
+
+    match result {
+        Ok(users) => Ok(ResponseBody::ok("Entrants fetched", users)),
+        Err(err) => Err(err),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{tournamentId}/standings",
+    tag = "Tournament",
+    operation_id = "tournament_standings",
+    params(
+        ("tournamentId", Path, description = "Unique id of a Tournament")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "Standings fetched successfully", body = TournamentStandingsResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No tournament found by id", body = ErrorResponse)
+    )
+)]
+pub async fn standings(
+    State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<Vec<TournamentStanding>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    let result = db::with_connection(pool, move |conn| {
+        let tournament = tournament_service::find_by_id(id, conn)?;
+        api_key_service::authorize_game(client.as_ref(), tournament.game_id)?;
+        tournament_service::standings(id, conn)
+    })
+    .await;
+
+    match result {
+        Ok(standings) => Ok(ResponseBody::ok("Standings fetched", standings)),
+        Err(err) => Err(err),
+    }
+}
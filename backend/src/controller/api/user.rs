@@ -1,22 +1,29 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Json,
+    Extension,
 };
 use utoipa::{OpenApi, ToSchema};
 use uuid::Uuid;
 
 use crate::{
-    models::user::{User, UserForm},
-    response::{ErrorResponse, ResponseBody},
-    service::user_service,
+    config::db,
+    extract::ValidatedJson,
+    models::{
+        api_key::GameClient,
+        audit_log::{Actor, AuditEntity},
+        pagination::{PageQuery, Pagination},
+        user::{User, UserForm, UserSort},
+    },
+    response::{AppError, ErrorResponse, ResponseBody},
+    service::{api_key_service, audit_service, game_service, user_service},
     SharedState,
 };
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(index, store, update, destroy),
-    components(schemas(User, UserForm, UserResponseBody, UsersResponseBody))
+    paths(index, store, update, destroy, restore),
+    components(schemas(User, UserForm, UserResponseBody, UsersResponseBody, UserSort, Pagination))
 )]
 pub struct UserApi;
 
@@ -36,6 +43,7 @@ pub struct UserResponseBody {
     pub message: String,
     pub status: String,
     pub data: Vec<User>,
+    pub pagination: Pagination,
 }
 
 #[utoipa::path(
@@ -45,19 +53,28 @@ pub struct UserResponseBody {
     operation_id = "user_index",
     params(
         ("id", Path, description = "Unique id of a game"),
+        ("limit", Query, description = "Maximum number of items to return, defaults to 50 with a maximum of 500"),
+        ("cursor", Query, description = "Cursor of the next page, as returned in the pagination metadata of the previous page"),
+        ("sort", Query, description = "Field to sort on, one of 'name' or 'created_at' (default)"),
+        ("order", Query, description = "Sort order, either 'asc' (default) or 'desc'")
     ),
     responses(
-        (status = StatusCode::OK, description = "User fetched successfully", body = UsersResponseBody)
+        (status = StatusCode::OK, description = "User fetched successfully", body = UserResponseBody),
+        (status = StatusCode::BAD_REQUEST, description = "Invalid cursor", body = ErrorResponse)
     )
 )]
 pub async fn index(
     State(app_state): State<SharedState>,
+    client: Option<Extension<GameClient>>,
     Path(game_id): Path<Uuid>,
-) -> Result<ResponseBody<Vec<User>>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    Query(page): Query<PageQuery<UserSort>>,
+) -> Result<ResponseBody<Vec<User>>, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let client = client.map(|Extension(client)| client);
+    api_key_service::authorize_game(client.as_ref(), game_id)?;
 
-    match user_service::find_by_game(game_id, pool) {
-        Ok(users) => Ok(ResponseBody::ok("Users fetched", users)),
+    match db::with_connection(pool, move |conn| user_service::find_by_game(game_id, &page, conn)).await {
+        Ok(users) => Ok(ResponseBody::page("Users fetched", users)),
         Err(err) => Err(err),
     }
 }
@@ -70,16 +87,35 @@ pub async fn index(
     request_body = UserForm,
     responses(
         (status = StatusCode::CREATED, description = "New user created", body = UsersResponseBody),
-        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse)
+        (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Name already taken in the game", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or name", body = ErrorResponse)
     )
 )]
 pub async fn store(
     State(app_state): State<SharedState>,
-    Json(new_user): Json<UserForm>,
-) -> Result<ResponseBody<User>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    actor: Actor,
+    client: Option<Extension<GameClient>>,
+    ValidatedJson(new_user): ValidatedJson<UserForm>,
+) -> Result<ResponseBody<User>, AppError> {
+    let (pool, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.name_filter.clone())
+    };
+    let client = client.map(|Extension(client)| client);
+    api_key_service::authorize_game(client.as_ref(), new_user.game_id)?;
+
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            game_service::authorize_moderator(&actor, new_user.game_id, conn)?;
+            let user = user_service::insert(new_user, &names, conn)?;
+            audit_service::created(&actor, AuditEntity::User, user.id, &user, conn)?;
+            Ok(user)
+        })
+    })
+    .await;
 
-    match user_service::insert(new_user, pool) {
+    match result {
         Ok(added_user) => Ok(ResponseBody::created("User created", added_user)),
         Err(err) => Err(err),
     }
@@ -97,17 +133,41 @@ pub async fn store(
     responses(
         (status = StatusCode::OK, description = "User updated successfully", body = UserResponseBody),
         (status = StatusCode::BAD_REQUEST, description = "Invalid input", body = ErrorResponse),
-        (status = StatusCode::NOT_FOUND, description = "No user found by id", body = ErrorResponse)
+        (status = StatusCode::NOT_FOUND, description = "No user found by id", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "Name already taken in the game", body = ErrorResponse),
+        (status = StatusCode::UNPROCESSABLE_ENTITY, description = "Invalid request body or name", body = ErrorResponse)
     )
 )]
 pub async fn update(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-    Json(updated_user): Json<UserForm>,
-) -> Result<ResponseBody<User>, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+    ValidatedJson(updated_user): ValidatedJson<UserForm>,
+) -> Result<ResponseBody<User>, AppError> {
+    let (pool, names) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.name_filter.clone())
+    };
 
-    match user_service::update(id, updated_user, pool) {
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_update(
+                &actor,
+                AuditEntity::User,
+                id,
+                |conn| user_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_moderator(&actor, before.game_id, conn)?;
+                    game_service::authorize_moderator(&actor, updated_user.game_id, conn)?;
+                    user_service::update(id, updated_user, &names, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
+
+    match result {
         Ok(level) => Ok(ResponseBody::ok("User updated", level)),
         Err(error) => Err(error),
     }
@@ -128,12 +188,70 @@ pub async fn update(
 )]
 pub async fn destroy(
     State(app_state): State<SharedState>,
+    actor: Actor,
     Path(id): Path<Uuid>,
-) -> Result<StatusCode, ErrorResponse> {
-    let pool = &app_state.read().unwrap().db;
+) -> Result<StatusCode, AppError> {
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            audit_service::record_delete(
+                &actor,
+                AuditEntity::User,
+                id,
+                |conn| user_service::find_by_id(id, conn),
+                |before, conn| {
+                    game_service::authorize_moderator(&actor, before.game_id, conn)?;
+                    user_service::delete(id, conn)
+                },
+                conn,
+            )
+        })
+    })
+    .await;
 
-    match user_service::delete(id, pool) {
+    match result {
         Ok(_) => Ok(StatusCode::NO_CONTENT),
         Err(err) => Err(err),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    tag = "User",
+    operation_id = "user_restore",
+    params(
+        ("id", Path, description = "Unique id of a deleted User")
+    ),
+    responses(
+        (status = StatusCode::OK, description = "User restored successfully", body = UserResponseBody),
+        (status = StatusCode::NOT_FOUND, description = "No deleted user found by id", body = ErrorResponse),
+        (status = StatusCode::FORBIDDEN, description = "Not allowed for game clients", body = ErrorResponse),
+        (status = StatusCode::CONFLICT, description = "The game of the user is deleted", body = ErrorResponse)
+    )
+)]
+pub async fn restore(
+    State(app_state): State<SharedState>,
+    actor: Actor,
+    Path(id): Path<Uuid>,
+) -> Result<ResponseBody<User>, AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Err(AppError::Forbidden("Only dashboard users can restore deleted data".to_string()));
+    }
+
+    let pool = app_state.read().unwrap().db.clone();
+    let result = db::with_connection(pool, move |conn| {
+        db::transaction(conn, |conn| {
+            let user = user_service::restore(id, conn)?;
+            game_service::authorize_moderator(&actor, user.game_id, conn)?;
+            audit_service::restored(&actor, AuditEntity::User, id, &user, conn)?;
+            Ok(user)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(user) => Ok(ResponseBody::ok("User restored", user)),
+        Err(err) => Err(err),
+    }
+}
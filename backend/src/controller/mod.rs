@@ -1,16 +1,28 @@
 use axum::{middleware, routing::get, Router};
 
-use crate::{middleware::auth_middleware, SharedState};
+use crate::{
+    middleware::{api_key_middleware, auth_middleware},
+    SharedState,
+};
 
 pub mod api;
 
-pub fn api_routes() -> Router<SharedState> {
+pub fn api_routes(state: SharedState) -> Router<SharedState> {
+    let game_client_routes = Router::new()
+        .nest("/player", api::player_routes())
+        .nest("/score", api::score_routes())
+        .nest("/season", api::season_routes())
+        .nest("/tournament", api::tournament_routes())
+        .nest("/user", api::user_routes())
+        .layer(middleware::from_fn_with_state(state.clone(), api_key_middleware::verify_client));
+
     Router::new()
+        .nest("/audit", api::audit_routes())
         .nest("/game", api::game_routes())
+        .nest("/key", api::api_key_routes())
         .nest("/level", api::level_routes())
         .nest("/stats", api::stats_routes())
-        .layer(middleware::from_fn(auth_middleware::verify_token))
-        .nest("/score", api::score_routes())
-        .nest("/user", api::user_routes())
+        .layer(middleware::from_fn_with_state(state, auth_middleware::verify_token))
+        .merge(game_client_routes)
         .route("/healthcheck", get(api::healthcheck))
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+use axum::{
+    extract::{
+        rejection::{JsonDataError, JsonRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::request::Parts,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+use crate::{
+    models::{api_key::GameClient, audit_log::Actor, auth::AuthUser, player::Player},
+    response::{AppError, FieldError},
+};
+
+/// Extracts and validates a JSON request body. In contrast to [`Json`], a body that cannot be parsed or is
+/// invalid is rejected with an [`ErrorResponse`](crate::response::ErrorResponse) instead of a plain text
+/// response.
+///
+/// # Errors
+/// - 400 if the body is not valid JSON or the content type is not `application/json`.
+/// - 422 if the body does not match the expected structure, or one of the fields is invalid.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                JsonRejection::JsonDataError(err) => data_error(err),
+                rejection => AppError::BadRequest(rejection.body_text()),
+            })?;
+
+        value.validate()?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// Turns a body that doesn't match the expected structure into a validation error of the field that could not be
+/// deserialized. The path of the field is found in the [`serde_path_to_error`] error that caused the rejection.
+fn data_error(rejection: JsonDataError) -> AppError {
+    let mut source = std::error::Error::source(&rejection);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>() {
+            return AppError::Validation("Invalid request body".to_string(), vec![field_error(err)]);
+        }
+        source = err.source();
+    }
+
+    AppError::Validation(rejection.body_text(), vec![])
+}
+
+/// Creates the error of the field at the path of the deserialization error. Serde reports a missing field at the
+/// path of the object it is missing from, so the name of the field is added to that path.
+fn field_error(err: &serde_path_to_error::Error<serde_json::Error>) -> FieldError {
+    let message = err.inner().to_string();
+    let message = message.split(" at line ").next().unwrap_or_default();
+    let path = err.path().to_string();
+
+    match message.strip_prefix("missing field `").and_then(|rest| rest.split_once('`')) {
+        Some((field, _)) if path == "." => FieldError::new(field, "is required"),
+        Some((field, _)) => FieldError::new(&format!("{}.{}", path, field), "is required"),
+        None => FieldError::new(&path, message),
+    }
+}
+
+impl<S> FromRequestParts<S> for Actor
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    /// Extracts the actor from the extensions added by the authentication middleware.
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(player) = parts.extensions.get::<Player>() {
+            return Ok(Actor::Player(player.user_id));
+        }
+        if let Some(client) = parts.extensions.get::<GameClient>() {
+            return Ok(Actor::GameClient(client.key_id));
+        }
+
+        match parts.extensions.get::<AuthUser>() {
+            Some(user) => Ok(Actor::User(user.clone())),
+            None => Err(AppError::Unauthorized("Invalid token".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Form {
+        name: String,
+        limits: Limits,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Limits {
+        max: i32,
+    }
+
+    fn error_of(body: &str) -> FieldError {
+        let deserializer = &mut serde_json::Deserializer::from_str(body);
+        let err = serde_path_to_error::deserialize::<_, Form>(deserializer).unwrap_err();
+        field_error(&err)
+    }
+
+    #[test]
+    fn reports_the_path_of_an_invalid_field() {
+        let error = error_of(r#"{"name": "Level", "limits": {"max": "ten"}}"#);
+
+        assert_eq!(error.field, "limits.max");
+        assert!(error.message.starts_with("invalid type"));
+    }
+
+    #[test]
+    fn reports_a_missing_field_as_required() {
+        assert_eq!(error_of(r#"{"limits": {"max": 10}}"#).field, "name");
+        assert_eq!(error_of(r#"{"name": "Level", "limits": {}}"#).field, "limits.max");
+        assert_eq!(error_of(r#"{"name": "Level", "limits": {}}"#).message, "is required");
+    }
+}
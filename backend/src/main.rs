@@ -1,23 +1,36 @@
 use std::{
-    env,
-    error::Error,
-    fs::OpenOptions,
-    io::{ErrorKind, Write},
-    net::SocketAddr,
+    process,
     sync::{Arc, RwLock},
     time::Duration,
 };
 
-use config::db::{init_db_pool, run_migration, Pool};
-use controller::api::{game::GameApi, level::LevelApi, score::ScoreApi, user::UserApi};
+use config::{
+    db::{self, init_db_pool, run_migration, Pool},
+    Config,
+};
+use controller::api::{
+    api_key::ApiKeyApi, audit::AuditApi, game::GameApi, level::LevelApi, player::PlayerApi, score::ScoreApi,
+    season::SeasonApi, tournament::TournamentApi, user::UserApi,
+};
 #[cfg(debug_assertions)]
 use dotenvy::dotenv;
 use log::{error, info};
-use tokio::{net::TcpListener, spawn, time::interval};
+use service::{
+    name_service::NameFilter,
+    oauth2_service::AuthProvider,
+    player_service::PlayerTokenIssuer,
+    purge_service, season_service,
+};
+use tokio::{
+    net::TcpListener,
+    spawn,
+    time::{interval_at, Instant},
+};
 use utoipa::OpenApi;
 
 pub mod config;
 pub mod controller;
+pub mod extract;
 pub mod middleware;
 pub mod models;
 pub mod response;
@@ -25,8 +38,6 @@ pub mod routes;
 pub mod schema;
 pub mod service;
 
-pub const JWK_FILE_PATH: &str = "data/jwk.json";
-
 #[derive(OpenApi)]
 #[openapi(
     info(
@@ -35,15 +46,25 @@ pub const JWK_FILE_PATH: &str = "data/jwk.json";
     ),
     servers((url = "https://babs.bonk.group/api")),
     nest(
+        (path = "/audit", api = AuditApi),
         (path = "/game", api = GameApi),
+        (path = "/key", api = ApiKeyApi),
         (path = "/level", api = LevelApi),
+        (path = "/player", api = PlayerApi),
         (path = "/score", api = ScoreApi),
+        (path = "/season", api = SeasonApi),
+        (path = "/tournament", api = TournamentApi),
         (path = "/user", api = UserApi)
     ),
     tags(
+        (name = "Audit", description = "Audit log of the changes made by dashboard users."),
         (name = "Game", description = "Game management endpoints."),
+        (name = "ApiKey", description = "API key management endpoints for game clients."),
         (name = "Level", description = "Level management endpoints."),
+        (name = "Player", description = "Player authentication endpoints for game clients."),
         (name = "Score", description = "Score management endpoints."),
+        (name = "Season", description = "Season management endpoints and archived season standings."),
+        (name = "Tournament", description = "Tournament management, registration and standings endpoints."),
         (name = "User", description = "User management endpoints.")
     )
 )]
@@ -54,76 +75,129 @@ async fn main() {
     #[cfg(debug_assertions)]
     dotenv().expect(".env file not found");
 
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
-
-    let app_host = env::var("APP_HOST").expect("APP_HOST must be set");
-    let app_port = env::var("APP_PORT").expect("APP_PORT must be set");
-    let app_url = format!("{}:{}", app_host, app_port);
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
 
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let db_pool = init_db_pool(&db_url);
-    run_migration(&mut db_pool.get().unwrap());
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or(&config.log_level));
 
-    let state = SharedState::new(RwLock::new(AppState { db: db_pool }));
+    let db_pool = init_db_pool(&config);
+    if let Err(err) = run_migration(&mut db_pool.get().unwrap()) {
+        error!("{}", err);
+        process::exit(1);
+    }
 
-    let addr: SocketAddr = app_url.parse().expect("Cannot parse app url to socket");
+    let addr = config.address().expect("Cannot parse app url to socket");
     let listener = TcpListener::bind(addr).await.unwrap();
+
+    let auth_provider = match AuthProvider::from_config(&config).await {
+        Ok(provider) => Arc::new(provider),
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    // The keys are fetched before the server accepts requests, so the first requests don't race the fetch.
+    if let Err(err) = auth_provider.jwks.refresh().await {
+        error!("{}", err);
+    }
+    spawn(refresh_jwk(auth_provider.clone(), config.jwks_refresh_interval));
+    spawn(purge_deleted(db_pool.clone(), config.deleted_retention, config.purge_interval));
+    spawn(archive_seasons(db_pool.clone(), config.season_interval));
+
+    let player_tokens = match PlayerTokenIssuer::from_config(&config) {
+        Ok(issuer) => Arc::new(issuer),
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let name_filter = match NameFilter::from_config(&config) {
+        Ok(filter) => Arc::new(filter),
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    let state = SharedState::new(RwLock::new(AppState {
+        db: db_pool,
+        config,
+        auth_provider,
+        player_tokens,
+        name_filter,
+    }));
     let app = routes::create_app(state).await;
 
-    spawn(refresh_jwk());
     axum::serve(listener, app).await.unwrap();
 }
 
-/// Fetches the JWKS data from the authorization server and saves the 
-/// content to a json file.
-/// 
-/// # Errors
-/// 
-/// This function fails if:
-/// - JWKS data could not be fetched
-/// - JWKS content could not be saved to file
-/// 
-async fn fetch_and_save_jwks() -> Result<(), Box<dyn Error>> {
-    let jwsk_url = env::var("OAUTH_JWKS_URL").expect("OAUTH_JWKS_URL must be set");
-    let tokens = reqwest::get(&jwsk_url).await?.text().await;
-
-    if tokens.is_err() {
-        error!("Could not fetch token from {}", jwsk_url);
-        return Err(Box::new(std::io::Error::new(
-            ErrorKind::Other,
-            "Could not fetch JWKS token",
-        )));
-    }
+/// Fetches the JWKS at the configured interval, so keys that are removed by the
+/// authorization server are no longer accepted. The first fetch is done at startup.
+async fn refresh_jwk(provider: Arc<AuthProvider>, period: Duration) {
+    let mut delay = interval_at(Instant::now() + period, period);
 
-    info!("JWKS token fetched, saving to file");
-    let file_options = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .open(JWK_FILE_PATH);
-
-    if file_options.is_ok() {
-        info!("Write new JWKS token to file");
-        let _ = file_options.unwrap().write_all(tokens.unwrap().as_bytes());
-    } else {
-        error!(
-            "Cannot write JWKS token to file, reason {}",
-            file_options.err().unwrap()
-        )
+    loop {
+        delay.tick().await;
+
+        info!("Refreshing JWKS token");
+        if let Err(err) = provider.jwks.refresh().await {
+            error!("{}", err);
+        }
     }
+}
+
+/// Removes the data that was deleted longer than the retention ago and the expired submission nonces and attempts,
+/// at the given interval. The first purge is done at startup.
+async fn purge_deleted(pool: Pool, retention: Duration, period: Duration) {
+    let mut delay = interval_at(Instant::now(), period);
 
-    Ok(())
+    loop {
+        delay.tick().await;
+
+        let pool = pool.clone();
+        let result = db::with_connection(pool, move |conn| {
+            Ok((purge_service::purge_deleted(retention, conn), purge_service::purge_expired(conn)))
+        })
+        .await;
+        match result {
+            Ok((deleted, expired)) => {
+                match deleted {
+                    Ok(0) => {}
+                    Ok(count) => info!("Purged {} deleted rows", count),
+                    Err(err) => error!("{}", err),
+                }
+                if let Err(err) = expired {
+                    error!("{}", err);
+                }
+            }
+            Err(_) => error!("Cannot purge deleted data, the database is unavailable"),
+        }
+    }
 }
 
-/// Calls the [`fetch_and_save_jwks`] function every month to make sure the 
-/// JWKS info is valid.
-async fn refresh_jwk() {
-    let mut delay = interval(Duration::from_secs(604_800));
+/// Archives the final standings of the seasons that have ended, at the given interval. The first check is done at
+/// startup, so seasons that ended while the server was down are archived right away.
+async fn archive_seasons(pool: Pool, period: Duration) {
+    let mut delay = interval_at(Instant::now(), period);
 
     loop {
         delay.tick().await;
 
-        info!("Refreshing JWKS token");
-        let _ = fetch_and_save_jwks().await;
+        let pool = pool.clone();
+        let result = db::with_connection(pool, move |conn| Ok(season_service::archive_ended(conn))).await;
+        match result {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => info!("Archived the standings of {} seasons", count),
+            Ok(Err(err)) => error!("{}", err),
+            Err(_) => error!("Cannot archive seasons, the database is unavailable"),
+        }
     }
 }
 
@@ -132,4 +206,8 @@ type SharedState = Arc<RwLock<AppState>>;
 #[derive(Clone)]
 pub struct AppState {
     db: Pool,
+    config: Config,
+    auth_provider: Arc<AuthProvider>,
+    player_tokens: Arc<PlayerTokenIssuer>,
+    name_filter: Arc<NameFilter>,
 }
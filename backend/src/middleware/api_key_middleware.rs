@@ -0,0 +1,71 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use log::info;
+
+use crate::{
+    config::db,
+    middleware::auth_middleware,
+    response::AppError,
+    service::api_key_service,
+    SharedState,
+};
+
+/// The header in which game clients send their API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// This function authenticates requests made by either a game client or a dashboard user. When the request
+/// contains an API key, the matching [`GameClient`](crate::models::api_key::GameClient) is added to the request
+/// extensions. A game client can send the access token of a signed in player as bearer token next to the API key,
+/// in which case the [`Player`](crate::models::player::Player) is added as well. Otherwise the request is
+/// validated as an OAuth2 request with [`auth_middleware::verify_token`].
+///
+/// # Errors
+/// - if the API key is invalid or revoked.
+/// - if the player access token is invalid, or issued for another game than the API key.
+/// - if a game client tries to update or delete data.
+/// - if no API key is present and the OAuth2 access token is invalid.
+pub async fn verify_client(
+    State(app_state): State<SharedState>,
+    headers: HeaderMap,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(api_key) = headers.get(API_KEY_HEADER) else {
+        return auth_middleware::verify_token(State(app_state), headers, req, next).await;
+    };
+
+    if req.method() != Method::GET && req.method() != Method::POST {
+        return Err(AppError::Forbidden("API keys can only be used to fetch and submit data".to_string()));
+    }
+
+    let secret = api_key
+        .to_str()
+        .map_err(|_| AppError::Unauthorized("Invalid API key".to_string()))?;
+    let secret = secret.to_string();
+    let (pool, player_tokens) = {
+        let state = app_state.read().unwrap();
+        (state.db.clone(), state.player_tokens.clone())
+    };
+    let client = db::with_connection(pool, move |conn| api_key_service::authenticate(&secret, conn)).await?;
+
+    info!("Game client authenticated with API key '{}'", client.key_id);
+    if let Some(token) = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        let player = player_tokens.verify(token)?;
+        if player.game_id != client.game_id {
+            return Err(AppError::Unauthorized("Invalid player token".to_string()));
+        }
+
+        req.extensions_mut().insert(player);
+    }
+    req.extensions_mut().insert(client);
+
+    Ok(next.run(req).await)
+}
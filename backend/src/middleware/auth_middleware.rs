@@ -1,20 +1,38 @@
-use std::env;
-
-use axum::{extract::Request, http::HeaderMap, middleware::Next, response::Response};
-use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use jsonwebtoken::DecodingKey;
 use log::{error, info};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::{
-    response::{ErrorResponse, ResponseBody},
-    service::oauth2_service,
+    models::{
+        api_key::GameClient,
+        auth::{AuthUser, Role},
+    },
+    response::AppError,
+    SharedState,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
-    aud: String,
     sub: String,
-    exp: usize,
+    #[serde(flatten)]
+    other: Map<String, Value>,
+}
+
+/// The roles that are allowed to access a group of routes, per type of request. `GET` requests are checked
+/// against `read`, `POST` against `create`, `PUT` and `PATCH` against `update` and `DELETE` against `delete`.
+#[derive(Debug, Clone, Copy)]
+pub struct RoutePolicy {
+    pub read: &'static [Role],
+    pub create: &'static [Role],
+    pub update: &'static [Role],
+    pub delete: &'static [Role],
 }
 
 /// This function validates if the given request contains a valid OAuth2 access token using the given JWKS token from
@@ -22,57 +40,131 @@ struct Claims {
 ///
 /// # Errors
 /// - if no Authorization header is present.
-/// - if the JWKS tokens could not be read.
-/// - if the JWKS tokens could not be decoded.
+/// - if no JWK matches the key id of the access token.
+/// - if the JWK could not be decoded.
 /// - If the given access token is invalid.
 pub async fn verify_token(
+    State(app_state): State<SharedState>,
     headers: HeaderMap,
-    req: Request,
+    mut req: Request,
     next: Next,
-) -> Result<Response, ErrorResponse> {
-    let audience = env::var("OAUTH_CLIENT_ID").expect("OAUTH_CLIENT_ID must be set");
-    let jwk_token = oauth2_service::get_jwk_tokens();
-    let auth_token = headers.get("Authorization");
+) -> Result<Response, AppError> {
+    let (roles_claim, provider) = {
+        let state = app_state.read().unwrap();
+        (state.config.oauth_roles_claim.clone(), state.auth_provider.clone())
+    };
 
-    if auth_token.is_none() || jwk_token.is_err() {
-        return Err(ResponseBody::unauthorized_error("Invalid token"));
-    }
+    let Some(token) = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.replace("Bearer ", ""))
+    else {
+        return Err(AppError::Unauthorized("Invalid token".to_string()));
+    };
+    let Ok(header) = jsonwebtoken::decode_header(&token) else {
+        return Err(AppError::Unauthorized("Invalid token".to_string()));
+    };
 
-    let token = auth_token.unwrap().to_str().unwrap().replace("Bearer ", "");
-    let mut validation = Validation::new(Algorithm::RS256);
-    validation.set_audience(&[audience]);
+    let validation = match provider.validation(header.alg) {
+        Ok(validation) => validation,
+        Err(err) => {
+            info!("User authentication failed, reason '{}'", err);
+            return Err(AppError::Unauthorized("Invalid token".to_string()));
+        }
+    };
 
-    let decoding_key = match jwk_token.unwrap() {
+    let decoding_key = match provider.jwks.find(header.kid.as_deref()).await {
         Some(token) => match DecodingKey::from_jwk(&token) {
             Ok(key) => key,
             Err(_) => {
                 error!("Could not decode the JWK");
 
-                return Err(ResponseBody::unauthorized_error(
-                    "Error during authenticating",
-                ));
+                return Err(AppError::Unauthorized("Error during authenticating".to_string()));
             }
         },
         None => {
-            error!("Could not get a JWK");
+            info!("No JWK found with key id '{}'", header.kid.unwrap_or_default());
 
-            return Err(ResponseBody::unauthorized_error(
-                "Error during authenticating",
-            ));
+            return Err(AppError::Unauthorized("Error during authenticating".to_string()));
         }
     };
 
-    match jsonwebtoken::decode::<Claims>(&token, &decoding_key, &validation) {
-        Ok(_) => info!("User authenticated"),
+    let claims = match jsonwebtoken::decode::<Claims>(&token, &decoding_key, &validation) {
+        Ok(data) => {
+            info!("User authenticated");
+            data.claims
+        }
         Err(err) => {
             info!(
                 "User authentication failed, invalid token. Reason '{:?}'",
                 err.kind()
             );
 
-            return Err(ResponseBody::unauthorized_error("Invalid token"));
+            return Err(AppError::Unauthorized("Invalid token".to_string()));
         }
+    };
+
+    req.extensions_mut().insert(AuthUser {
+        roles: extract_roles(&claims.other, &roles_claim),
+        sub: claims.sub,
+    });
+
+    Ok(next.run(req).await)
+}
+
+/// This function checks if the authenticated dashboard user has one of the roles the [`RoutePolicy`] requires
+/// for the method of the request. Requests made by a game client are let through, because their access is
+/// restricted by the API key middleware and the controllers.
+///
+/// # Errors
+/// - if the request is not authenticated.
+/// - if the user has none of the required roles.
+pub async fn authorize(
+    State(policy): State<RoutePolicy>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if req.extensions().get::<GameClient>().is_some() {
+        return Ok(next.run(req).await);
+    }
+
+    let Some(user) = req.extensions().get::<AuthUser>() else {
+        return Err(AppError::Unauthorized("Invalid token".to_string()));
+    };
+
+    let required_roles = match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => policy.read,
+        Method::POST => policy.create,
+        Method::PUT | Method::PATCH => policy.update,
+        Method::DELETE => policy.delete,
+        _ => &[],
+    };
+
+    if !user.has_any_role(required_roles) {
+        info!("User '{}' is not allowed to {} {}", user.sub, req.method(), req.uri().path());
+        return Err(AppError::Forbidden("You are not allowed to perform this action".to_string()));
     }
 
     Ok(next.run(req).await)
 }
+
+/// Reads the roles from the claim at the given path. Nested claims can be selected with a dot separated path,
+/// e.g. `realm_access.roles`. The claim can either be an array of roles or a space separated string, like the
+/// `scope` claim. Unknown roles are ignored.
+fn extract_roles(claims: &Map<String, Value>, path: &str) -> Vec<Role> {
+    let mut segments = path.split('.');
+    let mut claim = segments.next().and_then(|segment| claims.get(segment));
+    for segment in segments {
+        claim = claim.and_then(|value| value.get(segment));
+    }
+
+    match claim {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .filter_map(Role::from_claim)
+            .collect(),
+        Some(Value::String(value)) => value.split_whitespace().filter_map(Role::from_claim).collect(),
+        _ => vec![],
+    }
+}
@@ -1 +1,2 @@
+pub mod api_key_middleware;
 pub mod auth_middleware;
\ No newline at end of file
@@ -0,0 +1,127 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{prelude::*, Connection as _};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::db::Connection,
+    models::game::Game,
+    schema::{
+        api_key::{self, dsl::*},
+        game,
+    },
+};
+
+#[derive(Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = api_key)]
+#[diesel(belongs_to(Game))]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    /// The first characters of the key, used to recognize a key without exposing it.
+    pub prefix: String,
+    #[serde(skip)]
+    pub key_hash: String,
+    pub game_id: Uuid,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ApiKeyForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
+    pub name: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = api_key)]
+pub struct NewApiKey {
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub game_id: Uuid,
+}
+
+/// The game client that authenticated a request with an API key. Game clients only have access to the levels,
+/// users and scores of their own game.
+#[derive(Debug, Clone, Copy)]
+pub struct GameClient {
+    pub key_id: Uuid,
+    pub game_id: Uuid,
+}
+
+/// A newly created API key together with its secret. The secret is only returned once and cannot be retrieved
+/// afterwards, because only its hash is stored.
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub secret: String,
+}
+
+impl ApiKey {
+    /// Fetches an API key from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no API key is found with the given id.
+    pub fn find_by_id(key_id: Uuid, conn: &mut Connection) -> QueryResult<ApiKey> {
+        api_key.find(key_id).get_result::<ApiKey>(conn)
+    }
+
+    /// Fetches the API keys related to the given game from the database, including revoked keys.
+    pub fn find_by_game(game: &Game, conn: &mut Connection) -> QueryResult<Vec<ApiKey>> {
+        ApiKey::belonging_to(game)
+            .select(ApiKey::as_select())
+            .order(created_at.desc())
+            .load(conn)
+    }
+
+    /// Fetches the API key that is not revoked with the given hash from the database. Keys of deleted games are
+    /// not active.
+    ///
+    /// # Errors
+    /// - If no active API key is found with the given hash.
+    pub fn find_active_by_hash(hash: &str, conn: &mut Connection) -> QueryResult<ApiKey> {
+        api_key
+            .filter(key_hash.eq(hash))
+            .filter(revoked_at.is_null())
+            .filter(game_id.eq_any(game::table.filter(game::deleted_at.is_null()).select(game::id)))
+            .get_result::<ApiKey>(conn)
+    }
+
+    /// Adds a new API key to the database.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn insert(data: NewApiKey, conn: &mut Connection) -> QueryResult<ApiKey> {
+        diesel::insert_into(api_key)
+            .values(&data)
+            .get_result::<ApiKey>(conn)
+    }
+
+    /// Revokes the API key with the given id, after which it can no longer be used to authenticate.
+    ///
+    /// Errors
+    /// - If no API key is found with the given id.
+    pub fn revoke(key_id: Uuid, conn: &mut Connection) -> QueryResult<ApiKey> {
+        diesel::update(api_key)
+            .filter(id.eq(key_id))
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .get_result::<ApiKey>(conn)
+    }
+
+    /// Revokes the API key with the given id and adds the given key as its replacement, in a single transaction.
+    ///
+    /// Errors
+    /// - If no API key is found with the given id.
+    /// - If one of the fields contain invalid data.
+    pub fn rotate(key_id: Uuid, data: NewApiKey, conn: &mut Connection) -> QueryResult<ApiKey> {
+        conn.transaction(|conn| {
+            ApiKey::revoke(key_id, conn)?;
+            ApiKey::insert(data, conn)
+        })
+    }
+}
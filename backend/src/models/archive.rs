@@ -0,0 +1,246 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+    config::db::Connection,
+    models::level::{Aggregation, ScoreUnit, SortDirection},
+    schema::{game, level, score, user},
+};
+
+/// The version of the archives created by this version of the server. Archives with a newer version cannot be
+/// imported.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// A portable copy of a game with its levels, users and scores, used to move a game between servers. Deleted
+/// data and API keys are not part of an archive.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GameArchive {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub game: ArchivedGame,
+    pub levels: Vec<ArchivedLevel>,
+    pub users: Vec<ArchivedUser>,
+    pub scores: Vec<ArchivedScore>,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = game)]
+pub struct ArchivedGame {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    /// Only present when the archive was exported with its secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_secret: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = level)]
+pub struct ArchivedLevel {
+    pub id: Uuid,
+    pub name: String,
+    pub game_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub sort_direction: SortDirection,
+    pub score_unit: ScoreUnit,
+    pub aggregation: Aggregation,
+    pub min_score: Option<i32>,
+    pub max_score: Option<i32>,
+    pub max_submissions: Option<i32>,
+    pub submission_window: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = user)]
+pub struct ArchivedUser {
+    pub id: Uuid,
+    pub name: String,
+    pub game_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub is_guest: bool,
+    /// Only present when the archive was exported with its secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    /// Only present when the archive was exported with its secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id_hash: Option<String>,
+    /// Derived from the name when the archive is imported.
+    #[serde(skip)]
+    pub canonical_name: String,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Selectable, Insertable, ToSchema)]
+#[diesel(table_name = score)]
+pub struct ArchivedScore {
+    pub id: Uuid,
+    pub username: Option<String>,
+    #[serde(rename = "score")]
+    pub highscore: i32,
+    pub is_hidden: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    pub level_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    /// Absent in archives that were exported before personal bests were stored separately.
+    #[serde(default)]
+    pub is_personal_best: bool,
+    /// Absent in archives that were exported before the moment a score was achieved was stored, in which case it
+    /// is derived from the moment the score was submitted or updated.
+    #[serde(default)]
+    #[diesel(select_expression = score::achieved_at.nullable())]
+    #[diesel(select_expression_type = diesel::dsl::Nullable<score::achieved_at>)]
+    pub achieved_at: Option<NaiveDateTime>,
+}
+
+/// The structure of the query parameters of an archive export.
+#[derive(Deserialize)]
+pub struct ArchiveExportQuery {
+    /// Adds the signing secret of the game and the credentials of the players to the archive, so game clients and
+    /// players keep working after the archive is imported on another server.
+    #[serde(default)]
+    pub include_secrets: bool,
+}
+
+/// The ids the game, levels, users and scores of an imported archive get.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveIds {
+    /// Every row gets a new id, so the same archive can be imported more than once.
+    #[default]
+    New,
+    /// Every row keeps the id of the archive, so references to the game from outside the server keep working.
+    Preserve,
+}
+
+/// What happens when a game with the name of the imported game already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NameConflict {
+    /// The import fails.
+    #[default]
+    Fail,
+    /// The imported game gets a numbered name, e.g. `Game (2)`.
+    Rename,
+    /// The existing game is deleted and replaced by the imported game. The deleted game is renamed like with
+    /// `Rename`, and can be restored until it is purged. Its ids remain in use, so an archive of the same game
+    /// replaces it only when imported with new ids.
+    Replace,
+}
+
+/// The structure of the query parameters of an archive import.
+#[derive(Deserialize)]
+pub struct ArchiveImportQuery {
+    #[serde(default)]
+    pub ids: ArchiveIds,
+    #[serde(default)]
+    pub on_conflict: NameConflict,
+}
+
+impl Validate for GameArchive {
+    /// Checks if the archive can be read by this server, the references between the rows are checked when the
+    /// archive is imported.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.version == 0 || self.version > ARCHIVE_VERSION {
+            errors.add(
+                "version",
+                ValidationError::new("version").with_message("is not supported by this server".into()),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl GameArchive {
+    /// Reads the game with the given id into an archive, without the deleted levels, users and scores.
+    ///
+    /// # Errors
+    /// - If no game is found with the given id.
+    pub fn load(game_id: Uuid, conn: &mut Connection) -> QueryResult<GameArchive> {
+        let game = game::table
+            .find(game_id)
+            .filter(game::deleted_at.is_null())
+            .select(ArchivedGame::as_select())
+            .get_result(conn)?;
+        let levels = level::table
+            .filter(level::game_id.eq(game_id))
+            .filter(level::deleted_at.is_null())
+            .order(level::created_at.asc())
+            .select(ArchivedLevel::as_select())
+            .load(conn)?;
+        let users = user::table
+            .filter(user::game_id.eq(game_id))
+            .filter(user::deleted_at.is_null())
+            .order(user::created_at.asc())
+            .select(ArchivedUser::as_select())
+            .load(conn)?;
+        let scores = score::table
+            .inner_join(level::table)
+            .filter(level::game_id.eq(game_id))
+            .filter(level::deleted_at.is_null())
+            .filter(score::deleted_at.is_null())
+            .order(score::created_at.asc())
+            .select(ArchivedScore::as_select())
+            .load(conn)?;
+
+        Ok(GameArchive {
+            version: ARCHIVE_VERSION,
+            exported_at: chrono::Utc::now().naive_utc(),
+            game,
+            levels,
+            users,
+            scores,
+        })
+    }
+
+    /// Checks if the game, or one of the levels, users or scores of the archive already exists, in which case
+    /// the archive cannot be imported with its own ids.
+    pub fn ids_exist(&self, conn: &mut Connection) -> QueryResult<bool> {
+        let level_ids: Vec<Uuid> = self.levels.iter().map(|level| level.id).collect();
+        let user_ids: Vec<Uuid> = self.users.iter().map(|user| user.id).collect();
+        let score_ids: Vec<Uuid> = self.scores.iter().map(|score| score.id).collect();
+
+        diesel::select(
+            diesel::dsl::exists(game::table.find(self.game.id))
+                .or(diesel::dsl::exists(level::table.filter(level::id.eq_any(level_ids))))
+                .or(diesel::dsl::exists(user::table.filter(user::id.eq_any(user_ids))))
+                .or(diesel::dsl::exists(score::table.filter(score::id.eq_any(score_ids)))),
+        )
+        .get_result(conn)
+    }
+
+    /// Adds the game of the archive with its levels, users and scores to the database. The rows are inserted in
+    /// batches, so large archives stay below the parameter limit of a single query.
+    ///
+    /// Errors
+    /// - If one of the ids or names is already in use.
+    /// - If one of the fields contain invalid data.
+    pub fn insert(&self, conn: &mut Connection) -> QueryResult<()> {
+        diesel::insert_into(game::table).values(&self.game).execute(conn)?;
+        for batch in self.levels.chunks(1000) {
+            diesel::insert_into(level::table).values(batch).execute(conn)?;
+        }
+        for batch in self.users.chunks(1000) {
+            diesel::insert_into(user::table).values(batch).execute(conn)?;
+        }
+        for batch in self.scores.chunks(1000) {
+            diesel::insert_into(score::table).values(batch).execute(conn)?;
+        }
+
+        Ok(())
+    }
+}
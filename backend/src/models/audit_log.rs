@@ -0,0 +1,168 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        auth::AuthUser,
+        pagination::{keyset, Cursor, Page, PageError, PageQuery},
+    },
+    schema::audit_log::{self, dsl::*},
+};
+
+text_enum! {
+    /// The type of mutation that is recorded in the audit log.
+    pub enum AuditAction {
+        Create => "create",
+        Update => "update",
+        Delete => "delete",
+        Restore => "restore",
+    }
+}
+
+text_enum! {
+    /// The type of entity a mutation in the audit log applies to.
+    pub enum AuditEntity {
+        Game => "game",
+        Level => "level",
+        Score => "score",
+        User => "user",
+        ApiKey => "api_key",
+        Season => "season",
+        Tournament => "tournament",
+    }
+}
+
+/// The client that made a request, recorded as the actor of a mutation.
+#[derive(Debug, Clone)]
+pub enum Actor {
+    /// A dashboard user, identified by the subject of their access token.
+    User(AuthUser),
+    /// A game client, identified by the id of its API key.
+    GameClient(Uuid),
+    /// A player signed in on a game client.
+    Player(Uuid),
+}
+
+#[derive(Serialize, Queryable, Selectable, Identifiable, ToSchema)]
+#[diesel(table_name = audit_log)]
+pub struct AuditLog {
+    pub id: Uuid,
+    /// The subject of the dashboard user that made the change.
+    pub actor: String,
+    pub action: AuditAction,
+    pub entity_type: AuditEntity,
+    pub entity_id: Uuid,
+    /// The entity before the change, empty when the entity was created.
+    pub before: Option<serde_json::Value>,
+    /// The entity after the change, empty when the entity was deleted.
+    pub after: Option<serde_json::Value>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditLog {
+    pub actor: String,
+    pub action: AuditAction,
+    pub entity_type: AuditEntity,
+    pub entity_id: Uuid,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// The filters of the audit log, every filter is optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub entity_type: Option<AuditEntity>,
+    pub entity_id: Option<Uuid>,
+    /// Only changes made at or after this moment.
+    pub from: Option<NaiveDateTime>,
+    /// Only changes made before this moment.
+    pub to: Option<NaiveDateTime>,
+}
+
+/// The fields a page of the audit log can be sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditSort {
+    #[default]
+    CreatedAt,
+}
+
+impl Actor {
+    /// Returns the value the actor is stored with in the audit log.
+    pub fn id(&self) -> String {
+        match self {
+            Actor::User(user) => user.sub.clone(),
+            Actor::GameClient(key_id) => format!("api_key:{}", key_id),
+            Actor::Player(user_id) => format!("player:{}", user_id),
+        }
+    }
+}
+
+impl AuditLog {
+    /// Fetches a single page of the audit log matching the given filter.
+    ///
+    /// # Errors
+    /// - If the cursor of the page is invalid.
+    pub fn find_page(
+        filter: &AuditFilter,
+        page: &PageQuery<AuditSort>,
+        conn: &mut Connection,
+    ) -> Result<Page<AuditLog>, PageError> {
+        let query = filtered(filter);
+        let query = match page.sort {
+            AuditSort::CreatedAt => keyset!(query, created_at, id, page.order, page.after::<NaiveDateTime>()?),
+        };
+
+        let entries = query
+            .select(AuditLog::as_select())
+            .limit(page.limit() + 1)
+            .load(conn)?;
+        let total = filtered(filter).count().get_result(conn)?;
+
+        Ok(Page::new(entries, page.limit(), total, |item| match page.sort {
+            AuditSort::CreatedAt => Cursor::encode(&item.created_at, item.id),
+        }))
+    }
+
+    /// Adds a new entry to the audit log.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn insert(data: NewAuditLog, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::insert_into(audit_log).values(&data).execute(conn)
+    }
+}
+
+/// Creates a query on the audit log with the given filter applied.
+fn filtered(filter: &AuditFilter) -> audit_log::BoxedQuery<'static, diesel::pg::Pg> {
+    let mut query = audit_log.into_boxed();
+
+    if let Some(value) = &filter.actor {
+        query = query.filter(actor.eq(value.clone()));
+    }
+    if let Some(value) = filter.action {
+        query = query.filter(action.eq(value));
+    }
+    if let Some(value) = filter.entity_type {
+        query = query.filter(entity_type.eq(value));
+    }
+    if let Some(value) = filter.entity_id {
+        query = query.filter(entity_id.eq(value));
+    }
+    if let Some(value) = filter.from {
+        query = query.filter(created_at.ge(value));
+    }
+    if let Some(value) = filter.to {
+        query = query.filter(created_at.lt(value));
+    }
+
+    query
+}
@@ -0,0 +1,47 @@
+use serde::Serialize;
+
+/// The roles a dashboard user can have, as given in the claims of their access token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    /// Has access to everything, including deleting games.
+    Admin,
+    /// Can create games, and manage the games they own with their levels, users, scores and API keys.
+    GameOwner,
+    /// Can update and delete the users and scores of every game.
+    Moderator,
+    /// Can only fetch data.
+    ReadOnly,
+}
+
+/// Every role, used for routes that any authenticated dashboard user may access.
+pub const ALL_ROLES: &[Role] = &[Role::Admin, Role::GameOwner, Role::Moderator, Role::ReadOnly];
+
+/// The dashboard user that authenticated a request with an OAuth2 access token.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    /// The subject of the access token.
+    pub sub: String,
+    pub roles: Vec<Role>,
+}
+
+impl Role {
+    /// Parses a role from the value in a token claim. Both `game-owner` and `game_owner` styles are accepted and
+    /// the comparison is case-insensitive.
+    pub fn from_claim(value: &str) -> Option<Role> {
+        match value.to_lowercase().replace('_', "-").as_str() {
+            "admin" => Some(Role::Admin),
+            "game-owner" => Some(Role::GameOwner),
+            "moderator" => Some(Role::Moderator),
+            "read-only" => Some(Role::ReadOnly),
+            _ => None,
+        }
+    }
+}
+
+impl AuthUser {
+    /// Checks if the user has at least one of the given roles.
+    pub fn has_any_role(&self, roles: &[Role]) -> bool {
+        self.roles.iter().any(|role| roles.contains(role))
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::{level::Level, score::Score, user::User};
+
+/// The file formats scores can be exported to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// A CSV file with a header row.
+    #[default]
+    Csv,
+    /// A JSON object per line.
+    Ndjson,
+    /// A CSV file that opens correctly in Excel: it starts with a byte order mark, uses CRLF line endings and text
+    /// that would be read as a formula is escaped.
+    Excel,
+}
+
+/// The structure of the query parameters of an export, every filter is optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Only scores submitted at or after this moment.
+    pub from: Option<NaiveDateTime>,
+    /// Only scores submitted before this moment.
+    pub to: Option<NaiveDateTime>,
+    /// If hidden scores should also be exported.
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// The scores an export contains.
+#[derive(Debug, Clone, Copy)]
+pub enum ExportScope {
+    Game(Uuid),
+    Level(Uuid),
+    User(Uuid),
+}
+
+/// A row of an export. The columns match those of a score import, so an export can be imported again.
+#[derive(Serialize)]
+pub struct ScoreExportRow {
+    pub id: Uuid,
+    pub level_id: Option<Uuid>,
+    pub level: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub user: Option<String>,
+    pub username: Option<String>,
+    pub score: i32,
+    pub is_hidden: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl ExportFormat {
+    /// Returns the content type of a file in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv | ExportFormat::Excel => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    /// Returns the extension of a file in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv | ExportFormat::Excel => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+impl From<(Score, Option<Level>, Option<User>)> for ScoreExportRow {
+    fn from(value: (Score, Option<Level>, Option<User>)) -> Self {
+        let (score, level, user) = value;
+
+        ScoreExportRow {
+            id: score.id,
+            level_id: score.level_id,
+            level: level.map(|level| level.name),
+            user_id: score.user_id,
+            user: user.map(|user| user.name),
+            username: score.username,
+            score: score.highscore,
+            is_hidden: score.is_hidden,
+            created_at: score.created_at,
+            updated_at: score.updated_at,
+        }
+    }
+}
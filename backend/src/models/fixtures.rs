@@ -0,0 +1,72 @@
+//! Records that are inserted in the test database by the tests that need a database.
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        game::{Game, GameDTO},
+        level::{Aggregation, Level, LevelForm, SortDirection},
+        score::Score,
+        user::{User, UserForm},
+    },
+    schema::score,
+};
+
+/// Inserts a game with a unique name.
+pub fn game(conn: &mut Connection) -> Game {
+    let name = format!("Game {}", &Uuid::new_v4().to_string()[..8]);
+    Game::insert(GameDTO { name, owner: None }, conn).unwrap()
+}
+
+/// Inserts a level in the given game with the given sort direction and aggregation.
+pub fn level(game: &Game, sort_direction: SortDirection, aggregation: Aggregation, conn: &mut Connection) -> Level {
+    Level::insert(
+        LevelForm {
+            name: "Level".to_string(),
+            game_id: game.id,
+            sort_direction: Some(sort_direction),
+            score_unit: None,
+            aggregation: Some(aggregation),
+            min_score: None,
+            max_score: None,
+            max_submissions: None,
+            submission_window: None,
+        },
+        conn,
+    )
+    .unwrap()
+}
+
+/// Inserts a user in the given game with the given name.
+pub fn user(game: &Game, name: &str, conn: &mut Connection) -> User {
+    User::insert(UserForm { name: name.to_string(), game_id: game.id }, &name.to_lowercase(), conn).unwrap()
+}
+
+/// Inserts a score of the given user on the given level, submitted at the given moment.
+pub fn score(
+    level: &Level,
+    user: Option<&User>,
+    highscore: i32,
+    created_at: NaiveDateTime,
+    conn: &mut Connection,
+) -> Score {
+    diesel::insert_into(score::table)
+        .values((
+            score::level_id.eq(level.id),
+            score::user_id.eq(user.map(|user| user.id)),
+            score::highscore.eq(highscore),
+            score::is_hidden.eq(false),
+            score::created_at.eq(created_at),
+            score::achieved_at.eq(created_at),
+        ))
+        .get_result(conn)
+        .unwrap()
+}
+
+/// The current moment, as it is stored in the database.
+pub fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
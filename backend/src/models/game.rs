@@ -1,12 +1,17 @@
-use chrono::NaiveDateTime;
-use diesel::{dsl::count_star, prelude::*, AsChangeset, Insertable, QueryDsl};
+use chrono::{NaiveDateTime, Utc};
+use diesel::{dsl::count_star, prelude::*, AsChangeset, Connection as _, Insertable, QueryDsl};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
     config::db::Connection,
-    schema::game::{self, dsl::*},
+    models::pagination::{keyset, Cursor, Page, PageError, PageQuery},
+    schema::{
+        game::{self, dsl::*},
+        level, score, user,
+    },
 };
 
 #[derive(Queryable, Serialize, Identifiable, Deserialize, Default, ToSchema)]
@@ -16,18 +21,59 @@ pub struct Game {
     pub name: String,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    /// The secret used to sign score submissions. Submissions don't need to be signed when no secret is set.
+    #[serde(skip)]
+    pub signing_secret: Option<String>,
+    /// The moment the game was deleted, empty when the game is not deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
+    /// The subject of the dashboard user that owns the game, empty when only admins can manage the game.
+    pub owner: Option<String>,
 }
 
-#[derive(Insertable, AsChangeset, Serialize, Deserialize, ToSchema)]
+#[derive(Insertable, AsChangeset, Serialize, Deserialize, ToSchema, Validate)]
 #[diesel(table_name = game)]
 pub struct GameDTO {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
     pub name: String,
+    /// The subject of the dashboard user that owns the game, which only admins can change. A new game is owned by
+    /// the user that created it when empty, an updated game keeps its owner.
+    pub owner: Option<String>,
+}
+
+/// The fields a page of games can be sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSort {
+    Name,
+    #[default]
+    CreatedAt,
 }
 
 impl Game {
     /// Fetches all the games in the database
     pub fn find_all(conn: &mut Connection) -> QueryResult<Vec<Game>> {
-        game.load::<Game>(conn)
+        game.filter(deleted_at.is_null()).load::<Game>(conn)
+    }
+
+    /// Fetches a single page of games from the database.
+    ///
+    /// # Errors
+    /// - If the cursor of the page is invalid.
+    pub fn find_page(page: &PageQuery<GameSort>, conn: &mut Connection) -> Result<Page<Game>, PageError> {
+        let query = game.filter(deleted_at.is_null()).into_boxed();
+        let query = match page.sort {
+            GameSort::Name => keyset!(query, name, id, page.order, page.after::<String>()?),
+            GameSort::CreatedAt => keyset!(query, created_at, id, page.order, page.after::<NaiveDateTime>()?),
+        };
+
+        let games = query.limit(page.limit() + 1).load::<Game>(conn)?;
+        let total = Game::count(conn)?;
+
+        Ok(Page::new(games, page.limit(), total, |item| match page.sort {
+            GameSort::Name => Cursor::encode(&item.name, item.id),
+            GameSort::CreatedAt => Cursor::encode(&item.created_at, item.id),
+        }))
     }
 
     /// Fetches a game from the database with the given id.
@@ -35,7 +81,24 @@ impl Game {
     /// # Errors
     /// - If no game is found with the given id.
     pub fn find_by_id(game_id: Uuid, conn: &mut Connection) -> QueryResult<Game> {
-        game.find(game_id).get_result::<Game>(conn)
+        game.find(game_id)
+            .filter(deleted_at.is_null())
+            .get_result::<Game>(conn)
+    }
+
+    /// Fetches a deleted game from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no deleted game is found with the given id.
+    pub fn find_deleted_by_id(game_id: Uuid, conn: &mut Connection) -> QueryResult<Game> {
+        game.find(game_id)
+            .filter(deleted_at.is_not_null())
+            .get_result::<Game>(conn)
+    }
+
+    /// Fetches the game with the given name, including a deleted game, which keeps its name until it is purged.
+    pub fn find_by_name(game_name: &str, conn: &mut Connection) -> QueryResult<Option<Game>> {
+        game.filter(name.eq(game_name)).first::<Game>(conn).optional()
     }
 
     /// Adds a new game to the database.
@@ -56,17 +119,124 @@ impl Game {
     pub fn update(model_id: Uuid, data: GameDTO, conn: &mut Connection) -> QueryResult<Game> {
         diesel::update(game)
             .filter(id.eq(model_id))
+            .filter(deleted_at.is_null())
             .set(data)
             .get_result::<Game>(conn)
     }
 
-    /// Deletes a game with the given id from the database.
+    /// Sets the secret used to sign score submissions of the game with the given id. Signing is disabled when
+    /// the secret is `None`.
+    ///
+    /// Errors
+    /// - If no game is found with the given id.
+    pub fn set_signing_secret(
+        model_id: Uuid,
+        secret: Option<String>,
+        conn: &mut Connection,
+    ) -> QueryResult<Game> {
+        diesel::update(game)
+            .filter(id.eq(model_id))
+            .filter(deleted_at.is_null())
+            .set(signing_secret.eq(secret))
+            .get_result::<Game>(conn)
+    }
+
+    /// Renames the game with the given id, including a deleted game.
+    ///
+    /// Errors
+    /// - If the name is already in use.
+    pub fn rename(model_id: Uuid, new_name: &str, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::update(game)
+            .filter(id.eq(model_id))
+            .set(name.eq(new_name))
+            .execute(conn)
+    }
+
+    /// Marks a game with the given id as deleted, together with its levels, users and scores. Everything is
+    /// marked with the same moment, so [`Game::restore`] only restores what was deleted with the game.
     pub fn delete(model_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
+        let now = Utc::now().naive_utc();
+
+        conn.transaction(|conn| {
+            let deleted = diesel::update(game)
+                .filter(id.eq(model_id))
+                .filter(deleted_at.is_null())
+                .set(deleted_at.eq(now))
+                .execute(conn)?;
+            if deleted == 0 {
+                return Ok(0);
+            }
+
+            let levels = level::table
+                .filter(level::game_id.eq(model_id))
+                .select(level::id.nullable());
+            diesel::update(score::table)
+                .filter(score::level_id.eq_any(levels))
+                .filter(score::deleted_at.is_null())
+                .set(score::deleted_at.eq(now))
+                .execute(conn)?;
+            diesel::update(level::table)
+                .filter(level::game_id.eq(model_id))
+                .filter(level::deleted_at.is_null())
+                .set(level::deleted_at.eq(now))
+                .execute(conn)?;
+            diesel::update(user::table)
+                .filter(user::game_id.eq(model_id))
+                .filter(user::deleted_at.is_null())
+                .set(user::deleted_at.eq(now))
+                .execute(conn)?;
+
+            Ok(deleted)
+        })
+    }
+
+    /// Restores a deleted game with the given id, together with the levels, users and scores that were deleted
+    /// with it.
+    pub fn restore(model_id: Uuid, conn: &mut Connection) -> QueryResult<Game> {
+        conn.transaction(|conn| {
+            let deleted = Game::find_deleted_by_id(model_id, conn)?;
+
+            let levels = level::table
+                .filter(level::game_id.eq(model_id))
+                .select(level::id.nullable());
+            diesel::update(score::table)
+                .filter(score::level_id.eq_any(levels))
+                .filter(score::deleted_at.eq(deleted.deleted_at))
+                .set(score::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+            diesel::update(level::table)
+                .filter(level::game_id.eq(model_id))
+                .filter(level::deleted_at.eq(deleted.deleted_at))
+                .set(level::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+            diesel::update(user::table)
+                .filter(user::game_id.eq(model_id))
+                .filter(user::deleted_at.eq(deleted.deleted_at))
+                .set(user::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+
+            diesel::update(game)
+                .filter(id.eq(model_id))
+                .set(deleted_at.eq(None::<NaiveDateTime>))
+                .get_result::<Game>(conn)
+        })
+    }
+
+    /// Permanently removes the game with the given id, together with its levels, users, scores and API keys.
+    pub fn delete_permanently(model_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
         diesel::delete(game).filter(id.eq(model_id)).execute(conn)
     }
 
+    /// Permanently removes the games that were deleted before the given moment. The levels, users and scores of
+    /// the games are removed by the database.
+    pub fn purge(before: NaiveDateTime, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(game).filter(deleted_at.lt(before)).execute(conn)
+    }
+
     /// Counts the number of games in the database.
     pub fn count(conn: &mut Connection) -> QueryResult<i64> {
-        game.select(count_star()).first(conn)
+        game.filter(deleted_at.is_null())
+            .select(count_star())
+            .first(conn)
     }
 }
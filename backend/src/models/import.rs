@@ -0,0 +1,108 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{response::FieldError, schema::score};
+
+/// A row of a score import. The level and the optional user are referenced by either their id or their name.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScoreImportRow {
+    /// The id or name of the level.
+    pub level: String,
+    /// The id or name of the user.
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    pub score: i32,
+    #[serde(default)]
+    pub is_hidden: Option<bool>,
+    /// The moment the score was submitted, the moment of the import when empty. A row with a moment is skipped
+    /// when the same score was already submitted at that moment, so an import can be repeated safely.
+    #[serde(default)]
+    pub created_at: Option<NaiveDateTime>,
+}
+
+/// A score of an import that passed validation.
+#[derive(Insertable)]
+#[diesel(table_name = score)]
+pub struct NewImportedScore {
+    pub username: Option<String>,
+    pub highscore: i32,
+    pub is_hidden: bool,
+    pub level_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub achieved_at: Option<NaiveDateTime>,
+}
+
+/// The structure of the query parameters of an import.
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    /// Validates the rows and returns the report without storing any score.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// What happened to a row of an import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Accepted,
+    /// The score already exists.
+    Skipped,
+    /// The row is invalid, see the message and errors of the row.
+    Rejected,
+}
+
+/// The result of a single row of an import.
+#[derive(Serialize, ToSchema)]
+pub struct ImportRowResult {
+    /// The number of the row, starting at 1 for the first row after the header.
+    pub row: usize,
+    pub status: ImportStatus,
+    /// The id of the stored score, empty when nothing was stored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// The report of an import, with the result of every row.
+#[derive(Serialize, ToSchema)]
+pub struct ImportReport {
+    pub accepted: usize,
+    pub skipped: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl ImportRowResult {
+    pub fn new(row: usize, status: ImportStatus) -> Self {
+        ImportRowResult {
+            row,
+            status,
+            score_id: None,
+            message: None,
+            errors: vec![],
+        }
+    }
+}
+
+impl ImportReport {
+    /// Creates the report from the results of the rows, counting the rows per status.
+    pub fn new(rows: Vec<ImportRowResult>) -> Self {
+        let count = |status| rows.iter().filter(|row| row.status == status).count();
+
+        ImportReport {
+            accepted: count(ImportStatus::Accepted),
+            skipped: count(ImportStatus::Skipped),
+            rejected: count(ImportStatus::Rejected),
+            rows,
+        }
+    }
+}
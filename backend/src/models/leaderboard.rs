@@ -0,0 +1,276 @@
+use chrono::{
+    DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::score::{RankedScore, ScoreDto};
+
+/// The default number of entries returned when no limit is given.
+pub const DEFAULT_LEADERBOARD_LIMIT: i64 = 25;
+
+/// The maximum number of entries that can be requested in a single leaderboard page.
+pub const MAX_LEADERBOARD_LIMIT: i64 = 100;
+
+/// The way ranks are assigned to scores that are tied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RankingMode {
+    /// Tied scores share a rank and the next rank is skipped, e.g. `1, 2, 2, 4`.
+    #[default]
+    Competition,
+    /// Tied scores share a rank and the next rank is not skipped, e.g. `1, 2, 2, 3`.
+    Dense,
+}
+
+/// The period of time of which the scores are ranked on a leaderboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardPeriod {
+    /// Every score is ranked, regardless of when it was achieved.
+    #[default]
+    AllTime,
+    /// The scores achieved since the end of the last archived season of the game, or every score when no season
+    /// has been archived.
+    Season,
+    /// The scores achieved on a single day, identified by a key like `2026-10-18`.
+    Daily,
+    /// The scores achieved in a single ISO week starting on Monday, identified by a key like `2026-W41`.
+    Weekly,
+    /// The scores achieved in a single month, identified by a key like `2026-10`.
+    Monthly,
+}
+
+/// The query parameters that can be used when fetching a leaderboard.
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub hidden: bool,
+    #[serde(default)]
+    pub ranking: RankingMode,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub user_id: Option<Uuid>,
+    #[serde(default)]
+    pub period: LeaderboardPeriod,
+    /// The key of a past period, defaults to the current period.
+    pub period_key: Option<String>,
+    /// The IANA name of the timezone in which the periods start at midnight, defaults to `UTC`.
+    pub timezone: Option<String>,
+}
+
+/// The boundaries of the period of a leaderboard. The moments are in UTC, the start is inclusive and the end is
+/// exclusive.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PeriodWindow {
+    pub period: LeaderboardPeriod,
+    pub key: String,
+    pub timezone: String,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+}
+
+/// A single ranked score on a leaderboard.
+#[derive(Serialize, ToSchema)]
+pub struct LeaderboardEntry {
+    pub rank: i64,
+    /// Calculated from the competition rank as `100 * (total - rank) / (total - 1)`, so the best score has a
+    /// percentile of 100 and the worst score a percentile of 0.
+    pub percentile: f64,
+    pub score: ScoreDto,
+}
+
+/// A window of ranked scores, together with the total number of scores on the leaderboard.
+#[derive(Serialize, ToSchema)]
+pub struct Leaderboard {
+    pub total: i64,
+    pub ranking: RankingMode,
+    /// The period of which the scores are ranked, absent for the all-time and season leaderboards.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<PeriodWindow>,
+    /// The end of the last archived season of the game, from which the season leaderboard ranks the scores.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_at: Option<NaiveDateTime>,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl LeaderboardEntry {
+    /// Creates the entry of a ranked score, using the rank of the given ranking mode. The percentile is calculated
+    /// from the competition rank and the total number of ranked scores.
+    pub fn new(ranked: RankedScore, ranking: RankingMode, total: i64) -> LeaderboardEntry {
+        LeaderboardEntry {
+            rank: match ranking {
+                RankingMode::Competition => ranked.competition_rank,
+                RankingMode::Dense => ranked.dense_rank,
+            },
+            percentile: percentile(ranked.competition_rank, total),
+            score: ranked.score,
+        }
+    }
+}
+
+impl PeriodWindow {
+    /// Determines the boundaries of the given period in the given timezone. The period with the given key is used,
+    /// or the period that contains the given moment when no key is given. Returns nothing for the all-time and season
+    /// periods.
+    ///
+    /// # Errors
+    /// - If the timezone is unknown.
+    /// - If a key is given for the all-time or season period, or the key doesn't match the format of the period.
+    pub fn resolve(
+        period: LeaderboardPeriod,
+        key: Option<&str>,
+        timezone: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<Option<PeriodWindow>, String> {
+        let timezone: Tz = match timezone {
+            Some(name) => name.parse().map_err(|_| format!("Unknown timezone '{}'", name))?,
+            None => Tz::UTC,
+        };
+
+        let today = now.with_timezone(&timezone).date_naive();
+        let (start, end) = match (period, key) {
+            (LeaderboardPeriod::AllTime | LeaderboardPeriod::Season, None) => return Ok(None),
+            (LeaderboardPeriod::AllTime, Some(_)) => {
+                return Err("A period key cannot be used with the all-time leaderboard".to_string())
+            }
+            (LeaderboardPeriod::Season, Some(_)) => {
+                return Err("A period key cannot be used with the season leaderboard".to_string())
+            }
+            (LeaderboardPeriod::Daily, key) => {
+                let day = match key {
+                    Some(key) => NaiveDate::parse_from_str(key, "%Y-%m-%d")
+                        .map_err(|_| "A daily period key must be formatted like '2026-10-18'".to_string())?,
+                    None => today,
+                };
+                (Some(day), day.checked_add_days(Days::new(1)))
+            }
+            (LeaderboardPeriod::Weekly, key) => {
+                let monday = match key {
+                    Some(key) => parse_week(key)
+                        .ok_or_else(|| "A weekly period key must be formatted like '2026-W41'".to_string())?,
+                    None => today.week(Weekday::Mon).first_day(),
+                };
+                (Some(monday), monday.checked_add_days(Days::new(7)))
+            }
+            (LeaderboardPeriod::Monthly, key) => {
+                let first = match key {
+                    Some(key) => Some(
+                        NaiveDate::parse_from_str(&format!("{}-01", key), "%Y-%m-%d")
+                            .map_err(|_| "A monthly period key must be formatted like '2026-10'".to_string())?,
+                    ),
+                    None => today.with_day(1),
+                };
+                (first, first.and_then(|first| first.checked_add_months(Months::new(1))))
+            }
+        };
+
+        let (Some(start), Some(end)) = (start, end) else {
+            return Err("The period is out of range".to_string());
+        };
+
+        let key = match period {
+            LeaderboardPeriod::Weekly => start.format("%G-W%V").to_string(),
+            LeaderboardPeriod::Monthly => start.format("%Y-%m").to_string(),
+            _ => start.format("%Y-%m-%d").to_string(),
+        };
+
+        Ok(Some(PeriodWindow {
+            period,
+            key,
+            timezone: timezone.name().to_string(),
+            starts_at: start_of_day(start, timezone),
+            ends_at: start_of_day(end, timezone),
+        }))
+    }
+}
+
+/// Calculates the percentile of the given competition rank, rounded to two decimals.
+fn percentile(rank: i64, total: i64) -> f64 {
+    if total > 1 {
+        let ratio = (total - rank) as f64 / (total - 1) as f64;
+        (ratio * 10_000.0).round() / 100.0
+    } else {
+        100.0
+    }
+}
+
+/// Parses an ISO week like `2026-W41` into the Monday the week starts on.
+fn parse_week(key: &str) -> Option<NaiveDate> {
+    let (year, week) = key.split_once("-W")?;
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, Weekday::Mon)
+}
+
+/// The moment in UTC the given day starts in the given timezone. When midnight is skipped by a change to daylight
+/// saving time, the day starts at the first moment after the change.
+fn start_of_day(day: NaiveDate, timezone: Tz) -> NaiveDateTime {
+    let mut local = day.and_time(NaiveTime::MIN);
+    loop {
+        if let Some(moment) = timezone.from_local_datetime(&local).earliest() {
+            return moment.naive_utc();
+        }
+        local += TimeDelta::minutes(15);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moment(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn resolve(period: LeaderboardPeriod, key: Option<&str>, timezone: Option<&str>) -> PeriodWindow {
+        let now = moment("2026-10-18 12:00").and_utc();
+        PeriodWindow::resolve(period, key, timezone, now).unwrap().unwrap()
+    }
+
+    #[test]
+    fn starts_a_day_without_midnight_after_the_daylight_saving_change() {
+        // Clocks in Chile skip from midnight to 01:00 when daylight saving time starts.
+        let window = resolve(LeaderboardPeriod::Daily, Some("2026-09-06"), Some("America/Santiago"));
+
+        assert_eq!(window.starts_at, moment("2026-09-06 04:00"));
+        assert_eq!(window.ends_at, moment("2026-09-07 03:00"));
+    }
+
+    #[test]
+    fn resolves_the_53rd_week_of_long_iso_years() {
+        let window = resolve(LeaderboardPeriod::Weekly, Some("2026-W53"), None);
+        assert_eq!((window.key.as_str(), window.starts_at), ("2026-W53", moment("2026-12-28 00:00")));
+        assert_eq!(window.ends_at, moment("2027-01-04 00:00"));
+
+        let window = resolve(LeaderboardPeriod::Weekly, Some("2020-W53"), None);
+        assert_eq!((window.key.as_str(), window.starts_at), ("2020-W53", moment("2020-12-28 00:00")));
+
+        let now = moment("2026-10-18 12:00").and_utc();
+        assert!(PeriodWindow::resolve(LeaderboardPeriod::Weekly, Some("2025-W53"), None, now).is_err());
+    }
+
+    #[test]
+    fn rolls_months_over_into_the_next_year_and_the_local_month() {
+        let window = resolve(LeaderboardPeriod::Monthly, Some("2026-12"), None);
+        assert_eq!(window.starts_at, moment("2026-12-01 00:00"));
+        assert_eq!(window.ends_at, moment("2027-01-01 00:00"));
+
+        // It is already February in Tokyo at the end of January in UTC.
+        let now = moment("2026-01-31 23:30").and_utc();
+        let window = PeriodWindow::resolve(LeaderboardPeriod::Monthly, None, Some("Asia/Tokyo"), now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(window.key, "2026-02");
+        assert_eq!(window.starts_at, moment("2026-01-31 15:00"));
+        assert_eq!(window.ends_at, moment("2026-02-28 15:00"));
+    }
+
+    #[test]
+    fn refuses_an_unknown_timezone() {
+        let now = moment("2026-10-18 12:00").and_utc();
+        let result = PeriodWindow::resolve(LeaderboardPeriod::Daily, None, Some("Mars/Olympus_Mons"), now);
+
+        assert_eq!(result.unwrap_err(), "Unknown timezone 'Mars/Olympus_Mons'");
+    }
+}
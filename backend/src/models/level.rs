@@ -1,16 +1,66 @@
-use chrono::NaiveDateTime;
-use diesel::prelude::*;
+use chrono::{NaiveDateTime, Utc};
+use diesel::{prelude::*, Connection as _};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
     config::db::Connection,
-    models::game::Game,
-    schema::level::{self, dsl::*},
+    models::{
+        game::Game,
+        pagination::{keyset, Cursor, Page, PageError, PageQuery},
+    },
+    schema::{
+        level::{self, dsl::*},
+        score,
+    },
 };
 
-#[derive(Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
+text_enum! {
+    /// Determines which scores are ranked first on the leaderboard of a level.
+    #[derive(Default)]
+    pub enum SortDirection {
+        #[default]
+        HigherIsBetter => "higher_is_better",
+        LowerIsBetter => "lower_is_better",
+    }
+}
+
+text_enum! {
+    /// The unit of the scores submitted for a level. Scores in milliseconds are durations, so they cannot be
+    /// negative.
+    #[derive(Default)]
+    pub enum ScoreUnit {
+        #[default]
+        Points => "points",
+        Milliseconds => "milliseconds",
+    }
+}
+
+text_enum! {
+    /// Determines which scores of a level are taken into account when ranking.
+    #[derive(Default)]
+    pub enum Aggregation {
+        /// Every submitted score is ranked.
+        #[default]
+        AllSubmissions => "all_submissions",
+        /// Only the best score of each user is ranked.
+        BestPerUser => "best_per_user",
+    }
+}
+
+impl SortDirection {
+    /// Checks if the `new` score is better than the `current` score.
+    pub fn is_better(&self, new: i32, current: i32) -> bool {
+        match self {
+            SortDirection::HigherIsBetter => new > current,
+            SortDirection::LowerIsBetter => new < current,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
 #[diesel(table_name = level)]
 #[diesel(belongs_to(Game))]
 pub struct Level {
@@ -19,19 +69,57 @@ pub struct Level {
     pub game_id: Uuid,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    pub sort_direction: SortDirection,
+    pub score_unit: ScoreUnit,
+    pub aggregation: Aggregation,
+    /// The lowest score that can be submitted, no lower bound when empty.
+    pub min_score: Option<i32>,
+    /// The highest score that can be submitted, no upper bound when empty.
+    pub max_score: Option<i32>,
+    /// The number of scores a user can submit within the submission window, unlimited when empty.
+    pub max_submissions: Option<i32>,
+    /// The length in seconds of the window in which submissions are counted.
+    pub submission_window: Option<i32>,
+    /// The moment the level was deleted, empty when the level is not deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
-#[derive(Insertable, AsChangeset, Serialize, Deserialize, ToSchema)]
+#[derive(Insertable, AsChangeset, Serialize, Deserialize, ToSchema, Validate)]
 #[diesel(table_name = level)]
+#[diesel(treat_none_as_null = true)]
 pub struct LevelForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
     pub name: String,
     pub game_id: Uuid,
+    /// Defaults to `higher_is_better` for a new level, and is left unchanged when omitted on an update.
+    #[diesel(treat_none_as_null = false)]
+    pub sort_direction: Option<SortDirection>,
+    /// Defaults to `points` for a new level, and is left unchanged when omitted on an update.
+    #[diesel(treat_none_as_null = false)]
+    pub score_unit: Option<ScoreUnit>,
+    /// Defaults to `all_submissions` for a new level, and is left unchanged when omitted on an update.
+    #[diesel(treat_none_as_null = false)]
+    pub aggregation: Option<Aggregation>,
+    pub min_score: Option<i32>,
+    pub max_score: Option<i32>,
+    pub max_submissions: Option<i32>,
+    pub submission_window: Option<i32>,
+}
+
+/// The fields a page of levels can be sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LevelSort {
+    Name,
+    #[default]
+    CreatedAt,
 }
 
 impl Level {
     /// Fetches all the levels in the database.
     pub fn find_all(conn: &mut Connection) -> QueryResult<Vec<Level>> {
-        level.load::<Level>(conn)
+        level.filter(deleted_at.is_null()).load::<Level>(conn)
     }
 
     /// Fetches a level from the database with the given id.
@@ -39,15 +127,60 @@ impl Level {
     /// # Errors
     /// - If no level is found with the given id.
     pub fn find_by_id(level_id: Uuid, conn: &mut Connection) -> QueryResult<Level> {
-        level.find(level_id).get_result::<Level>(conn)
+        level
+            .find(level_id)
+            .filter(deleted_at.is_null())
+            .get_result::<Level>(conn)
+    }
+
+    /// Fetches a deleted level from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no deleted level is found with the given id.
+    pub fn find_deleted_by_id(level_id: Uuid, conn: &mut Connection) -> QueryResult<Level> {
+        level
+            .find(level_id)
+            .filter(deleted_at.is_not_null())
+            .get_result::<Level>(conn)
     }
 
     /// Fetches levels related to the given game from the database.
     pub fn find_by_game(game: &Game, conn: &mut Connection) -> QueryResult<Vec<Level>> {
         Level::belonging_to(game)
+            .filter(deleted_at.is_null())
             .select(Level::as_select())
             .load(conn)
-    }    
+    }
+
+    /// Fetches a single page of levels related to the given game from the database.
+    ///
+    /// # Errors
+    /// - If the cursor of the page is invalid.
+    pub fn find_page_by_game(
+        game: &Game,
+        page: &PageQuery<LevelSort>,
+        conn: &mut Connection,
+    ) -> Result<Page<Level>, PageError> {
+        let query = Level::belonging_to(game).filter(deleted_at.is_null()).into_boxed();
+        let query = match page.sort {
+            LevelSort::Name => keyset!(query, name, id, page.order, page.after::<String>()?),
+            LevelSort::CreatedAt => keyset!(query, created_at, id, page.order, page.after::<NaiveDateTime>()?),
+        };
+
+        let levels = query
+            .select(Level::as_select())
+            .limit(page.limit() + 1)
+            .load(conn)?;
+        let total = Level::belonging_to(game)
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(conn)?;
+
+        Ok(Page::new(levels, page.limit(), total, |item| match page.sort {
+            LevelSort::Name => Cursor::encode(&item.name, item.id),
+            LevelSort::CreatedAt => Cursor::encode(&item.created_at, item.id),
+        }))
+    }
 
     /// Adds a new level to the database.
     /// 
@@ -67,12 +200,57 @@ impl Level {
     pub fn update(level_id: Uuid, data: LevelForm, conn: &mut Connection) -> QueryResult<Level> {
         diesel::update(level)
             .filter(id.eq(level_id))
+            .filter(deleted_at.is_null())
             .set(data)
             .get_result::<Level>(conn)
     }
 
-    /// Deletes a level with the given id from the database.
+    /// Marks a level with the given id as deleted, together with its scores.
     pub fn delete(level_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
-        diesel::delete(level).filter(id.eq(level_id)).execute(conn)
+        let now = Utc::now().naive_utc();
+
+        conn.transaction(|conn| {
+            let deleted = diesel::update(level)
+                .filter(id.eq(level_id))
+                .filter(deleted_at.is_null())
+                .set(deleted_at.eq(now))
+                .execute(conn)?;
+            if deleted == 0 {
+                return Ok(0);
+            }
+
+            diesel::update(score::table)
+                .filter(score::level_id.eq(level_id))
+                .filter(score::deleted_at.is_null())
+                .set(score::deleted_at.eq(now))
+                .execute(conn)?;
+
+            Ok(deleted)
+        })
+    }
+
+    /// Restores a deleted level with the given id, together with the scores that were deleted with it.
+    pub fn restore(level_id: Uuid, conn: &mut Connection) -> QueryResult<Level> {
+        conn.transaction(|conn| {
+            let deleted = Level::find_deleted_by_id(level_id, conn)?;
+
+            diesel::update(score::table)
+                .filter(score::level_id.eq(level_id))
+                .filter(score::deleted_at.eq(deleted.deleted_at))
+                .set(score::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+
+            diesel::update(level)
+                .filter(id.eq(level_id))
+                .set(deleted_at.eq(None::<NaiveDateTime>))
+                .get_result::<Level>(conn)
+        })
+    }
+
+    /// Permanently removes the levels that were deleted before the given moment, together with their scores.
+    pub fn purge(before: NaiveDateTime, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(level)
+            .filter(deleted_at.lt(before))
+            .execute(conn)
     }
 }
\ No newline at end of file
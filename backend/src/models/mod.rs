@@ -1,5 +1,91 @@
+/// Declares an enum that is stored as text in the database and as a snake case string in JSON. Every variant is
+/// mapped to the given string value in both representations.
+macro_rules! text_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident => $value:literal,
+            )+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            PartialEq,
+            Eq,
+            serde::Serialize,
+            serde::Deserialize,
+            utoipa::ToSchema,
+            diesel::AsExpression,
+            diesel::FromSqlRow,
+        )]
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                #[serde(rename = $value)]
+                $variant,
+            )+
+        }
+
+        impl $name {
+            /// Returns the value used to store the variant.
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $value,)+
+                }
+            }
+        }
+
+        impl diesel::serialize::ToSql<diesel::sql_types::Text, diesel::pg::Pg> for $name {
+            fn to_sql<'b>(
+                &'b self,
+                out: &mut diesel::serialize::Output<'b, '_, diesel::pg::Pg>,
+            ) -> diesel::serialize::Result {
+                use std::io::Write;
+
+                out.write_all(self.as_str().as_bytes())?;
+                Ok(diesel::serialize::IsNull::No)
+            }
+        }
+
+        impl diesel::deserialize::FromSql<diesel::sql_types::Text, diesel::pg::Pg> for $name {
+            fn from_sql(bytes: diesel::pg::PgValue<'_>) -> diesel::deserialize::Result<Self> {
+                match bytes.as_bytes() {
+                    $(value if value == $value.as_bytes() => Ok(Self::$variant),)+
+                    value => Err(format!(
+                        "Unknown {} value '{}'",
+                        stringify!($name),
+                        String::from_utf8_lossy(value)
+                    )
+                    .into()),
+                }
+            }
+        }
+    };
+}
+
+pub mod api_key;
+pub mod archive;
+pub mod audit_log;
+pub mod auth;
+pub mod export;
+#[cfg(test)]
+pub mod fixtures;
 pub mod game;
+pub mod import;
+pub mod leaderboard;
 pub mod level;
+pub mod pagination;
+pub mod player;
 pub mod score;
+pub mod score_attempt;
+pub mod season;
 pub mod stats;
+pub mod submission_nonce;
+pub mod tournament;
 pub mod user;
\ No newline at end of file
@@ -0,0 +1,151 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use diesel::result::Error;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The default number of items returned in a page when no limit is given.
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+
+/// The maximum number of items that can be requested in a single page.
+pub const MAX_PAGE_LIMIT: i64 = 500;
+
+/// Applies keyset pagination to a boxed query. The query is ordered by the given column, using the id column as
+/// tiebreaker, and filtered to only contain the rows after the given `(value, id)` position.
+macro_rules! keyset {
+    ($query:expr, $column:expr, $id:expr, $order:expr, $after:expr) => {{
+        let mut query = $query;
+
+        if let Some((value, last_id)) = $after {
+            query = match $order {
+                $crate::models::pagination::SortOrder::Asc => query.filter(
+                    $column
+                        .gt(value.clone())
+                        .or($column.eq(value).and($id.gt(last_id))),
+                ),
+                $crate::models::pagination::SortOrder::Desc => query.filter(
+                    $column
+                        .lt(value.clone())
+                        .or($column.eq(value).and($id.lt(last_id))),
+                ),
+            };
+        }
+
+        match $order {
+            $crate::models::pagination::SortOrder::Asc => query.order(($column.asc(), $id.asc())),
+            $crate::models::pagination::SortOrder::Desc => query.order(($column.desc(), $id.desc())),
+        }
+    }};
+}
+
+pub(crate) use keyset;
+
+/// The direction in which a page is sorted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// The structure of the query parameters that can be used to fetch a page of items. The type of `sort` is
+/// specific to the items that are fetched.
+#[derive(Deserialize)]
+pub struct PageQuery<S> {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sort: S,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// The position of the last item of a page. The cursor is encoded as an opaque string and sent to the client,
+/// which can use it to fetch the next page.
+#[derive(Serialize, Deserialize)]
+pub struct Cursor {
+    value: serde_json::Value,
+    id: Uuid,
+}
+
+/// The reasons fetching a page can fail. An invalid cursor is sent by the client, so it is kept apart from the
+/// errors of the database.
+#[derive(Debug)]
+pub enum PageError {
+    /// The cursor is malformed or its value doesn't match the type of the sort column.
+    InvalidCursor,
+    Query(Error),
+}
+
+/// A single page of items.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: i64,
+}
+
+/// The pagination metadata included in the response body of a paginated endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Pagination {
+    pub next_cursor: Option<String>,
+    pub total: i64,
+}
+
+impl<S> PageQuery<S> {
+    /// Returns the number of items that should be fetched, bounded by [`MAX_PAGE_LIMIT`].
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT)
+    }
+
+    /// Decodes the cursor, returning the value of the sort column and the id of the last item of the previous
+    /// page.
+    ///
+    /// # Errors
+    /// - If the cursor is malformed or its value doesn't match the type of the sort column.
+    pub fn after<T: DeserializeOwned>(&self) -> Result<Option<(T, Uuid)>, PageError> {
+        let Some(encoded) = &self.cursor else {
+            return Ok(None);
+        };
+
+        let bytes = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| PageError::InvalidCursor)?;
+        let cursor = serde_json::from_slice::<Cursor>(&bytes).map_err(|_| PageError::InvalidCursor)?;
+        let value = serde_json::from_value::<T>(cursor.value).map_err(|_| PageError::InvalidCursor)?;
+
+        Ok(Some((value, cursor.id)))
+    }
+}
+
+impl Cursor {
+    /// Creates an encoded cursor pointing at the item with the given sort value and id.
+    pub fn encode<T: Serialize>(value: &T, id: Uuid) -> String {
+        let cursor = Cursor {
+            value: serde_json::to_value(value).unwrap_or_default(),
+            id,
+        };
+
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap_or_default())
+    }
+}
+
+impl From<Error> for PageError {
+    fn from(value: Error) -> Self {
+        PageError::Query(value)
+    }
+}
+
+impl<T> Page<T> {
+    /// Creates a page from items that were fetched with a limit of one more than the page size. The extra item
+    /// is only used to determine if there is a next page, in which case a cursor is created from the last item
+    /// in the page.
+    pub fn new(mut items: Vec<T>, limit: i64, total: i64, cursor: impl Fn(&T) -> String) -> Self {
+        let has_next = items.len() as i64 > limit;
+        items.truncate(limit as usize);
+
+        Page {
+            next_cursor: if has_next { items.last().map(cursor) } else { None },
+            items,
+            total,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{prelude::*, Connection as _};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::db::Connection,
+    models::user::User,
+    schema::refresh_token::{self, dsl::*},
+};
+
+/// The credentials of a new player. A player registers with a password, a device id or both.
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct RegisterForm {
+    pub game_id: Uuid,
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
+    pub name: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub password: Option<String>,
+    /// A random identifier generated by the game on the device of the player.
+    #[validate(length(min = 16, max = 256, message = "must be between 16 and 256 characters"))]
+    pub device_id: Option<String>,
+}
+
+/// The credentials of a player, either a name and password or a device id.
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct LoginForm {
+    pub game_id: Uuid,
+    pub name: Option<String>,
+    pub password: Option<String>,
+    pub device_id: Option<String>,
+}
+
+/// The game a new guest plays.
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct GuestForm {
+    pub game_id: Uuid,
+}
+
+/// The name and password a guest chooses when it becomes a named player.
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpgradeForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
+    pub name: String,
+    #[validate(length(min = 8, max = 128, message = "must be between 8 and 128 characters"))]
+    pub password: String,
+}
+
+/// The device token of the guest whose scores are merged into the signed in player.
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct MergeForm {
+    #[validate(length(min = 1, message = "is required"))]
+    pub device_token: String,
+}
+
+/// The result of merging a guest into a named player.
+#[derive(Serialize, ToSchema)]
+pub struct MergeResult {
+    pub user: User,
+    /// The number of scores of the guest that were moved to the player.
+    pub moved_scores: usize,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct RefreshForm {
+    #[validate(length(min = 1, message = "is required"))]
+    pub refresh_token: String,
+}
+
+/// The tokens of an authenticated player. The access token is sent as bearer token together with the API key of
+/// the game, the refresh token is used to get a new access token when it expires.
+#[derive(Serialize, ToSchema)]
+pub struct PlayerSession {
+    pub user: User,
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// The number of seconds the access token is valid.
+    pub expires_in: u64,
+    pub refresh_token: String,
+    /// The generated device token of a new guest, which is only returned once. The guest signs in again by
+    /// sending it as `device_id`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_token: Option<String>,
+}
+
+/// The player that authenticated a request with an access token issued by BABS.
+#[derive(Debug, Clone, Copy)]
+pub struct Player {
+    pub user_id: Uuid,
+    pub game_id: Uuid,
+}
+
+/// The claims of a player access token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayerClaims {
+    pub sub: Uuid,
+    pub game_id: Uuid,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations)]
+#[diesel(table_name = refresh_token)]
+#[diesel(belongs_to(User))]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = refresh_token)]
+pub struct NewRefreshToken {
+    pub token_hash: String,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+}
+
+impl RefreshToken {
+    /// Fetches the refresh token with the given hash that is not revoked or expired.
+    ///
+    /// # Errors
+    /// - If no active refresh token is found with the given hash.
+    pub fn find_active_by_hash(hash: &str, conn: &mut Connection) -> QueryResult<RefreshToken> {
+        refresh_token
+            .filter(token_hash.eq(hash))
+            .filter(revoked_at.is_null())
+            .filter(expires_at.gt(Utc::now().naive_utc()))
+            .get_result::<RefreshToken>(conn)
+    }
+
+    /// Adds a new refresh token to the database.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn insert(data: NewRefreshToken, conn: &mut Connection) -> QueryResult<RefreshToken> {
+        diesel::insert_into(refresh_token)
+            .values(&data)
+            .get_result::<RefreshToken>(conn)
+    }
+
+    /// Revokes the refresh token with the given id.
+    pub fn revoke(token_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::update(refresh_token)
+            .filter(id.eq(token_id))
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    /// Revokes the refresh token with the given id and adds the given token as its replacement, in a single
+    /// transaction. Returns `false` when the token was already revoked by a concurrent request.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn rotate(token_id: Uuid, data: NewRefreshToken, conn: &mut Connection) -> QueryResult<bool> {
+        conn.transaction(|conn| {
+            let revoked = diesel::update(refresh_token)
+                .filter(id.eq(token_id))
+                .filter(revoked_at.is_null())
+                .set(revoked_at.eq(Utc::now().naive_utc()))
+                .execute(conn)?;
+            if revoked == 0 {
+                return Ok(false);
+            }
+
+            RefreshToken::insert(data, conn)?;
+            Ok(true)
+        })
+    }
+}
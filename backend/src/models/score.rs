@@ -1,12 +1,29 @@
-use chrono::NaiveDateTime;
-use diesel::{dsl::count_star, prelude::*, result::Error, AsChangeset, Insertable};
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::{
+    dsl::count_star,
+    pg::PgRowByRowLoadingMode,
+    prelude::*,
+    result::Error,
+    sql_types::{BigInt, Bool, Nullable, Timestamp},
+    AsChangeset, Connection as _, Insertable,
+};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::{Validate, ValidationErrors};
 
 use crate::{
     config::db::Connection,
-    models::{game::Game, level::Level, user::User},
+    models::{
+        export::{ExportQuery, ExportScope},
+        game::Game,
+        import::NewImportedScore,
+        level::{Aggregation, Level, SortDirection},
+        pagination::{keyset, Cursor, Page, PageError, PageQuery},
+        user::User,
+    },
     schema::{level, score, user},
 };
 
@@ -23,11 +40,17 @@ pub struct Score {
     pub updated_at: Option<NaiveDateTime>,
     pub level_id: Option<Uuid>,
     pub user_id: Option<Uuid>,
+    pub deleted_at: Option<NaiveDateTime>,
+    /// Whether the score is the personal best of its user, stored by a submission in the personal best mode.
+    pub is_personal_best: bool,
+    /// The moment the score was submitted, or the moment the personal best was last improved.
+    pub achieved_at: NaiveDateTime,
 }
 
-#[derive(Insertable, AsChangeset, Deserialize, ToSchema, Clone)]
+#[derive(Insertable, AsChangeset, Deserialize, ToSchema, Clone, Validate)]
 #[diesel(table_name = score)]
 pub struct ScoreForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
     pub username: Option<String>,
     #[serde(rename = "score")]
     pub highscore: i32,
@@ -46,11 +69,82 @@ pub struct ScoreDto {
     pub user: Option<User>,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    /// The moment the score was submitted, or the moment the personal best was last improved.
+    pub achieved_at: NaiveDateTime,
+}
+
+/// The signature of a score submission. The game client computes the signature as the hex encoded HMAC-SHA256
+/// of `{level_id}:{user_id}:{score}:{is_hidden}:{timestamp}:{nonce}:{username}`, using the signing secret of the
+/// game as key. The `user_id` is left empty when the score has no user, and is the id of the player when the score
+/// is submitted with a player access token. `is_hidden` is either `true` or `false`, and the `username` is left
+/// empty when the score has none. The username is signed last, because it is the only part that can contain a
+/// colon.
+#[derive(Deserialize, ToSchema, Clone)]
+pub struct SubmissionSignature {
+    /// Unix timestamp, in seconds, of the moment the score was signed.
+    pub timestamp: i64,
+    /// A random value that may only be used once, it cannot contain a colon.
+    pub nonce: String,
+    pub signature: String,
+}
+
+/// A score submitted by a game client. The signature is required when the game has a signing secret.
+#[derive(Deserialize, ToSchema)]
+pub struct ScoreSubmissionForm {
+    #[serde(flatten)]
+    pub score: ScoreForm,
+    #[serde(flatten)]
+    pub signature: Option<SubmissionSignature>,
+}
+
+/// The fields a page of scores can be sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreSort {
+    Score,
+    #[default]
+    CreatedAt,
+}
+
+/// The way a submitted score is stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionMode {
+    /// Every submission is stored as a new score.
+    #[default]
+    Insert,
+    /// Only the best score of a user on a level is stored, the score is replaced when a better score is
+    /// submitted.
+    PersonalBest,
+}
+
+/// The structure of the query parameters that can be used when submitting a score.
+#[derive(Deserialize)]
+pub struct SubmissionQuery {
+    #[serde(default)]
+    pub mode: SubmissionMode,
+}
+
+/// The result of submitting a score. The `personal_best` field is only present when the score was submitted
+/// in the [`SubmissionMode::PersonalBest`] mode.
+#[derive(Serialize, ToSchema)]
+pub struct ScoreSubmission {
+    #[serde(flatten)]
+    pub score: ScoreDto,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub personal_best: Option<bool>,
 }
 
-impl Into<ScoreDto> for (Score, Option<Level>, Option<User>) {
-    fn into(self) -> ScoreDto {
-        let (score, level, user) = self;
+impl Validate for ScoreSubmissionForm {
+    /// Validates the score of the submission, the signature is verified when the score is stored.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        self.score.validate()
+    }
+}
+
+impl From<(Score, Option<Level>, Option<User>)> for ScoreDto {
+    fn from(value: (Score, Option<Level>, Option<User>)) -> Self {
+        let (score, level, user) = value;
 
         ScoreDto {
             id: score.id,
@@ -61,16 +155,55 @@ impl Into<ScoreDto> for (Score, Option<Level>, Option<User>) {
             level,
             created_at: score.created_at,
             updated_at: score.updated_at,
+            achieved_at: score.achieved_at,
         }
     }
 }
 
+/// The scores that are ranked on the leaderboard of a level.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RankFilter {
+    pub include_hidden: bool,
+    /// Only the scores achieved from this moment are ranked, when given.
+    pub since: Option<NaiveDateTime>,
+    /// Only the scores achieved before this moment are ranked, when given.
+    pub until: Option<NaiveDateTime>,
+}
+
+/// A score together with its competition and dense rank on the leaderboard of its level.
+pub struct RankedScore {
+    pub score: ScoreDto,
+    pub competition_rank: i64,
+    pub dense_rank: i64,
+}
+
+/// The number of ranked scores on the leaderboard of a level, and the position of the best score of a user.
+#[derive(QueryableByName)]
+pub struct RankLookup {
+    #[diesel(sql_type = BigInt)]
+    pub total: i64,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    pub position: Option<i64>,
+}
+
+/// A single row of the ranking query.
+#[derive(QueryableByName)]
+struct RankedRow {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    id: Uuid,
+    #[diesel(sql_type = BigInt)]
+    competition_rank: i64,
+    #[diesel(sql_type = BigInt)]
+    dense_rank: i64,
+}
+
 impl Score {
     /// Fetches all the scores in the database by looking up all the levels related to the given game. And using
     /// the levels to fetch all the scores.
     pub fn find_all(game: &Game, conn: &mut Connection) -> Result<Vec<ScoreDto>, Error> {
         let levels = Level::find_by_game(game, conn)?;
         let scores = Score::belonging_to(&levels)
+            .filter(score::dsl::deleted_at.is_null())
             .left_join(user::table)
             .left_join(level::table)
             .select((Score::as_select(), Option::<Level>::as_select(), Option::<User>::as_select()))
@@ -82,12 +215,65 @@ impl Score {
         Ok(scores)
     }
 
+    /// Fetches a single page of the scores related to the levels of the given game.
+    ///
+    /// # Errors
+    /// - If the cursor of the page is invalid.
+    pub fn find_page_by_game(
+        game: &Game,
+        page: &PageQuery<ScoreSort>,
+        conn: &mut Connection,
+    ) -> Result<Page<ScoreDto>, PageError> {
+        let query = score::table
+            .filter(score::dsl::level_id.eq_any(Level::belonging_to(game).select(level::dsl::id.nullable())))
+            .filter(score::dsl::deleted_at.is_null())
+            .into_boxed();
+
+        let query = match page.sort {
+            ScoreSort::Score => keyset!(
+                query,
+                score::dsl::highscore,
+                score::dsl::id,
+                page.order,
+                page.after::<i32>()?
+            ),
+            ScoreSort::CreatedAt => keyset!(
+                query,
+                score::dsl::created_at,
+                score::dsl::id,
+                page.order,
+                page.after::<NaiveDateTime>()?
+            ),
+        };
+
+        let scores = query
+            .limit(page.limit() + 1)
+            .left_join(user::table)
+            .left_join(level::table)
+            .select((Score::as_select(), Option::<Level>::as_select(), Option::<User>::as_select()))
+            .load::<(Score, Option<Level>, Option<User>)>(conn)?
+            .into_iter()
+            .map(|item| item.into())
+            .collect::<Vec<ScoreDto>>();
+        let total = score::table
+            .filter(score::dsl::level_id.eq_any(Level::belonging_to(game).select(level::dsl::id.nullable())))
+            .filter(score::dsl::deleted_at.is_null())
+            .count()
+            .get_result(conn)?;
+
+        Ok(Page::new(scores, page.limit(), total, |item| match page.sort {
+            ScoreSort::Score => Cursor::encode(&item.score, item.id),
+            ScoreSort::CreatedAt => Cursor::encode(&item.created_at, item.id),
+        }))
+    }
+
     /// Fetches a score from the database with the given id.
     /// 
     /// # Errors
     /// - If no score is found with the given id.
     pub fn find_by_id(score_id: Uuid, conn: &mut Connection) -> Result<ScoreDto, Error> {
         let result = score::dsl::score.find(score_id)
+            .filter(score::dsl::deleted_at.is_null())
             .left_join(user::table)
             .left_join(level::table)
             .select((Score::as_select(), Option::<Level>::as_select(), Option::<User>::as_select()))
@@ -103,6 +289,7 @@ impl Score {
         conn: &mut Connection,
     ) -> Result<Vec<ScoreDto>, Error> {
         let mut query = Score::belonging_to(level)
+            .filter(score::dsl::deleted_at.is_null())
             .into_boxed();
 
         if !include_hidden {
@@ -121,6 +308,73 @@ impl Score {
         Ok(scores)
     }
 
+    /// Fetches a window of the ranked scores of the given level, from the best to the worst score according to
+    /// the sort direction of the level. The ranks are computed by the database, tied scores share a rank and are
+    /// ordered by the moment they were submitted. On a level that ranks only the best score per user, every
+    /// other score of the user is skipped. Every ranked score is returned when no limit is given.
+    pub fn find_ranked_by_level(
+        level: &Level,
+        filter: &RankFilter,
+        offset: i64,
+        limit: Option<i64>,
+        conn: &mut Connection,
+    ) -> QueryResult<Vec<RankedScore>> {
+        let rows = diesel::sql_query(format!(
+            "{} SELECT id, competition_rank, dense_rank FROM ranked WHERE position > $5 ORDER BY position LIMIT $6",
+            ranking_query(level)
+        ))
+        .bind::<diesel::sql_types::Uuid, _>(level.id)
+        .bind::<Bool, _>(filter.include_hidden)
+        .bind::<Nullable<Timestamp>, _>(filter.since)
+        .bind::<Nullable<Timestamp>, _>(filter.until)
+        .bind::<BigInt, _>(offset)
+        .bind::<Nullable<BigInt>, _>(limit)
+        .load::<RankedRow>(conn)?;
+
+        let ids = rows.iter().map(|row| row.id).collect::<Vec<Uuid>>();
+        let mut scores = score::table
+            .filter(score::dsl::id.eq_any(&ids))
+            .left_join(user::table)
+            .left_join(level::table)
+            .select((Score::as_select(), Option::<Level>::as_select(), Option::<User>::as_select()))
+            .load::<(Score, Option<Level>, Option<User>)>(conn)?
+            .into_iter()
+            .map(|item| (item.0.id, item.into()))
+            .collect::<HashMap<Uuid, ScoreDto>>();
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                scores.remove(&row.id).map(|score| RankedScore {
+                    score,
+                    competition_rank: row.competition_rank,
+                    dense_rank: row.dense_rank,
+                })
+            })
+            .collect())
+    }
+
+    /// Counts the ranked scores of the given level and looks up the position of the best score of the given
+    /// user, starting at 1. The position is empty when no user is given or the user has no ranked score.
+    pub fn lookup_rank(
+        level: &Level,
+        filter: &RankFilter,
+        user_id: Option<Uuid>,
+        conn: &mut Connection,
+    ) -> QueryResult<RankLookup> {
+        diesel::sql_query(format!(
+            "{} SELECT (SELECT COUNT(*) FROM ranked) AS total, \
+                (SELECT MIN(position) FROM ranked WHERE user_id = $5) AS position",
+            ranking_query(level)
+        ))
+        .bind::<diesel::sql_types::Uuid, _>(level.id)
+        .bind::<Bool, _>(filter.include_hidden)
+        .bind::<Nullable<Timestamp>, _>(filter.since)
+        .bind::<Nullable<Timestamp>, _>(filter.until)
+        .bind::<Nullable<diesel::sql_types::Uuid>, _>(user_id)
+        .get_result(conn)
+    }
+
     /// Fetches all the scores in the database related to the given user.
     pub fn find_by_user(
         user: &User,
@@ -128,6 +382,7 @@ impl Score {
         conn: &mut Connection,
     ) -> Result<Vec<ScoreDto>, Error> {
         let mut query = Score::belonging_to(user)
+            .filter(score::dsl::deleted_at.is_null())
             .into_boxed();
 
         if !include_hidden {
@@ -163,6 +418,237 @@ impl Score {
         Ok((inserted_score, Some(level), user).into())
     }
 
+    /// Adds the imported scores to the database, in batches so large imports stay below the parameter limit of
+    /// a single query.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn insert_many(new_scores: &[NewImportedScore], conn: &mut Connection) -> QueryResult<Vec<Score>> {
+        let mut inserted = Vec::with_capacity(new_scores.len());
+        for batch in new_scores.chunks(1000) {
+            inserted.extend(
+                diesel::insert_into(score::dsl::score)
+                    .values(batch)
+                    .returning(Score::as_returning())
+                    .get_results(conn)?,
+            );
+        }
+
+        Ok(inserted)
+    }
+
+    /// Fetches the scores on the given levels that were submitted at one of the given moments.
+    pub fn find_by_levels_submitted_at(
+        level_ids: &[Uuid],
+        moments: &[NaiveDateTime],
+        conn: &mut Connection,
+    ) -> QueryResult<Vec<Score>> {
+        score::dsl::score
+            .filter(score::dsl::level_id.eq_any(level_ids))
+            .filter(score::dsl::created_at.eq_any(moments))
+            .filter(score::dsl::deleted_at.is_null())
+            .select(Score::as_select())
+            .load(conn)
+    }
+
+    /// Reads the scores of the export one at a time, ordered by the moment they were submitted, and passes them to
+    /// the given function. Rows are fetched from the database while they are read, so an export of any size
+    /// doesn't have to fit in memory. Reading stops when the function returns `false`.
+    pub fn for_each_exported<F>(
+        scope: ExportScope,
+        filter: &ExportQuery,
+        conn: &mut Connection,
+        mut f: F,
+    ) -> QueryResult<()>
+    where
+        F: FnMut((Score, Option<Level>, Option<User>)) -> bool,
+    {
+        let mut query = score::table
+            .left_join(user::table)
+            .left_join(level::table)
+            .filter(score::dsl::deleted_at.is_null())
+            .into_boxed();
+
+        query = match scope {
+            ExportScope::Game(game_id) => query.filter(level::dsl::game_id.eq(game_id)),
+            ExportScope::Level(level_id) => query.filter(score::dsl::level_id.eq(level_id)),
+            ExportScope::User(user_id) => query.filter(score::dsl::user_id.eq(user_id)),
+        };
+        if let Some(from) = filter.from {
+            query = query.filter(score::dsl::created_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(score::dsl::created_at.lt(to));
+        }
+        if !filter.hidden {
+            query = query.filter(score::dsl::is_hidden.eq(false));
+        }
+
+        let rows = query
+            .order((score::dsl::created_at.asc(), score::dsl::id.asc()))
+            .select((Score::as_select(), Option::<Level>::as_select(), Option::<User>::as_select()))
+            .load_iter::<(Score, Option<Level>, Option<User>), PgRowByRowLoadingMode>(conn)?;
+
+        for row in rows {
+            if !f(row?) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the personal best of the given user on the given level. When a moment is given, only a personal
+    /// best achieved from that moment is fetched.
+    pub fn find_personal_best(
+        level: &Level,
+        user: &User,
+        since: Option<NaiveDateTime>,
+        conn: &mut Connection,
+    ) -> QueryResult<Option<Score>> {
+        let mut query = Score::belonging_to(level)
+            .filter(score::dsl::user_id.eq(user.id))
+            .filter(score::dsl::is_personal_best.eq(true))
+            .filter(score::dsl::deleted_at.is_null())
+            .into_boxed();
+
+        if let Some(since) = since {
+            query = query.filter(score::dsl::achieved_at.ge(since));
+        }
+
+        query.select(Score::as_select()).first(conn).optional()
+    }
+
+    /// Turns the personal bests of the given users that were achieved before the given moment into regular scores.
+    /// The personal bests of an archived season stay on its standings, and the user gets a new personal best in
+    /// the next season.
+    fn end_personal_bests(
+        user_ids: &[Uuid],
+        since: Option<NaiveDateTime>,
+        conn: &mut Connection,
+    ) -> QueryResult<usize> {
+        let Some(since) = since else { return Ok(0) };
+
+        diesel::update(score::dsl::score)
+            .filter(score::dsl::user_id.eq_any(user_ids))
+            .filter(score::dsl::is_personal_best.eq(true))
+            .filter(score::dsl::achieved_at.lt(since))
+            .set(score::dsl::is_personal_best.eq(false))
+            .execute(conn)
+    }
+
+    /// Stores the given score as the personal best of the user on the level. If the user already has a personal
+    /// best on the level, it is only replaced when the new score is better according to the sort direction of the
+    /// level. Scores submitted in the insert mode are never replaced. Returns the stored score and whether the
+    /// submitted score is a new personal best.
+    ///
+    /// When the moment of the last reset of the leaderboards is given, a personal best achieved before it is kept
+    /// as a regular score and a new personal best is stored, so the user is ranked on the live leaderboard again.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn upsert_best(
+        new_score: ScoreForm,
+        level: Level,
+        user: User,
+        since: Option<NaiveDateTime>,
+        conn: &mut Connection,
+    ) -> Result<(ScoreDto, bool), Error> {
+        conn.transaction(|conn| {
+            // Lock the user so concurrent submissions of the same user are handled one at a time.
+            user::table
+                .find(user.id)
+                .select(user::dsl::id)
+                .for_update()
+                .first::<Uuid>(conn)?;
+
+            Score::end_personal_bests(&[user.id], since, conn)?;
+            let (stored_score, is_personal_best) = match Score::find_personal_best(&level, &user, since, conn)? {
+                Some(current) if !level.sort_direction.is_better(new_score.highscore, current.highscore) => {
+                    (current, false)
+                }
+                Some(current) => {
+                    let updated_score = diesel::update(score::dsl::score)
+                        .filter(score::dsl::id.eq(current.id))
+                        .set((&new_score, score::dsl::achieved_at.eq(Utc::now().naive_utc())))
+                        .get_result::<Score>(conn)?;
+
+                    (updated_score, true)
+                }
+                None => {
+                    let inserted_score = diesel::insert_into(score::dsl::score)
+                        .values((&new_score, score::dsl::is_personal_best.eq(true)))
+                        .get_result::<Score>(conn)?;
+
+                    (inserted_score, true)
+                }
+            };
+
+            Ok(((stored_score, Some(level), Some(user)).into(), is_personal_best))
+        })
+    }
+
+    /// Moves every score of the guest to the given user and marks the guest as deleted. The better personal best of
+    /// both users stays the personal best on every level, the other scores of both users are kept as regular
+    /// scores. Returns the number of moved scores.
+    ///
+    /// When the moment of the last reset of the leaderboards is given, only the personal bests achieved from that
+    /// moment are compared, the scores of archived seasons are moved as they are.
+    ///
+    /// Errors
+    /// - If one of the users no longer exists.
+    pub fn merge_users(
+        guest: &User,
+        target: &User,
+        since: Option<NaiveDateTime>,
+        conn: &mut Connection,
+    ) -> Result<usize, Error> {
+        conn.transaction(|conn| {
+            // Lock both users, so scores submitted during the merge are not lost.
+            let locked = user::table
+                .filter(user::dsl::id.eq_any([guest.id, target.id]))
+                .filter(user::dsl::deleted_at.is_null())
+                .select(user::dsl::id)
+                .for_update()
+                .load::<Uuid>(conn)?;
+            if locked.len() != 2 {
+                return Err(Error::NotFound);
+            }
+
+            // A user has a single personal best on a level, so the worse personal best of both users becomes a
+            // regular score.
+            Score::end_personal_bests(&[guest.id, target.id], since, conn)?;
+            let guest_bests = Score::belonging_to(guest)
+                .filter(score::dsl::is_personal_best.eq(true))
+                .filter(score::dsl::deleted_at.is_null())
+                .select(Score::as_select())
+                .load::<Score>(conn)?;
+            for guest_best in guest_bests {
+                let Some(level_id) = guest_best.level_id else { continue };
+                let level = level::table.find(level_id).get_result::<Level>(conn)?;
+                let Some(target_best) = Score::find_personal_best(&level, target, since, conn)? else { continue };
+
+                let worse = if level.sort_direction.is_better(guest_best.highscore, target_best.highscore) {
+                    target_best.id
+                } else {
+                    guest_best.id
+                };
+                diesel::update(score::dsl::score.find(worse))
+                    .set(score::dsl::is_personal_best.eq(false))
+                    .execute(conn)?;
+            }
+
+            let moved = diesel::update(score::dsl::score)
+                .filter(score::dsl::user_id.eq(guest.id))
+                .set(score::dsl::user_id.eq(target.id))
+                .execute(conn)?;
+
+            User::delete(guest.id, conn)?;
+
+            Ok(moved)
+        })
+    }
+
     /// Updates a score with the given id in the database.
     /// 
     /// Errors
@@ -175,6 +661,7 @@ impl Score {
     ) -> Result<ScoreDto, Error> {
         let score = diesel::update(score::dsl::score)
             .filter(score::dsl::id.eq(score_id))
+            .filter(score::dsl::deleted_at.is_null())
             .set(updated_score.clone())
             .get_result::<Score>(conn)?;
 
@@ -186,10 +673,45 @@ impl Score {
         Ok((score, Some(level), user).into())
     }
 
-    /// Deletes multiple scores from the database with the given ids.
+    /// Marks multiple scores with the given ids as deleted.
     pub fn delete_many(score_ids: Vec<Uuid>, conn: &mut Connection) -> QueryResult<usize> {
-        diesel::delete(score::dsl::score)
+        diesel::update(score::dsl::score)
             .filter(score::dsl::id.eq_any(score_ids))
+            .filter(score::dsl::deleted_at.is_null())
+            .set(score::dsl::deleted_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+
+    /// Fetches a deleted score from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no deleted score is found with the given id.
+    pub fn find_deleted_by_id(score_id: Uuid, conn: &mut Connection) -> QueryResult<Score> {
+        score::dsl::score
+            .find(score_id)
+            .filter(score::dsl::deleted_at.is_not_null())
+            .select(Score::as_select())
+            .get_result(conn)
+    }
+
+    /// Restores a deleted score with the given id.
+    ///
+    /// # Errors
+    /// - If no deleted score is found with the given id.
+    pub fn restore(score_id: Uuid, conn: &mut Connection) -> Result<ScoreDto, Error> {
+        diesel::update(score::dsl::score)
+            .filter(score::dsl::id.eq(score_id))
+            .filter(score::dsl::deleted_at.is_not_null())
+            .set(score::dsl::deleted_at.eq(None::<NaiveDateTime>))
+            .execute(conn)?;
+
+        Score::find_by_id(score_id, conn)
+    }
+
+    /// Permanently removes the scores that were deleted before the given moment.
+    pub fn purge(before: NaiveDateTime, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(score::dsl::score)
+            .filter(score::dsl::deleted_at.lt(before))
             .execute(conn)
     }
 
@@ -202,12 +724,252 @@ impl Score {
                 .load(conn)?;
 
             Score::belonging_to(&levels)
+                .filter(score::dsl::deleted_at.is_null())
                 .select(count_star())
                 .first(conn)?
         } else {
-            score::dsl::score.select(count_star()).first(conn)?
+            score::dsl::score
+                .filter(score::dsl::deleted_at.is_null())
+                .select(count_star())
+                .first(conn)?
         };
 
         Ok(count)
     }
-}
\ No newline at end of file
+}
+
+/// Builds the common table expressions that rank the scores of the given level in a `ranked` table, with the
+/// competition rank, dense rank and position of each score. The query expects the level id, whether hidden scores
+/// are ranked, and the optional start and end of the ranked period as the first four parameters.
+fn ranking_query(level: &Level) -> String {
+    let direction = match level.sort_direction {
+        SortDirection::HigherIsBetter => "DESC",
+        SortDirection::LowerIsBetter => "ASC",
+    };
+    let aggregation = match level.aggregation {
+        Aggregation::AllSubmissions => "TRUE",
+        Aggregation::BestPerUser => "user_position = 1",
+    };
+
+    // Scores without a user are grouped by their username, and scores without either are never grouped.
+    format!(
+        "WITH eligible AS ( \
+            SELECT id, user_id, score.score AS highscore, achieved_at, ROW_NUMBER() OVER ( \
+                PARTITION BY COALESCE(user_id::text, 'username:' || username, id::text) \
+                ORDER BY score.score {direction}, achieved_at ASC, id ASC \
+            ) AS user_position \
+            FROM score \
+            WHERE level_id = $1 AND deleted_at IS NULL AND ($2 OR NOT is_hidden) \
+                AND ($3::timestamp IS NULL OR achieved_at >= $3) AND ($4::timestamp IS NULL OR achieved_at < $4) \
+        ), ranked AS ( \
+            SELECT id, user_id, \
+                RANK() OVER (ORDER BY highscore {direction}) AS competition_rank, \
+                DENSE_RANK() OVER (ORDER BY highscore {direction}) AS dense_rank, \
+                ROW_NUMBER() OVER (ORDER BY highscore {direction}, achieved_at ASC, id ASC) AS position \
+            FROM eligible \
+            WHERE {aggregation} \
+        )"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::{
+        config::db::test_connection,
+        models::{
+            fixtures,
+            season::{Season, SeasonForm},
+        },
+    };
+
+    /// Fetches every ranked score of the level as `(score, competition rank, dense rank)`.
+    fn ranks(level: &Level, conn: &mut Connection) -> Vec<(i32, i64, i64)> {
+        Score::find_ranked_by_level(level, &RankFilter::default(), 0, None, conn)
+            .unwrap()
+            .into_iter()
+            .map(|ranked| (ranked.score.score, ranked.competition_rank, ranked.dense_rank))
+            .collect()
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn ranks_tied_scores_by_competition_and_dense_ranking() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::AllSubmissions, &mut conn);
+        for highscore in [50, 100, 80, 80, 80] {
+            fixtures::score(&level, None, highscore, fixtures::now(), &mut conn);
+        }
+
+        assert_eq!(
+            ranks(&level, &mut conn),
+            vec![(100, 1, 1), (80, 2, 2), (80, 2, 2), (80, 2, 2), (50, 5, 3)]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn ranks_lowest_score_first_when_lower_is_better() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::LowerIsBetter, Aggregation::AllSubmissions, &mut conn);
+        for highscore in [300, 100, 200, 100] {
+            fixtures::score(&level, None, highscore, fixtures::now(), &mut conn);
+        }
+
+        assert_eq!(ranks(&level, &mut conn), vec![(100, 1, 1), (100, 1, 1), (200, 3, 2), (300, 4, 3)]);
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn ranks_only_best_score_per_user() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::BestPerUser, &mut conn);
+        let alice = fixtures::user(&game, "Alice", &mut conn);
+        let bob = fixtures::user(&game, "Bob", &mut conn);
+        for (user, highscore) in [(&alice, 10), (&alice, 30), (&bob, 20), (&alice, 20)] {
+            fixtures::score(&level, Some(user), highscore, fixtures::now(), &mut conn);
+        }
+
+        assert_eq!(ranks(&level, &mut conn), vec![(30, 1, 1), (20, 2, 2)]);
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn orders_tied_scores_by_submission_and_looks_up_position() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::AllSubmissions, &mut conn);
+        let alice = fixtures::user(&game, "Alice", &mut conn);
+        let bob = fixtures::user(&game, "Bob", &mut conn);
+        let started_at = fixtures::now() - TimeDelta::hours(1);
+        fixtures::score(&level, Some(&bob), 10, started_at + TimeDelta::minutes(2), &mut conn);
+        fixtures::score(&level, Some(&alice), 10, started_at + TimeDelta::minutes(1), &mut conn);
+        fixtures::score(&level, None, 5, started_at, &mut conn);
+
+        let lookup = Score::lookup_rank(&level, &RankFilter::default(), Some(bob.id), &mut conn).unwrap();
+        assert_eq!((lookup.total, lookup.position), (3, Some(2)));
+
+        let page = Score::find_ranked_by_level(&level, &RankFilter::default(), 1, Some(1), &mut conn).unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].score.user.as_ref().map(|user| user.id), Some(bob.id));
+        assert_eq!(page[0].competition_rank, 1);
+    }
+
+    /// Builds a personal best submission of the given user.
+    fn submission(level: &Level, user: &User, highscore: i32) -> ScoreForm {
+        ScoreForm {
+            username: None,
+            highscore,
+            is_hidden: false,
+            level_id: level.id,
+            user_id: Some(user.id),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn keeps_a_single_personal_best_per_user() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::LowerIsBetter, Aggregation::AllSubmissions, &mut conn);
+        let user = fixtures::user(&game, "Alice", &mut conn);
+        fixtures::score(&level, Some(&user), 50, fixtures::now(), &mut conn);
+
+        let (first, is_first_best) =
+            Score::upsert_best(submission(&level, &user, 300), level.clone(), user.clone(), None, &mut conn).unwrap();
+        let (worse, is_worse_best) =
+            Score::upsert_best(submission(&level, &user, 400), level.clone(), user.clone(), None, &mut conn).unwrap();
+        let (better, is_better_best) =
+            Score::upsert_best(submission(&level, &user, 200), level.clone(), user.clone(), None, &mut conn).unwrap();
+
+        assert_eq!((is_first_best, is_worse_best, is_better_best), (true, false, true));
+        assert_eq!((worse.id, worse.score), (first.id, 300));
+        assert_eq!((better.id, better.score), (first.id, 200));
+        // The score submitted in the insert mode is kept next to the personal best.
+        assert_eq!(Score::find_by_user(&user, false, &mut conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn stores_a_new_personal_best_after_an_archived_season() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::BestPerUser, &mut conn);
+        let user = fixtures::user(&game, "Alice", &mut conn);
+        let (season_best, _) =
+            Score::upsert_best(submission(&level, &user, 300), level.clone(), user.clone(), None, &mut conn).unwrap();
+
+        // The personal best was achieved during a season that has been archived since.
+        let starts_at = fixtures::now() - TimeDelta::hours(2);
+        diesel::update(score::table.find(season_best.id))
+            .set(score::achieved_at.eq(starts_at + TimeDelta::minutes(30)))
+            .execute(&mut conn)
+            .unwrap();
+        let season = Season::insert(
+            SeasonForm {
+                name: "Season 1".to_string(),
+                game_id: game.id,
+                starts_at,
+                ends_at: starts_at + TimeDelta::hours(1),
+            },
+            &mut conn,
+        )
+        .unwrap();
+        Season::archive(season.id, &[], &mut conn).unwrap();
+        let reset_at = Season::last_reset(game.id, &mut conn).unwrap();
+
+        let (best, is_best) =
+            Score::upsert_best(submission(&level, &user, 100), level.clone(), user.clone(), reset_at, &mut conn)
+                .unwrap();
+        let (worse, is_worse_best) =
+            Score::upsert_best(submission(&level, &user, 50), level.clone(), user.clone(), reset_at, &mut conn)
+                .unwrap();
+
+        assert_eq!((is_best, is_worse_best), (true, false));
+        assert_ne!(best.id, season_best.id);
+        assert_eq!((worse.id, worse.score), (best.id, 100));
+        // The personal best of the archived season is kept as a regular score, and the user is ranked again.
+        assert_eq!(Score::find_by_id(season_best.id, &mut conn).unwrap().score, 300);
+        let filter = RankFilter { since: reset_at, ..RankFilter::default() };
+        let live: Vec<i32> = Score::find_ranked_by_level(&level, &filter, 0, None, &mut conn)
+            .unwrap()
+            .into_iter()
+            .map(|ranked| ranked.score.score)
+            .collect();
+        assert_eq!(live, vec![100]);
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn merges_a_guest_into_a_user() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::BestPerUser, &mut conn);
+        let guest = fixtures::user(&game, "Guest", &mut conn);
+        let user = fixtures::user(&game, "Alice", &mut conn);
+        let (guest_best, _) =
+            Score::upsert_best(submission(&level, &guest, 300), level.clone(), guest.clone(), None, &mut conn).unwrap();
+        let (user_best, _) =
+            Score::upsert_best(submission(&level, &user, 200), level.clone(), user.clone(), None, &mut conn).unwrap();
+        fixtures::score(&level, Some(&guest), 100, fixtures::now(), &mut conn);
+
+        let moved = Score::merge_users(&guest, &user, None, &mut conn).unwrap();
+
+        // Every score of both users is kept, only the better personal best stays the personal best.
+        assert_eq!(moved, 2);
+        let kept = Score::find_by_user(&user, false, &mut conn).unwrap();
+        assert_eq!(kept.len(), 3);
+        let old_best = score::dsl::score.find(user_best.id).select(Score::as_select()).first(&mut conn).unwrap();
+        assert_eq!((old_best.highscore, old_best.is_personal_best, old_best.deleted_at), (200, false, None));
+        let best = Score::find_personal_best(&level, &user, None, &mut conn).unwrap().unwrap();
+        assert_eq!(best.id, guest_best.id);
+        // The guest is only marked as deleted.
+        assert!(User::find_by_id(guest.id, &mut conn).is_err());
+        assert!(User::find_deleted_by_id(guest.id, &mut conn).is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::{TimeDelta, Utc};
+use diesel::{
+    prelude::*,
+    sql_types::{Text, Timestamp},
+    Connection as _,
+};
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{level::Level, score::ScoreForm},
+    schema::score_attempt::{self, dsl::*},
+};
+
+/// A score submitted on a level with a submission limit. Attempts are stored separately from the scores, because a
+/// personal best submission that doesn't beat the stored best is not stored as a score, but counts towards the
+/// limit as well.
+#[derive(Insertable)]
+#[diesel(table_name = score_attempt)]
+pub struct ScoreAttempt {
+    pub level_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub username: Option<String>,
+}
+
+impl ScoreAttempt {
+    /// Records an attempt of the submitter of the score on the level, unless the submitter already made the given
+    /// number of attempts in the last given number of seconds. The submitter is the user of the score, or the
+    /// username when the score has no user. Returns whether the attempt was recorded.
+    ///
+    /// The submitter is locked until the surrounding transaction ends, so concurrent submissions of the same
+    /// submitter are counted one at a time and cannot exceed the limit together.
+    ///
+    /// Errors
+    /// - If the level no longer exists.
+    pub fn record(
+        level: &Level,
+        new_score: &ScoreForm,
+        window: i32,
+        max_attempts: i32,
+        conn: &mut Connection,
+    ) -> QueryResult<bool> {
+        conn.transaction(|conn| {
+            let submitter = match (new_score.user_id, &new_score.username) {
+                (Some(submitter_id), _) => format!("{}:user:{}", level.id, submitter_id),
+                (None, Some(name)) => format!("{}:username:{}", level.id, name),
+                (None, None) => format!("{}:anonymous", level.id),
+            };
+            diesel::sql_query("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))")
+                .bind::<Text, _>(submitter)
+                .execute(conn)?;
+
+            let now = Utc::now().naive_utc();
+            let mut query = score_attempt
+                .filter(level_id.eq(level.id))
+                .filter(created_at.gt(now - TimeDelta::seconds(window.into())))
+                .into_boxed();
+            query = match (new_score.user_id, &new_score.username) {
+                (Some(submitter_id), _) => query.filter(user_id.eq(submitter_id)),
+                (None, Some(name)) => query.filter(username.eq(name)),
+                (None, None) => query.filter(user_id.is_null().and(username.is_null())),
+            };
+            if query.count().get_result::<i64>(conn)? >= max_attempts as i64 {
+                return Ok(false);
+            }
+
+            diesel::insert_into(score_attempt)
+                .values((
+                    ScoreAttempt {
+                        level_id: level.id,
+                        user_id: new_score.user_id,
+                        username: new_score.username.clone(),
+                    },
+                    created_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            Ok(true)
+        })
+    }
+
+    /// Deletes the attempts that are older than the submission window of their level, or made on a level that no
+    /// longer has a submission limit.
+    pub fn purge(conn: &mut Connection) -> QueryResult<usize> {
+        diesel::sql_query(
+            "DELETE FROM score_attempt USING level \
+             WHERE score_attempt.level_id = level.id \
+             AND (level.submission_window IS NULL \
+             OR score_attempt.created_at < $1 - make_interval(secs => level.submission_window))",
+        )
+        .bind::<Timestamp, _>(Utc::now().naive_utc())
+        .execute(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::db::test_connection,
+        models::{
+            fixtures,
+            level::{Aggregation, SortDirection},
+        },
+    };
+
+    fn submission(level: &Level, user: Option<Uuid>) -> ScoreForm {
+        ScoreForm {
+            username: None,
+            highscore: 10,
+            is_hidden: false,
+            level_id: level.id,
+            user_id: user,
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn refuses_attempts_over_the_limit_per_submitter() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::BestPerUser, &mut conn);
+        let alice = fixtures::user(&game, "Alice", &mut conn);
+        let bob = fixtures::user(&game, "Bob", &mut conn);
+
+        let attempt = submission(&level, Some(alice.id));
+        assert!(ScoreAttempt::record(&level, &attempt, 60, 2, &mut conn).unwrap());
+        assert!(ScoreAttempt::record(&level, &attempt, 60, 2, &mut conn).unwrap());
+        assert!(!ScoreAttempt::record(&level, &attempt, 60, 2, &mut conn).unwrap());
+
+        let other = submission(&level, Some(bob.id));
+        assert!(ScoreAttempt::record(&level, &other, 60, 2, &mut conn).unwrap());
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::db::Connection,
+    models::game::Game,
+    schema::{game, season, season_standing},
+};
+
+/// A period of time of a game, at the end of which the final standings of every level are archived and the live
+/// leaderboards start over.
+#[derive(Clone, Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = season)]
+#[diesel(belongs_to(Game))]
+pub struct Season {
+    pub id: Uuid,
+    pub name: String,
+    pub game_id: Uuid,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    /// The moment the final standings were archived, empty while the season has not ended.
+    pub archived_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, AsChangeset, Serialize, Deserialize, ToSchema, Validate)]
+#[diesel(table_name = season)]
+pub struct SeasonForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
+    pub name: String,
+    pub game_id: Uuid,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+}
+
+/// The final rank of a score on the leaderboard of a level at the end of a season.
+#[derive(Serialize, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = season_standing)]
+pub struct SeasonStanding {
+    pub id: Uuid,
+    pub season_id: Uuid,
+    pub level_id: Uuid,
+    pub rank: i64,
+    /// The ranked score, empty when the score has been removed since.
+    pub score_id: Option<Uuid>,
+    /// The user of the ranked score, empty when the score has no user or the user has been removed since.
+    pub user_id: Option<Uuid>,
+    /// The name of the user, or the username of the score, at the end of the season.
+    pub name: Option<String>,
+    pub score: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = season_standing)]
+pub struct NewSeasonStanding {
+    pub season_id: Uuid,
+    pub level_id: Uuid,
+    pub rank: i64,
+    pub score_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub score: i32,
+}
+
+/// The query parameters that can be used when fetching the standings of a season.
+#[derive(Deserialize)]
+pub struct StandingQuery {
+    pub level_id: Option<Uuid>,
+}
+
+impl Season {
+    /// Fetches a season from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no season is found with the given id.
+    pub fn find_by_id(season_id: Uuid, conn: &mut Connection) -> QueryResult<Season> {
+        season::table.find(season_id).get_result::<Season>(conn)
+    }
+
+    /// Fetches the seasons of the given game from the database, with the latest season first.
+    pub fn find_by_game(game: &Game, conn: &mut Connection) -> QueryResult<Vec<Season>> {
+        Season::belonging_to(game)
+            .select(Season::as_select())
+            .order(season::starts_at.desc())
+            .load(conn)
+    }
+
+    /// Checks if a season of the given game overlaps with the given period. The season with the given id is
+    /// left out, so a season doesn't overlap with itself when it is updated.
+    pub fn overlaps(
+        game_id: Uuid,
+        starts_at: NaiveDateTime,
+        ends_at: NaiveDateTime,
+        except: Option<Uuid>,
+        conn: &mut Connection,
+    ) -> QueryResult<bool> {
+        let mut query = season::table
+            .filter(season::game_id.eq(game_id))
+            .filter(season::starts_at.lt(ends_at))
+            .filter(season::ends_at.gt(starts_at))
+            .into_boxed();
+
+        if let Some(except) = except {
+            query = query.filter(season::id.ne(except));
+        }
+
+        diesel::select(diesel::dsl::exists(query)).get_result(conn)
+    }
+
+    /// Fetches the end of the last archived season of the given game, from which the live leaderboards rank the
+    /// scores.
+    pub fn last_reset(game_id: Uuid, conn: &mut Connection) -> QueryResult<Option<NaiveDateTime>> {
+        season::table
+            .filter(season::game_id.eq(game_id))
+            .filter(season::archived_at.is_not_null())
+            .select(diesel::dsl::max(season::ends_at))
+            .get_result(conn)
+    }
+
+    /// Fetches the season that ended first and is not archived yet, and locks it until the end of the transaction.
+    /// Seasons that are locked by another server are skipped, as are the seasons of deleted games.
+    pub fn lock_next_ended(conn: &mut Connection) -> QueryResult<Option<Season>> {
+        season::table
+            .filter(season::archived_at.is_null())
+            .filter(season::ends_at.le(Utc::now().naive_utc()))
+            .filter(season::game_id.eq_any(game::table.filter(game::deleted_at.is_null()).select(game::id)))
+            .order(season::ends_at.asc())
+            .for_update()
+            .skip_locked()
+            .first::<Season>(conn)
+            .optional()
+    }
+
+    /// Adds a new season to the database.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn insert(new_season: SeasonForm, conn: &mut Connection) -> QueryResult<Season> {
+        diesel::insert_into(season::table)
+            .values(&new_season)
+            .get_result::<Season>(conn)
+    }
+
+    /// Updates the season with the given id.
+    ///
+    /// Errors
+    /// - If no season is found with the given id.
+    /// - If one of the fields contain invalid data.
+    pub fn update(season_id: Uuid, updated_season: SeasonForm, conn: &mut Connection) -> QueryResult<Season> {
+        diesel::update(season::table.find(season_id))
+            .set(&updated_season)
+            .get_result::<Season>(conn)
+    }
+
+    /// Removes the season with the given id from the database, together with its standings.
+    pub fn delete(season_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(season::table.find(season_id)).execute(conn)
+    }
+
+    /// Stores the final standings of the season and marks the season as archived. The standings are inserted in
+    /// batches, so large leaderboards stay below the parameter limit of a single query.
+    ///
+    /// Errors
+    /// - If one of the standings contain invalid data.
+    pub fn archive(season_id: Uuid, standings: &[NewSeasonStanding], conn: &mut Connection) -> QueryResult<Season> {
+        for batch in standings.chunks(1000) {
+            diesel::insert_into(season_standing::table)
+                .values(batch)
+                .execute(conn)?;
+        }
+
+        diesel::update(season::table.find(season_id))
+            .set(season::archived_at.eq(Utc::now().naive_utc()))
+            .get_result::<Season>(conn)
+    }
+}
+
+impl SeasonStanding {
+    /// Fetches the standings of the season with the given id, ordered by level and rank. When a level is given,
+    /// only the standings of that level are fetched.
+    pub fn find_by_season(
+        season_id: Uuid,
+        level_id: Option<Uuid>,
+        conn: &mut Connection,
+    ) -> QueryResult<Vec<SeasonStanding>> {
+        let mut query = season_standing::table
+            .filter(season_standing::season_id.eq(season_id))
+            .into_boxed();
+
+        if let Some(level_id) = level_id {
+            query = query.filter(season_standing::level_id.eq(level_id));
+        }
+
+        query
+            .order((season_standing::level_id.asc(), season_standing::rank.asc(), season_standing::id.asc()))
+            .select(SeasonStanding::as_select())
+            .load(conn)
+    }
+}
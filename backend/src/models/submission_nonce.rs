@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    schema::submission_nonce::{self, dsl::*},
+};
+
+/// A nonce of a signed score submission. Every nonce can only be used once per game, which prevents signed
+/// submissions from being replayed.
+#[derive(Insertable)]
+#[diesel(table_name = submission_nonce)]
+pub struct SubmissionNonce {
+    pub game_id: Uuid,
+    pub nonce: String,
+}
+
+impl SubmissionNonce {
+    /// Adds the nonce to the database.
+    ///
+    /// Errors
+    /// - If the nonce was already used for the game.
+    pub fn insert(data: SubmissionNonce, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::insert_into(submission_nonce)
+            .values(&data)
+            .execute(conn)
+    }
+
+    /// Deletes the nonces that were used before the given moment from the database.
+    pub fn delete_before(moment: NaiveDateTime, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(submission_nonce)
+            .filter(created_at.lt(moment))
+            .execute(conn)
+    }
+}
@@ -0,0 +1,319 @@
+use chrono::NaiveDateTime;
+use diesel::{dsl::count_star, prelude::*, Connection as _};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::db::Connection,
+    models::{game::Game, level::SortDirection, user::User},
+    schema::{level, score, tournament, tournament_entrant, tournament_level, tournament_prize, user},
+};
+
+text_enum! {
+    /// Determines how the scores of an entrant on the levels of a tournament are combined.
+    #[derive(Default)]
+    pub enum TournamentScoring {
+        /// The best scores of the entrant on every level are added up. Entrants with a score on more levels are
+        /// ranked first.
+        #[default]
+        Sum => "sum",
+        /// Only the single best score of the entrant on any of the levels counts.
+        Best => "best",
+    }
+}
+
+/// A time-limited event on a set of levels of a game, in which only the scores of the registered entrants count.
+#[derive(Clone, Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
+#[diesel(table_name = tournament)]
+#[diesel(belongs_to(Game))]
+pub struct Tournament {
+    pub id: Uuid,
+    pub name: String,
+    pub game_id: Uuid,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    /// The number of users that can join the tournament, unlimited when empty.
+    pub max_entrants: Option<i32>,
+    pub scoring: TournamentScoring,
+    pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    /// The sort direction of the levels of the tournament, at the moment the tournament was saved.
+    pub sort_direction: SortDirection,
+}
+
+/// A tournament together with its levels, prizes and the number of users that joined it.
+#[derive(Serialize, ToSchema)]
+pub struct TournamentDto {
+    #[serde(flatten)]
+    pub tournament: Tournament,
+    pub level_ids: Vec<Uuid>,
+    pub prizes: Vec<TournamentPrize>,
+    pub entrants: i64,
+}
+
+/// The prize awarded to the entrant that finishes a tournament at the given rank.
+#[derive(Clone, Serialize, Deserialize, Queryable, Selectable, ToSchema, Validate)]
+#[diesel(table_name = tournament_prize)]
+pub struct TournamentPrize {
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub rank: i32,
+    #[validate(length(min = 1, max = 255, message = "must be between 1 and 255 characters"))]
+    pub description: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TournamentForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
+    pub name: String,
+    pub game_id: Uuid,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    #[validate(range(min = 1, message = "must be at least 1"))]
+    pub max_entrants: Option<i32>,
+    #[serde(default)]
+    pub scoring: TournamentScoring,
+    #[validate(length(min = 1, max = 50, message = "must contain between 1 and 50 levels"))]
+    pub level_ids: Vec<Uuid>,
+    #[serde(default)]
+    #[validate(nested)]
+    pub prizes: Vec<TournamentPrize>,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = tournament)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewTournament {
+    pub name: String,
+    pub game_id: Uuid,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub max_entrants: Option<i32>,
+    pub scoring: TournamentScoring,
+    pub sort_direction: SortDirection,
+}
+
+/// The user that joins a tournament. The user is taken from the access token when a player joins.
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct TournamentEntryForm {
+    pub user_id: Option<Uuid>,
+}
+
+/// The rank of an entrant of a tournament, based on the scores the entrant submitted during the tournament.
+#[derive(Serialize, ToSchema)]
+pub struct TournamentStanding {
+    pub rank: i64,
+    pub user_id: Uuid,
+    pub name: String,
+    /// The sum of the best scores on every level, or the single best score, depending on the scoring of the
+    /// tournament.
+    pub score: i64,
+    /// The number of levels the entrant submitted a score on.
+    pub levels: i64,
+    /// The prize for the rank of the entrant, if any.
+    pub prize: Option<String>,
+}
+
+/// A score that counts for a tournament.
+#[derive(Queryable)]
+pub struct TournamentScore {
+    pub user_id: Uuid,
+    pub level_id: Uuid,
+    pub score: i32,
+}
+
+impl Tournament {
+    /// Fetches a tournament from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no tournament is found with the given id.
+    pub fn find_by_id(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<Tournament> {
+        tournament::table.find(tournament_id).get_result::<Tournament>(conn)
+    }
+
+    /// Fetches a tournament from the database with the given id, and locks it until the end of the transaction,
+    /// so entrants can't join at the same time.
+    ///
+    /// # Errors
+    /// - If no tournament is found with the given id.
+    pub fn lock(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<Tournament> {
+        tournament::table
+            .find(tournament_id)
+            .for_update()
+            .get_result::<Tournament>(conn)
+    }
+
+    /// Fetches the tournaments of the given game from the database, with the latest tournament first.
+    pub fn find_by_game(game: &Game, conn: &mut Connection) -> QueryResult<Vec<Tournament>> {
+        Tournament::belonging_to(game)
+            .select(Tournament::as_select())
+            .order(tournament::starts_at.desc())
+            .load(conn)
+    }
+
+    /// Fetches the ids of the levels of the tournament with the given id.
+    pub fn level_ids(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<Vec<Uuid>> {
+        tournament_level::table
+            .filter(tournament_level::tournament_id.eq(tournament_id))
+            .select(tournament_level::level_id)
+            .load(conn)
+    }
+
+    /// Fetches the prizes of the tournament with the given id, ordered by rank.
+    pub fn prizes(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<Vec<TournamentPrize>> {
+        tournament_prize::table
+            .filter(tournament_prize::tournament_id.eq(tournament_id))
+            .order(tournament_prize::rank.asc())
+            .select(TournamentPrize::as_select())
+            .load(conn)
+    }
+
+    /// Counts the users that joined the tournament with the given id.
+    pub fn count_entrants(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<i64> {
+        tournament_entrant::table
+            .filter(tournament_entrant::tournament_id.eq(tournament_id))
+            .select(count_star())
+            .get_result(conn)
+    }
+
+    /// Fetches the users that joined the tournament with the given id, in the order they joined. Deleted users are
+    /// left out.
+    pub fn entrants(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<Vec<User>> {
+        tournament_entrant::table
+            .inner_join(user::table)
+            .filter(tournament_entrant::tournament_id.eq(tournament_id))
+            .filter(user::deleted_at.is_null())
+            .order(tournament_entrant::created_at.asc())
+            .select(User::as_select())
+            .load(conn)
+    }
+
+    /// Checks if the user with the given id joined the tournament with the given id.
+    pub fn has_entrant(tournament_id: Uuid, user_id: Uuid, conn: &mut Connection) -> QueryResult<bool> {
+        diesel::select(diesel::dsl::exists(
+            tournament_entrant::table.find((tournament_id, user_id)),
+        ))
+        .get_result(conn)
+    }
+
+    /// Fetches the scores that count for the tournament: the scores of the entrants on the levels of the
+    /// tournament, achieved during the tournament. Hidden and deleted scores don't count.
+    pub fn find_scores(tournament: &Tournament, conn: &mut Connection) -> QueryResult<Vec<TournamentScore>> {
+        let levels = tournament_level::table
+            .filter(tournament_level::tournament_id.eq(tournament.id))
+            .select(tournament_level::level_id.nullable());
+        let entrants = tournament_entrant::table
+            .filter(tournament_entrant::tournament_id.eq(tournament.id))
+            .select(tournament_entrant::user_id.nullable());
+
+        score::table
+            .inner_join(level::table)
+            .filter(score::level_id.eq_any(levels))
+            .filter(score::user_id.eq_any(entrants))
+            .filter(score::achieved_at.ge(tournament.starts_at))
+            .filter(score::achieved_at.lt(tournament.ends_at))
+            .filter(score::is_hidden.eq(false))
+            .filter(score::deleted_at.is_null())
+            .filter(level::deleted_at.is_null())
+            .select((score::user_id.assume_not_null(), level::id, score::highscore))
+            .load(conn)
+    }
+
+    /// Adds a new tournament with its levels and prizes to the database.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    pub fn insert(
+        new_tournament: NewTournament,
+        level_ids: &[Uuid],
+        prizes: &[TournamentPrize],
+        conn: &mut Connection,
+    ) -> QueryResult<Tournament> {
+        conn.transaction(|conn| {
+            let created = diesel::insert_into(tournament::table)
+                .values(&new_tournament)
+                .get_result::<Tournament>(conn)?;
+            Tournament::insert_details(created.id, level_ids, prizes, conn)?;
+
+            Ok(created)
+        })
+    }
+
+    /// Updates the tournament with the given id, replacing its levels and prizes.
+    ///
+    /// Errors
+    /// - If no tournament is found with the given id.
+    /// - If one of the fields contain invalid data.
+    pub fn update(
+        tournament_id: Uuid,
+        updated_tournament: NewTournament,
+        level_ids: &[Uuid],
+        prizes: &[TournamentPrize],
+        conn: &mut Connection,
+    ) -> QueryResult<Tournament> {
+        conn.transaction(|conn| {
+            let updated = diesel::update(tournament::table.find(tournament_id))
+                .set(&updated_tournament)
+                .get_result::<Tournament>(conn)?;
+
+            diesel::delete(tournament_level::table.filter(tournament_level::tournament_id.eq(tournament_id)))
+                .execute(conn)?;
+            diesel::delete(tournament_prize::table.filter(tournament_prize::tournament_id.eq(tournament_id)))
+                .execute(conn)?;
+            Tournament::insert_details(tournament_id, level_ids, prizes, conn)?;
+
+            Ok(updated)
+        })
+    }
+
+    /// Removes the tournament with the given id from the database, together with its levels, entrants and prizes.
+    pub fn delete(tournament_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(tournament::table.find(tournament_id)).execute(conn)
+    }
+
+    /// Registers the user with the given id as an entrant of the tournament with the given id.
+    ///
+    /// Errors
+    /// - If the user already joined the tournament.
+    pub fn join(tournament_id: Uuid, user_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::insert_into(tournament_entrant::table)
+            .values((
+                tournament_entrant::tournament_id.eq(tournament_id),
+                tournament_entrant::user_id.eq(user_id),
+            ))
+            .execute(conn)
+    }
+
+    fn insert_details(
+        tournament_id: Uuid,
+        level_ids: &[Uuid],
+        prizes: &[TournamentPrize],
+        conn: &mut Connection,
+    ) -> QueryResult<()> {
+        let levels: Vec<_> = level_ids
+            .iter()
+            .map(|level_id| {
+                (
+                    tournament_level::tournament_id.eq(tournament_id),
+                    tournament_level::level_id.eq(*level_id),
+                )
+            })
+            .collect();
+        diesel::insert_into(tournament_level::table).values(&levels).execute(conn)?;
+
+        let prizes: Vec<_> = prizes
+            .iter()
+            .map(|prize| {
+                (
+                    tournament_prize::tournament_id.eq(tournament_id),
+                    tournament_prize::rank.eq(prize.rank),
+                    tournament_prize::description.eq(&prize.description),
+                )
+            })
+            .collect();
+        diesel::insert_into(tournament_prize::table).values(&prizes).execute(conn)?;
+
+        Ok(())
+    }
+}
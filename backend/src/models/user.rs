@@ -1,16 +1,23 @@
-use chrono::NaiveDateTime;
-use diesel::{dsl::count_star, prelude::*, result::Error, AsChangeset, Insertable};
+use chrono::{NaiveDateTime, Utc};
+use diesel::{dsl::count_star, prelude::*, result::Error, AsChangeset, Connection as _, Insertable};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
     config::db::Connection,
-    models::game::Game,
-    schema::user::{self, dsl::*},
+    models::{
+        game::Game,
+        pagination::{keyset, Cursor, Page, PageError, PageQuery},
+    },
+    schema::{
+        score,
+        user::{self, dsl::*},
+    },
 };
 
-#[derive(Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
+#[derive(Clone, Serialize, Associations, Identifiable, Queryable, Selectable, ToSchema)]
 #[diesel(table_name = user)]
 #[diesel(belongs_to(Game))]
 pub struct User {
@@ -19,19 +26,52 @@ pub struct User {
     pub game_id: Uuid,
     pub created_at: NaiveDateTime,
     pub updated_at: Option<NaiveDateTime>,
+    #[serde(skip)]
+    pub password_hash: Option<String>,
+    #[serde(skip)]
+    pub device_id_hash: Option<String>,
+    /// Whether the user is an anonymous guest, identified only by the device token generated for it.
+    pub is_guest: bool,
+    #[serde(skip)]
+    pub canonical_name: String,
+    /// The moment the user was deleted, empty when the user is not deleted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
-#[derive(Insertable, AsChangeset, Deserialize, ToSchema)]
+#[derive(Insertable, AsChangeset, Deserialize, ToSchema, Validate)]
 #[diesel(table_name = user)]
 pub struct UserForm {
+    #[validate(length(min = 1, max = 50, message = "must be between 1 and 50 characters"))]
     pub name: String,
     pub game_id: Uuid,
 }
 
+/// A user that registered as a player, with the hashes of their credentials.
+#[derive(Insertable)]
+#[diesel(table_name = user)]
+pub struct NewPlayer {
+    pub name: String,
+    pub game_id: Uuid,
+    pub password_hash: Option<String>,
+    pub device_id_hash: Option<String>,
+    pub is_guest: bool,
+    pub canonical_name: String,
+}
+
+/// The fields a page of users can be sorted by.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSort {
+    Name,
+    #[default]
+    CreatedAt,
+}
+
 impl User {
     /// Fetches all the users in the database
     pub fn find_all(conn: &mut Connection) -> QueryResult<Vec<User>> {
-        user.load::<User>(conn)
+        user.filter(deleted_at.is_null()).load::<User>(conn)
     }
 
     /// Fetches a user from the database with the given id.
@@ -39,41 +79,224 @@ impl User {
     /// # Errors
     /// - If no user is found with the given id.
     pub fn find_by_id(user_id: Uuid, conn: &mut Connection) -> QueryResult<User> {
-        user.find(user_id).get_result::<User>(conn)
+        user.find(user_id)
+            .filter(deleted_at.is_null())
+            .get_result::<User>(conn)
+    }
+
+    /// Fetches a deleted user from the database with the given id.
+    ///
+    /// # Errors
+    /// - If no deleted user is found with the given id.
+    pub fn find_deleted_by_id(user_id: Uuid, conn: &mut Connection) -> QueryResult<User> {
+        user.find(user_id)
+            .filter(deleted_at.is_not_null())
+            .get_result::<User>(conn)
     }
 
     /// Fetches levels related to the given game from the database.
     pub fn find_by_game(game: &Game, conn: &mut Connection) -> QueryResult<Vec<User>> {
         User::belonging_to(game)
+            .filter(deleted_at.is_null())
             .select(User::as_select())
             .load(conn)
     }
 
+    /// Fetches a single page of users related to the given game from the database.
+    ///
+    /// # Errors
+    /// - If the cursor of the page is invalid.
+    pub fn find_page_by_game(
+        game: &Game,
+        page: &PageQuery<UserSort>,
+        conn: &mut Connection,
+    ) -> Result<Page<User>, PageError> {
+        let query = User::belonging_to(game).filter(deleted_at.is_null()).into_boxed();
+        let query = match page.sort {
+            UserSort::Name => keyset!(query, name, id, page.order, page.after::<String>()?),
+            UserSort::CreatedAt => keyset!(query, created_at, id, page.order, page.after::<NaiveDateTime>()?),
+        };
+
+        let users = query
+            .select(User::as_select())
+            .limit(page.limit() + 1)
+            .load(conn)?;
+        let total = User::belonging_to(game)
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(conn)?;
+
+        Ok(Page::new(users, page.limit(), total, |item| match page.sort {
+            UserSort::Name => Cursor::encode(&item.name, item.id),
+            UserSort::CreatedAt => Cursor::encode(&item.created_at, item.id),
+        }))
+    }
+
     /// Adds a new user to the database.
     /// 
     /// Errors
     /// - If one of the fields contain invalid data.
-    pub fn insert(data: UserForm, conn: &mut Connection) -> QueryResult<User> {
+    pub fn insert(data: UserForm, canonical: &str, conn: &mut Connection) -> QueryResult<User> {
+        diesel::insert_into(user)
+            .values((&data, canonical_name.eq(canonical)))
+            .get_result::<User>(conn)
+    }
+
+    /// Adds a new player to the database.
+    ///
+    /// Errors
+    /// - If one of the fields contain invalid data.
+    /// - If the device id is already registered in the game.
+    pub fn insert_player(data: NewPlayer, conn: &mut Connection) -> QueryResult<User> {
         diesel::insert_into(user)
             .values(&data)
             .get_result::<User>(conn)
     }
 
+    /// Fetches the user with the given canonical name in the given game that has a password.
+    ///
+    /// Errors
+    /// - If no user with a password is found with the given name.
+    pub fn find_by_canonical_name_with_password(
+        player_game_id: Uuid,
+        canonical: &str,
+        conn: &mut Connection,
+    ) -> QueryResult<User> {
+        user.filter(game_id.eq(player_game_id))
+            .filter(canonical_name.eq(canonical))
+            .filter(password_hash.is_not_null())
+            .filter(deleted_at.is_null())
+            .first::<User>(conn)
+    }
+
+    /// Checks if a user other than the given user has the given canonical name in the given game. Deleted users
+    /// keep their name until they are purged.
+    pub fn canonical_name_exists(
+        player_game_id: Uuid,
+        canonical: &str,
+        except: Option<Uuid>,
+        conn: &mut Connection,
+    ) -> QueryResult<bool> {
+        let mut query = user
+            .filter(game_id.eq(player_game_id))
+            .filter(canonical_name.eq(canonical))
+            .into_boxed();
+        if let Some(except) = except {
+            query = query.filter(id.ne(except));
+        }
+
+        diesel::select(diesel::dsl::exists(query)).get_result(conn)
+    }
+
+    /// Fetches the user with the given device id hash in the given game.
+    ///
+    /// Errors
+    /// - If no user is found with the given device id hash.
+    pub fn find_by_device_id_hash(player_game_id: Uuid, hash: &str, conn: &mut Connection) -> QueryResult<User> {
+        user.filter(game_id.eq(player_game_id))
+            .filter(device_id_hash.eq(hash))
+            .filter(deleted_at.is_null())
+            .first::<User>(conn)
+    }
+
+    /// Turns the guest with the given id into a named player with the given password hash.
+    ///
+    /// Errors
+    /// - If no user is found with the given id.
+    /// - If one of the fields contain invalid data.
+    pub fn upgrade_guest(
+        user_id: Uuid,
+        player_name: &str,
+        canonical: &str,
+        player_password_hash: &str,
+        conn: &mut Connection,
+    ) -> QueryResult<User> {
+        diesel::update(user)
+            .filter(id.eq(user_id))
+            .filter(deleted_at.is_null())
+            .set((
+                name.eq(player_name),
+                canonical_name.eq(canonical),
+                password_hash.eq(player_password_hash),
+                is_guest.eq(false),
+            ))
+            .get_result::<User>(conn)
+    }
+
     /// Updates a user with the given id in the database.
     /// 
     /// Errors
     /// - If no user is found with the given id.
     /// - If one of the fields contain invalid data.
-    pub fn update(user_id: Uuid, data: UserForm, conn: &mut Connection) -> QueryResult<User> {
+    pub fn update(user_id: Uuid, data: UserForm, canonical: &str, conn: &mut Connection) -> QueryResult<User> {
         diesel::update(user)
             .filter(id.eq(user_id))
-            .set(data)
+            .filter(deleted_at.is_null())
+            .set((&data, canonical_name.eq(canonical)))
             .get_result::<User>(conn)
     }
 
-    /// Deletes a user with the given id from the database.
+    /// Fetches the id and name of the users that have no canonical name yet.
+    pub fn find_without_canonical_name(conn: &mut Connection) -> QueryResult<Vec<(Uuid, String)>> {
+        user.filter(canonical_name.is_null()).select((id, name)).load(conn)
+    }
+
+    /// Sets the canonical name of the user with the given id.
+    ///
+    /// Errors
+    /// - If another user in the same game has the same canonical name.
+    pub fn set_canonical_name(user_id: Uuid, canonical: &str, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::update(user)
+            .filter(id.eq(user_id))
+            .set(canonical_name.eq(canonical))
+            .execute(conn)
+    }
+
+    /// Marks a user with the given id as deleted, together with their scores.
     pub fn delete(user_id: Uuid, conn: &mut Connection) -> QueryResult<usize> {
-        diesel::delete(user).filter(id.eq(user_id)).execute(conn)
+        let now = Utc::now().naive_utc();
+
+        conn.transaction(|conn| {
+            let deleted = diesel::update(user)
+                .filter(id.eq(user_id))
+                .filter(deleted_at.is_null())
+                .set(deleted_at.eq(now))
+                .execute(conn)?;
+            if deleted == 0 {
+                return Ok(0);
+            }
+
+            diesel::update(score::table)
+                .filter(score::user_id.eq(user_id))
+                .filter(score::deleted_at.is_null())
+                .set(score::deleted_at.eq(now))
+                .execute(conn)?;
+
+            Ok(deleted)
+        })
+    }
+
+    /// Restores a deleted user with the given id, together with the scores that were deleted with them.
+    pub fn restore(user_id: Uuid, conn: &mut Connection) -> QueryResult<User> {
+        conn.transaction(|conn| {
+            let deleted = User::find_deleted_by_id(user_id, conn)?;
+
+            diesel::update(score::table)
+                .filter(score::user_id.eq(user_id))
+                .filter(score::deleted_at.eq(deleted.deleted_at))
+                .set(score::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+
+            diesel::update(user)
+                .filter(id.eq(user_id))
+                .set(deleted_at.eq(None::<NaiveDateTime>))
+                .get_result::<User>(conn)
+        })
+    }
+
+    /// Permanently removes the users that were deleted before the given moment, together with their scores.
+    pub fn purge(before: NaiveDateTime, conn: &mut Connection) -> QueryResult<usize> {
+        diesel::delete(user).filter(deleted_at.lt(before)).execute(conn)
     }
 
     /// Counts the number of users in the database. If a game is given, only the number of users relates to the game are
@@ -81,10 +304,12 @@ impl User {
     pub fn count(game: &Option<Game>, conn: &mut Connection) -> Result<i64, Error> {
         let count = if let Some(value) = game {
             User::belonging_to(value)
+                .filter(deleted_at.is_null())
                 .select(count_star())
                 .first(conn)?
         } else {
-            user.select(count_star())
+            user.filter(deleted_at.is_null())
+                .select(count_star())
                 .first(conn)?
         };
 
@@ -3,8 +3,13 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use diesel::result::{DatabaseErrorKind, Error};
+use log::error;
 use serde::Serialize;
 use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+use crate::models::pagination::{Page, Pagination};
 
 /// The structure of the body of a response from the api.
 /// 
@@ -24,14 +29,54 @@ pub struct ResponseBody<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schema(ignore)]
     pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+    /// A stable, machine-readable code describing the error, see [`AppError::code`].
+    #[serde(rename = "code", skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
     #[serde(skip)]
     pub code: StatusCode,
 }
 
+/// A validation error of a single field in the body of a request.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
 /// Type alias for [`ResponseBody`] whose type generic is set to an empty value, because an error
 /// response contains no data.
 pub type ErrorResponse = ResponseBody<()>;
 
+/// The errors that can be returned by the api. Every error is converted into an [`ErrorResponse`] with the
+/// matching status code and a machine-readable error code.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Validation(String, Vec<FieldError>),
+    TooManyRequests(String),
+    ServiceUnavailable(String),
+    /// An unexpected error, the message is returned to the client and should not contain any details about the
+    /// cause of the error.
+    Internal(String),
+}
+
 impl<T> ResponseBody<T> {
     /// Creates a new response with a 200 status code
     pub fn ok(message: &str, data: T) -> Self {
@@ -39,6 +84,9 @@ impl<T> ResponseBody<T> {
             status: "success",
             message: message.to_string(),
             data: Some(data),
+            pagination: None,
+            error_code: None,
+            errors: None,
             code: StatusCode::OK,
         }
     }
@@ -49,41 +97,178 @@ impl<T> ResponseBody<T> {
             status: "success",
             message: message.to_string(),
             data: Some(data),
+            pagination: None,
+            error_code: None,
+            errors: None,
             code: StatusCode::CREATED,
         }
     }
+}
 
-    /// Creates a new response with a 500 status code
-    pub fn internal_error(err: &str) -> Self {
+impl<T> ResponseBody<Vec<T>> {
+    /// Creates a new response with a 200 status code, containing the items of the page and the pagination
+    /// metadata
+    pub fn page(message: &str, page: Page<T>) -> Self {
         ResponseBody {
-            status: "fail",
-            message: err.to_string(),
-            data: None,
-            code: StatusCode::INTERNAL_SERVER_ERROR,
+            status: "success",
+            message: message.to_string(),
+            data: Some(page.items),
+            pagination: Some(Pagination {
+                next_cursor: page.next_cursor,
+                total: page.total,
+            }),
+            error_code: None,
+            errors: None,
+            code: StatusCode::OK,
         }
     }
+}
 
-    /// Creates a new response with a 404 status code
-    pub fn not_found_error(err: &str) -> Self {
-        ResponseBody {
-            status: "fail",
-            message: err.to_string(),
-            data: None,
-            code: StatusCode::NOT_FOUND,
+impl AppError {
+    /// Returns the stable error code that is sent to the client, which can be used to handle specific errors
+    /// without parsing the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Validation(_, _) => "validation_failed",
+            AppError::TooManyRequests(_) => "too_many_requests",
+            AppError::ServiceUnavailable(_) => "service_unavailable",
+            AppError::Internal(_) => "internal_error",
         }
     }
 
-    /// Creates a new response with a 401 status code
-    pub fn unauthorized_error(err: &str) -> Self {
+    /// Returns the message of the error, together with the invalid fields of a validation error.
+    pub fn into_message(self) -> (String, Vec<FieldError>) {
+        match self {
+            AppError::Validation(message, errors) => (message, errors),
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::TooManyRequests(message)
+            | AppError::ServiceUnavailable(message)
+            | AppError::Internal(message) => (message, vec![]),
+        }
+    }
+
+    /// Returns the HTTP status code of the error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Validation(_, _) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<Error> for AppError {
+    /// Maps a database error to the error that is returned to the client. Constraint violations are caused by
+    /// the data sent by the client, every other error is logged and returned as an internal error without
+    /// exposing the message of the database.
+    fn from(value: Error) -> Self {
+        match value {
+            Error::NotFound => AppError::NotFound("Resource not found".to_string()),
+            Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                AppError::Conflict("Resource already exists".to_string())
+            }
+            Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                let field = foreign_key_column(info.table_name(), info.constraint_name());
+                AppError::Validation(
+                    "Referenced resource does not exist".to_string(),
+                    field
+                        .map(|field| vec![FieldError::new(&field, "does not exist")])
+                        .unwrap_or_default(),
+                )
+            }
+            Error::DatabaseError(DatabaseErrorKind::NotNullViolation, info) => AppError::Validation(
+                "Missing required field".to_string(),
+                info.column_name()
+                    .map(|field| vec![FieldError::new(field, "is required")])
+                    .unwrap_or_default(),
+            ),
+            Error::DatabaseError(DatabaseErrorKind::CheckViolation, _) => {
+                AppError::Validation("Invalid data".to_string(), vec![])
+            }
+            err => {
+                error!("Database error: {}", err);
+                AppError::Internal("Internal server error".to_string())
+            }
+        }
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    /// Lists every invalid field, using the message of the validation rule or its code when the rule has no
+    /// message.
+    fn from(value: ValidationErrors) -> Self {
+        let mut errors: Vec<FieldError> = value
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| {
+                    let message = match &error.message {
+                        Some(message) => message.to_string(),
+                        None => error.code.to_string(),
+                    };
+
+                    FieldError::new(&field, &message)
+                })
+            })
+            .collect();
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+
+        AppError::Validation("Invalid request body".to_string(), errors)
+    }
+}
+
+impl From<AppError> for ErrorResponse {
+    fn from(value: AppError) -> Self {
+        let code = value.status();
+        let error_code = Some(value.code());
+        // Only validation errors list the invalid fields, every other error omits them.
+        let validation = matches!(value, AppError::Validation(_, _));
+        let (message, errors) = value.into_message();
+
         ResponseBody {
             status: "fail",
-            message: err.to_string(),
+            message,
             data: None,
-            code: StatusCode::UNAUTHORIZED,
+            pagination: None,
+            error_code,
+            errors: validation.then_some(errors),
+            code,
         }
     }
 }
 
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        ErrorResponse::from(self).into_response()
+    }
+}
+
+/// Derives the column of a foreign key from the name of its constraint. The constraints are named
+/// `fk_<referenced table>_<table>` and reference the `<referenced table>_id` column.
+fn foreign_key_column(table: Option<&str>, constraint: Option<&str>) -> Option<String> {
+    let referenced = constraint?
+        .strip_prefix("fk_")?
+        .strip_suffix(table?)?
+        .strip_suffix('_')?;
+
+    Some(format!("{}_id", referenced))
+}
+
 impl<T> IntoResponse for ResponseBody<T>
 where
     T: Serialize,
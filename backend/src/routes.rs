@@ -3,41 +3,55 @@ use std::time::Duration;
 use axum::{
     http::{
         header::{ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE},
-        Method,
+        HeaderName, Method,
     },
     Router,
 };
 use tower_http::{
-    cors::{Any, CorsLayer},
+    cors::{AllowOrigin, Any, CorsLayer},
     services::ServeDir,
 };
 use tower_http::services::ServeFile;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::{controller, ApiDoc, SharedState};
+use crate::{
+    config::Config, controller, middleware::api_key_middleware::API_KEY_HEADER, ApiDoc, SharedState,
+};
 
 /// Set up the entire routing for the web service and create the OpenAPI
 /// documentation page
 pub async fn create_app(state: SharedState) -> Router {
-    let front_end = ServeDir::new("./dist")
-        .not_found_service(ServeFile::new("./dist/index.html"));
+    let config = state.read().unwrap().config.clone();
+    let front_end = ServeDir::new(&config.static_dir)
+        .not_found_service(ServeFile::new(format!("{}/index.html", config.static_dir)));
 
     Router::new()
-        .nest("/api", controller::api_routes())
+        .nest("/api", controller::api_routes(state.clone()))
         .merge(SwaggerUi::new("/swagger").url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .layer(setup_cors())
+        .layer(setup_cors(&config))
         .with_state(state)
         .fallback_service(front_end)
 }
 
 /// Creates a new [`CorsLayer`] to allow external origins making a call
-/// to the server.
-fn setup_cors() -> CorsLayer {
+/// to the server. Every origin is allowed when no origins are configured.
+fn setup_cors(config: &Config) -> CorsLayer {
+    let origins = if config.cors_allowed_origins.is_empty() {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(config.cors_allowed_origins.clone())
+    };
+
     CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(origins)
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE])
+        .allow_headers([
+            AUTHORIZATION,
+            ACCEPT,
+            CONTENT_TYPE,
+            HeaderName::from_static(API_KEY_HEADER),
+        ])
         .expose_headers([CONTENT_DISPOSITION])
         .max_age(Duration::from_secs(3600))
 }
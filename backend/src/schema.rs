@@ -1,5 +1,37 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_key (id) {
+        id -> Uuid,
+        #[max_length = 50]
+        name -> Varchar,
+        #[max_length = 16]
+        prefix -> Varchar,
+        #[max_length = 64]
+        key_hash -> Varchar,
+        game_id -> Uuid,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    audit_log (id) {
+        id -> Uuid,
+        #[max_length = 255]
+        actor -> Varchar,
+        #[max_length = 20]
+        action -> Varchar,
+        #[max_length = 20]
+        entity_type -> Varchar,
+        entity_id -> Uuid,
+        before -> Nullable<Jsonb>,
+        after -> Nullable<Jsonb>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     game (id) {
         id -> Uuid,
@@ -7,6 +39,11 @@ diesel::table! {
         name -> Varchar,
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        #[max_length = 128]
+        signing_secret -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamp>,
+        #[max_length = 255]
+        owner -> Nullable<Varchar>,
     }
 }
 
@@ -18,6 +55,17 @@ diesel::table! {
         game_id -> Uuid,
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        #[max_length = 20]
+        sort_direction -> Varchar,
+        #[max_length = 20]
+        score_unit -> Varchar,
+        #[max_length = 20]
+        aggregation -> Varchar,
+        min_score -> Nullable<Int4>,
+        max_score -> Nullable<Int4>,
+        max_submissions -> Nullable<Int4>,
+        submission_window -> Nullable<Int4>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -33,6 +81,113 @@ diesel::table! {
         updated_at -> Nullable<Timestamp>,
         level_id -> Nullable<Uuid>,
         user_id -> Nullable<Uuid>,
+        deleted_at -> Nullable<Timestamp>,
+        is_personal_best -> Bool,
+        achieved_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    refresh_token (id) {
+        id -> Uuid,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        user_id -> Uuid,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    score_attempt (id) {
+        id -> Uuid,
+        level_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        #[max_length = 50]
+        username -> Nullable<Varchar>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    season (id) {
+        id -> Uuid,
+        #[max_length = 50]
+        name -> Varchar,
+        game_id -> Uuid,
+        starts_at -> Timestamp,
+        ends_at -> Timestamp,
+        archived_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    season_standing (id) {
+        id -> Uuid,
+        season_id -> Uuid,
+        level_id -> Uuid,
+        rank -> Int8,
+        score_id -> Nullable<Uuid>,
+        user_id -> Nullable<Uuid>,
+        #[max_length = 50]
+        name -> Nullable<Varchar>,
+        score -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    submission_nonce (game_id, nonce) {
+        game_id -> Uuid,
+        #[max_length = 64]
+        nonce -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tournament (id) {
+        id -> Uuid,
+        #[max_length = 50]
+        name -> Varchar,
+        game_id -> Uuid,
+        starts_at -> Timestamp,
+        ends_at -> Timestamp,
+        max_entrants -> Nullable<Int4>,
+        #[max_length = 20]
+        scoring -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        #[max_length = 20]
+        sort_direction -> Varchar,
+    }
+}
+
+diesel::table! {
+    tournament_entrant (tournament_id, user_id) {
+        tournament_id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tournament_level (tournament_id, level_id) {
+        tournament_id -> Uuid,
+        level_id -> Uuid,
+    }
+}
+
+diesel::table! {
+    tournament_prize (tournament_id, rank) {
+        tournament_id -> Uuid,
+        rank -> Int4,
+        #[max_length = 255]
+        description -> Varchar,
     }
 }
 
@@ -44,17 +199,51 @@ diesel::table! {
         game_id -> Uuid,
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        #[max_length = 255]
+        password_hash -> Nullable<Varchar>,
+        #[max_length = 64]
+        device_id_hash -> Nullable<Varchar>,
+        is_guest -> Bool,
+        canonical_name -> Text,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
+diesel::joinable!(api_key -> game (game_id));
 diesel::joinable!(level -> game (game_id));
+diesel::joinable!(refresh_token -> user (user_id));
 diesel::joinable!(score -> level (level_id));
 diesel::joinable!(score -> user (user_id));
+diesel::joinable!(score_attempt -> level (level_id));
+diesel::joinable!(score_attempt -> user (user_id));
+diesel::joinable!(season -> game (game_id));
+diesel::joinable!(season_standing -> level (level_id));
+diesel::joinable!(season_standing -> score (score_id));
+diesel::joinable!(season_standing -> season (season_id));
+diesel::joinable!(season_standing -> user (user_id));
+diesel::joinable!(submission_nonce -> game (game_id));
+diesel::joinable!(tournament -> game (game_id));
+diesel::joinable!(tournament_entrant -> tournament (tournament_id));
+diesel::joinable!(tournament_entrant -> user (user_id));
+diesel::joinable!(tournament_level -> level (level_id));
+diesel::joinable!(tournament_level -> tournament (tournament_id));
+diesel::joinable!(tournament_prize -> tournament (tournament_id));
 diesel::joinable!(user -> game (game_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_key,
+    audit_log,
     game,
     level,
+    refresh_token,
     score,
+    score_attempt,
+    season,
+    season_standing,
+    submission_nonce,
+    tournament,
+    tournament_entrant,
+    tournament_level,
+    tournament_prize,
     user,
 );
@@ -0,0 +1,241 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ring::{
+    digest::{digest, SHA256},
+    rand::{SecureRandom, SystemRandom},
+};
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        api_key::{ApiKey, ApiKeyForm, CreatedApiKey, GameClient, NewApiKey},
+        score::Score,
+    },
+    response::AppError,
+};
+
+use super::{game_service, level_service, user_service};
+
+/// The prefix of every generated API key, which makes the keys easy to recognize in for example secret scanners.
+const KEY_PREFIX: &str = "babs_";
+
+/// The number of characters of a key that are stored in plain text to recognize the key.
+const VISIBLE_KEY_LENGTH: usize = 12;
+
+/// Queries the database and fetches the API keys of the given game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - an error occurred during execution.
+///
+pub fn find_by_game(game_id: Uuid, conn: &mut Connection) -> Result<Vec<ApiKey>, AppError> {
+    let game = game_service::find_by_id(game_id, conn)?;
+
+    match ApiKey::find_by_game(&game, conn) {
+        Ok(keys) => Ok(keys),
+        Err(_) => Err(AppError::Internal("Cannot fetch API keys".to_string())),
+    }
+}
+
+/// Queries the database and fetches the API key with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no API key could be found with the given id.
+///
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<ApiKey, AppError> {
+    match ApiKey::find_by_id(id, conn) {
+        Ok(api_key) => Ok(api_key),
+        Err(_) => Err(AppError::NotFound(format!(
+            "API key with id '{}' not found",
+            id
+        ))),
+    }
+}
+
+/// Generates a new API key for the given game and stores its hash in the database.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - an error occurred during execution.
+///
+pub fn insert(game_id: Uuid, form: ApiKeyForm, conn: &mut Connection) -> Result<CreatedApiKey, AppError> {
+    game_service::find_by_id(game_id, conn)?;
+
+    let secret = generate_secret()?;
+    let new_key = new_api_key(form.name, game_id, &secret);
+
+    match ApiKey::insert(new_key, conn) {
+        Ok(api_key) => Ok(CreatedApiKey { api_key, secret }),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Revokes the API key with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no API key could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn revoke(id: Uuid, conn: &mut Connection) -> Result<ApiKey, AppError> {
+    find_by_id(id, conn)?;
+
+    match ApiKey::revoke(id, conn) {
+        Ok(api_key) => Ok(api_key),
+        Err(_) => Err(AppError::Internal("Could not revoke API key".to_string())),
+    }
+}
+
+/// Revokes the API key with the given id and replaces it with a newly generated key with the same name.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no API key could be found with the given id.
+/// - the API key is already revoked.
+/// - an error occurred during execution.
+///
+pub fn rotate(id: Uuid, conn: &mut Connection) -> Result<CreatedApiKey, AppError> {
+    let current = find_by_id(id, conn)?;
+    if current.revoked_at.is_some() {
+        return Err(AppError::BadRequest(format!(
+            "API key with id '{}' is revoked",
+            id
+        )));
+    }
+
+    let secret = generate_secret()?;
+    let new_key = new_api_key(current.name, current.game_id, &secret);
+
+    match ApiKey::rotate(id, new_key, conn) {
+        Ok(api_key) => Ok(CreatedApiKey { api_key, secret }),
+        Err(_) => Err(AppError::Internal("Could not rotate API key".to_string())),
+    }
+}
+
+/// Looks up the active API key matching the given secret.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no active API key matches the secret.
+///
+pub fn authenticate(secret: &str, conn: &mut Connection) -> Result<GameClient, AppError> {
+    match ApiKey::find_active_by_hash(&hash_secret(secret), conn) {
+        Ok(api_key) => Ok(GameClient {
+            key_id: api_key.id,
+            game_id: api_key.game_id,
+        }),
+        Err(_) => Err(AppError::Unauthorized("Invalid API key".to_string())),
+    }
+}
+
+/// Checks if the given game client has access to the game with the given id. Requests that are not made by a
+/// game client always have access.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the game client belongs to another game.
+///
+pub fn authorize_game(client: Option<&GameClient>, game_id: Uuid) -> Result<(), AppError> {
+    match client {
+        Some(client) if client.game_id != game_id => Err(AppError::Forbidden("API key has no access to this game".to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// Checks if the given game client has access to the level with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no level could be found with the given id.
+/// - the level belongs to another game than the game client.
+///
+pub fn authorize_level(client: Option<&GameClient>, level_id: Uuid, conn: &mut Connection) -> Result<(), AppError> {
+    if client.is_none() {
+        return Ok(());
+    }
+
+    let level = level_service::find_by_id(level_id, conn)?;
+    authorize_game(client, level.game_id)
+}
+
+/// Checks if the given game client has access to the user with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no user could be found with the given id.
+/// - the user belongs to another game than the game client.
+///
+pub fn authorize_user(client: Option<&GameClient>, user_id: Uuid, conn: &mut Connection) -> Result<(), AppError> {
+    if client.is_none() {
+        return Ok(());
+    }
+
+    let user = user_service::find_by_id(user_id, conn)?;
+    authorize_game(client, user.game_id)
+}
+
+/// Checks if the given game client has access to the score with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no score could be found with the given id.
+/// - the score belongs to another game than the game client.
+///
+pub fn authorize_score(client: Option<&GameClient>, score_id: Uuid, conn: &mut Connection) -> Result<(), AppError> {
+    if client.is_none() {
+        return Ok(());
+    }
+
+    match Score::find_by_id(score_id, conn) {
+        Ok(score) => match score.level {
+            Some(level) => authorize_game(client, level.game_id),
+            None => Err(AppError::Forbidden("API key has no access to this score".to_string())),
+        },
+        Err(_) => Err(AppError::NotFound(format!(
+            "Score with id '{}' not found",
+            score_id
+        ))),
+    }
+}
+
+/// Creates the database representation of a key with the given secret.
+fn new_api_key(name: String, game_id: Uuid, secret: &str) -> NewApiKey {
+    NewApiKey {
+        name,
+        prefix: secret.chars().take(VISIBLE_KEY_LENGTH).collect(),
+        key_hash: hash_secret(secret),
+        game_id,
+    }
+}
+
+/// Generates a new random API key secret.
+fn generate_secret() -> Result<String, AppError> {
+    let mut bytes = [0u8; 32];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        return Err(AppError::Internal("Could not generate API key".to_string()));
+    }
+
+    Ok(format!("{}{}", KEY_PREFIX, URL_SAFE_NO_PAD.encode(bytes)))
+}
+
+/// Hashes a secret, like an API key, with SHA-256 and returns the hex encoded hash.
+pub fn hash_secret(secret: &str) -> String {
+    digest(&SHA256, secret.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,204 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::result::{DatabaseErrorKind, Error};
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        archive::{ArchiveIds, ArchiveImportQuery, GameArchive, NameConflict},
+        game::Game,
+    },
+    response::AppError,
+};
+
+use super::name_service;
+
+/// The maximum size in bytes of an imported archive.
+pub const MAX_ARCHIVE_SIZE: usize = 100 * 1024 * 1024;
+
+/// The maximum length of the name of a game.
+const MAX_NAME_LENGTH: usize = 50;
+
+/// Reads the game with the given id into an archive. The signing secret of the game and the credentials of the
+/// players are left out, unless the secrets are included.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no game could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn export_game(id: Uuid, include_secrets: bool, conn: &mut Connection) -> Result<GameArchive, AppError> {
+    let mut archive = match GameArchive::load(id, conn) {
+        Ok(archive) => archive,
+        Err(Error::NotFound) => return Err(AppError::NotFound(format!("Game with id '{}' not found", id))),
+        Err(_) => return Err(AppError::Internal("Cannot export game".to_string())),
+    };
+
+    if !include_secrets {
+        archive.game.signing_secret = None;
+        for user in &mut archive.users {
+            user.password_hash = None;
+            user.device_id_hash = None;
+        }
+    }
+
+    Ok(archive)
+}
+
+/// Creates the game of the archive with its levels, users and scores. Returns the imported game, together with
+/// the game it replaced.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - a score of the archive references a level or user that is not part of the archive.
+/// - a game with the same name exists and the conflict is not resolved by renaming or replacing it.
+/// - the ids of the archive are preserved and one of them already exists.
+/// - one of the rows of the archive contains invalid data.
+/// - an error occurred during execution.
+///
+pub fn import_game(
+    mut archive: GameArchive,
+    query: &ArchiveImportQuery,
+    conn: &mut Connection,
+) -> Result<(Game, Option<Game>), AppError> {
+    check_references(&archive)?;
+
+    let mut replaced = None;
+    if let Some(existing) = find_by_name(&archive.game.name, conn)? {
+        match query.on_conflict {
+            NameConflict::Fail => {
+                return Err(AppError::Conflict("A game with this name already exists".to_string()));
+            }
+            NameConflict::Rename => archive.game.name = available_name(&archive.game.name, conn)?,
+            NameConflict::Replace => {
+                // The replaced game is deleted like any other game, so it can still be restored until it is
+                // purged. It is renamed to free its name for the imported game.
+                let free_name = available_name(&existing.name, conn)?;
+                if Game::delete(existing.id, conn).is_err() || Game::rename(existing.id, &free_name, conn).is_err() {
+                    return Err(AppError::Internal("Error occurred when replacing game".to_string()));
+                }
+                replaced = Some(existing);
+            }
+        }
+    }
+
+    match query.ids {
+        ArchiveIds::New => assign_new_ids(&mut archive),
+        ArchiveIds::Preserve => match archive.ids_exist(conn) {
+            Ok(false) => {}
+            Ok(true) => {
+                return Err(AppError::Conflict(
+                    "The archive contains ids that already exist, import it with new ids instead".to_string(),
+                ));
+            }
+            Err(_) => return Err(AppError::Internal("Cannot check ids of archive".to_string())),
+        },
+    }
+
+    let game_id = archive.game.id;
+    for level in &mut archive.levels {
+        level.game_id = game_id;
+    }
+    for user in &mut archive.users {
+        user.game_id = game_id;
+        user.canonical_name = name_service::canonicalize(&user.name);
+    }
+    for score in &mut archive.scores {
+        if score.achieved_at.is_none() {
+            score.achieved_at = if score.is_personal_best {
+                score.updated_at.or(Some(score.created_at))
+            } else {
+                Some(score.created_at)
+            };
+        }
+    }
+
+    match archive.insert(conn) {
+        Ok(_) => {}
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            return Err(AppError::Conflict(
+                "The archive contains levels or users with the same name".to_string(),
+            ));
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    match Game::find_by_id(game_id, conn) {
+        Ok(game) => Ok((game, replaced)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Checks if every score references a level and user of the archive.
+fn check_references(archive: &GameArchive) -> Result<(), AppError> {
+    let level_ids: HashSet<Uuid> = archive.levels.iter().map(|level| level.id).collect();
+    let user_ids: HashSet<Uuid> = archive.users.iter().map(|user| user.id).collect();
+
+    for score in &archive.scores {
+        if score.level_id.is_some_and(|id| !level_ids.contains(&id)) {
+            return Err(AppError::BadRequest(format!(
+                "Score '{}' references a level that is not part of the archive",
+                score.id
+            )));
+        }
+        if score.user_id.is_some_and(|id| !user_ids.contains(&id)) {
+            return Err(AppError::BadRequest(format!(
+                "Score '{}' references a user that is not part of the archive",
+                score.id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Gives every row of the archive a new id, and updates the references between the rows.
+fn assign_new_ids(archive: &mut GameArchive) {
+    archive.game.id = Uuid::new_v4();
+
+    let mut levels = HashMap::new();
+    for level in &mut archive.levels {
+        let id = Uuid::new_v4();
+        levels.insert(level.id, id);
+        level.id = id;
+    }
+
+    let mut users = HashMap::new();
+    for user in &mut archive.users {
+        let id = Uuid::new_v4();
+        users.insert(user.id, id);
+        user.id = id;
+    }
+
+    for score in &mut archive.scores {
+        score.id = Uuid::new_v4();
+        score.level_id = score.level_id.and_then(|id| levels.get(&id).copied());
+        score.user_id = score.user_id.and_then(|id| users.get(&id).copied());
+    }
+}
+
+/// Finds the game with the given name, including a deleted game.
+fn find_by_name(name: &str, conn: &mut Connection) -> Result<Option<Game>, AppError> {
+    match Game::find_by_name(name, conn) {
+        Ok(game) => Ok(game),
+        Err(_) => Err(AppError::Internal("Cannot check name of game".to_string())),
+    }
+}
+
+/// Finds the first numbered variant of the name that is not in use, e.g. `Game (2)`.
+fn available_name(name: &str, conn: &mut Connection) -> Result<String, AppError> {
+    for number in 2..100 {
+        let suffix = format!(" ({})", number);
+        let base: String = name.chars().take(MAX_NAME_LENGTH - suffix.len()).collect();
+        let candidate = format!("{}{}", base.trim_end(), suffix);
+
+        if find_by_name(&candidate, conn)?.is_none() {
+            return Ok(candidate);
+        }
+    }
+
+    Err(AppError::Conflict("No available name found for the game".to_string()))
+}
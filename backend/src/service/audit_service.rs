@@ -0,0 +1,155 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        audit_log::{Actor, AuditAction, AuditEntity, AuditFilter, AuditLog, AuditSort, NewAuditLog},
+        pagination::{Page, PageError, PageQuery},
+    },
+    response::AppError,
+};
+
+/// Queries the database and fetches a page of the audit log matching the given filter.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the cursor of the page is invalid.
+/// - an error occurred during execution.
+///
+pub fn find_all(
+    filter: &AuditFilter,
+    page: &PageQuery<AuditSort>,
+    conn: &mut Connection,
+) -> Result<Page<AuditLog>, AppError> {
+    match AuditLog::find_page(filter, page, conn) {
+        Ok(entries) => Ok(entries),
+        Err(PageError::InvalidCursor) => Err(AppError::BadRequest("Invalid cursor".to_string())),
+        Err(_) => Err(AppError::Internal("Cannot fetch audit log".to_string())),
+    }
+}
+
+/// Records the creation of an entity.
+///
+/// # Errors
+/// - If an error occurred during execution.
+pub fn created<T: Serialize>(
+    actor: &Actor,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    after: &T,
+    conn: &mut Connection,
+) -> Result<(), AppError> {
+    record(actor, AuditAction::Create, entity, entity_id, None, Some(after), conn)
+}
+
+/// Runs the update of an existing entity and records it, with the entity before and after the update. The entity
+/// is fetched with `find` before and after the update, the update is given the entity before the update to
+/// authorize the change against.
+///
+/// # Errors
+/// - If the entity cannot be found.
+/// - If the update fails.
+/// - If an error occurred during execution.
+pub fn record_update<T: Serialize, R>(
+    actor: &Actor,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    find: impl Fn(&mut Connection) -> Result<T, AppError>,
+    update: impl FnOnce(&T, &mut Connection) -> Result<R, AppError>,
+    conn: &mut Connection,
+) -> Result<R, AppError> {
+    let before = find(conn)?;
+    let result = update(&before, conn)?;
+    let after = find(conn)?;
+    record(actor, AuditAction::Update, entity, entity_id, Some(&before), Some(&after), conn)?;
+    Ok(result)
+}
+
+/// Runs the deletion of an existing entity and records it, with the entity before it was deleted. The entity is
+/// fetched with `find` before the deletion, the deletion is given the entity to authorize the change against.
+///
+/// # Errors
+/// - If the entity cannot be found.
+/// - If the deletion fails.
+/// - If an error occurred during execution.
+pub fn record_delete<T: Serialize, R>(
+    actor: &Actor,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    find: impl FnOnce(&mut Connection) -> Result<T, AppError>,
+    delete: impl FnOnce(&T, &mut Connection) -> Result<R, AppError>,
+    conn: &mut Connection,
+) -> Result<R, AppError> {
+    let before = find(conn)?;
+    let result = delete(&before, conn)?;
+    deleted(actor, entity, entity_id, &before, conn)?;
+    Ok(result)
+}
+
+/// Records the deletion of an entity, with the entity before it was deleted.
+///
+/// # Errors
+/// - If an error occurred during execution.
+pub fn deleted<T: Serialize>(
+    actor: &Actor,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    before: &T,
+    conn: &mut Connection,
+) -> Result<(), AppError> {
+    record(actor, AuditAction::Delete, entity, entity_id, Some(before), None, conn)
+}
+
+/// Records the restoration of a deleted entity, with the entity after it was restored.
+///
+/// # Errors
+/// - If an error occurred during execution.
+pub fn restored<T: Serialize>(
+    actor: &Actor,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    after: &T,
+    conn: &mut Connection,
+) -> Result<(), AppError> {
+    record(actor, AuditAction::Restore, entity, entity_id, None, Some(after), conn)
+}
+
+/// Adds an entry to the audit log. Only the changes of dashboard users are recorded, scores and players created
+/// by game clients are not administrative changes and would flood the log.
+fn record<T: Serialize>(
+    actor: &Actor,
+    action: AuditAction,
+    entity: AuditEntity,
+    entity_id: Uuid,
+    before: Option<&T>,
+    after: Option<&T>,
+    conn: &mut Connection,
+) -> Result<(), AppError> {
+    if !matches!(actor, Actor::User(_)) {
+        return Ok(());
+    }
+
+    let entry = NewAuditLog {
+        actor: actor.id(),
+        action,
+        entity_type: entity,
+        entity_id,
+        before: before.map(snapshot).transpose()?,
+        after: after.map(snapshot).transpose()?,
+    };
+
+    match AuditLog::insert(entry, conn) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(AppError::Internal("Could not record change in audit log".to_string())),
+    }
+}
+
+/// Serializes an entity to be stored in the audit log.
+fn snapshot<T: Serialize>(value: &T) -> Result<serde_json::Value, AppError> {
+    match serde_json::to_value(value) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(AppError::Internal("Could not record change in audit log".to_string())),
+    }
+}
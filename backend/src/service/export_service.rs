@@ -0,0 +1,199 @@
+use std::{io, mem};
+
+use chrono::Utc;
+use log::error;
+use tokio::{sync::mpsc, task::spawn_blocking};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    config::db::{Connection, Pool},
+    models::{
+        export::{ExportFormat, ExportQuery, ExportScope, ScoreExportRow},
+        score::Score,
+    },
+    response::AppError,
+};
+
+use super::{game_service, level_service, user_service};
+
+/// The size in bytes the rows of an export are buffered to before they are sent to the client.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The number of chunks that can be waiting to be sent, after which reading from the database pauses until the
+/// client catches up.
+const MAX_PENDING_CHUNKS: usize = 16;
+
+/// The columns of a score export.
+const COLUMNS: [&str; 10] = [
+    "id",
+    "level_id",
+    "level",
+    "user_id",
+    "user",
+    "username",
+    "score",
+    "is_hidden",
+    "created_at",
+    "updated_at",
+];
+
+/// The chunks of an export, streamed to the client while the rows are read from the database.
+pub type ExportStream = ReceiverStream<Result<Vec<u8>, io::Error>>;
+
+/// Creates the name of the file of an export, based on the name of the exported game, level or user.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no game, level or user could be found with the id of the scope.
+///
+pub fn file_name(scope: ExportScope, format: ExportFormat, conn: &mut Connection) -> Result<String, AppError> {
+    let name = match scope {
+        ExportScope::Game(id) => game_service::find_by_id(id, conn)?.name,
+        ExportScope::Level(id) => level_service::find_by_id(id, conn)?.name,
+        ExportScope::User(id) => user_service::find_by_id(id, conn)?.name,
+    };
+
+    Ok(format!(
+        "{}-scores-{}.{}",
+        slugify(&name),
+        Utc::now().format("%Y%m%d"),
+        format.extension()
+    ))
+}
+
+/// Streams the scores of the export in the requested format. The rows are read on a separate connection while
+/// the client downloads the file, an error that occurs halfway ends the stream with an error, so the client
+/// doesn't mistake a partial file for a complete export.
+pub fn stream_scores(pool: Pool, scope: ExportScope, filter: ExportQuery) -> ExportStream {
+    let (sender, receiver) = mpsc::channel(MAX_PENDING_CHUNKS);
+
+    spawn_blocking(move || {
+        let mut conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(err) => {
+                error!("Could not get a database connection: {}", err);
+                let _ = sender.blocking_send(Err(io::Error::other("Database is unavailable")));
+                return;
+            }
+        };
+
+        let mut writer = ExportWriter::new(filter.format);
+        let result = Score::for_each_exported(scope, &filter, &mut conn, |row| {
+            writer.write(row.into());
+            match writer.take_full_chunk() {
+                Some(chunk) => sender.blocking_send(Ok(chunk)).is_ok(),
+                None => true,
+            }
+        });
+
+        match result {
+            Ok(_) => {
+                let _ = sender.blocking_send(Ok(writer.finish()));
+            }
+            Err(err) => {
+                error!("Could not export scores: {}", err);
+                let _ = sender.blocking_send(Err(io::Error::other("Could not export scores")));
+            }
+        }
+    });
+
+    ReceiverStream::new(receiver)
+}
+
+/// Writes the rows of an export to a buffer in the format of the export.
+struct ExportWriter {
+    format: ExportFormat,
+    buffer: Vec<u8>,
+}
+
+impl ExportWriter {
+    /// Creates the writer and writes the start of the file.
+    fn new(format: ExportFormat) -> Self {
+        let mut writer = ExportWriter {
+            format,
+            buffer: Vec::with_capacity(CHUNK_SIZE),
+        };
+        if format == ExportFormat::Excel {
+            writer.buffer.extend_from_slice("\u{feff}".as_bytes());
+        }
+        if format != ExportFormat::Ndjson {
+            // Writing to a buffer in memory cannot fail.
+            let _ = writer.csv().write_record(COLUMNS);
+        }
+
+        writer
+    }
+
+    fn write(&mut self, mut row: ScoreExportRow) {
+        // Writing to a buffer in memory cannot fail, and every row can be serialized.
+        match self.format {
+            ExportFormat::Csv => {
+                let _ = self.csv().serialize(row);
+            }
+            ExportFormat::Excel => {
+                row.level = row.level.map(escape_formula);
+                row.user = row.user.map(escape_formula);
+                row.username = row.username.map(escape_formula);
+                let _ = self.csv().serialize(row);
+            }
+            ExportFormat::Ndjson => {
+                let _ = serde_json::to_writer(&mut self.buffer, &row);
+                self.buffer.push(b'\n');
+            }
+        }
+    }
+
+    /// Creates a CSV writer that appends to the buffer, the writer is flushed when it is dropped.
+    fn csv(&mut self) -> csv::Writer<&mut Vec<u8>> {
+        let terminator = match self.format {
+            ExportFormat::Excel => csv::Terminator::CRLF,
+            _ => csv::Terminator::Any(b'\n'),
+        };
+
+        csv::WriterBuilder::new()
+            .has_headers(false)
+            .terminator(terminator)
+            .buffer_capacity(1024)
+            .from_writer(&mut self.buffer)
+    }
+
+    /// Takes the written rows when they fill a chunk.
+    fn take_full_chunk(&mut self) -> Option<Vec<u8>> {
+        if self.buffer.len() < CHUNK_SIZE {
+            return None;
+        }
+
+        Some(mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE)))
+    }
+
+    /// Takes the rows that are not sent yet.
+    fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Prefixes text that a spreadsheet would read as a formula with a quote, so it is shown as text.
+fn escape_formula(value: String) -> String {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value
+    }
+}
+
+/// Turns a name into a part of a file name, keeping only ASCII letters and digits.
+pub fn slugify(name: &str) -> String {
+    let slug = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+
+    if slug.is_empty() {
+        "export".to_string()
+    } else {
+        slug
+    }
+}
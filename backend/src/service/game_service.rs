@@ -1,25 +1,31 @@
+use diesel::result::{DatabaseErrorKind, Error};
 use uuid::Uuid;
 
 use crate::{
-    config::db::Pool,
+    config::db::Connection,
     models::{
-        game::{Game, GameDTO},
+        audit_log::Actor,
+        auth::Role,
+        game::{Game, GameDTO, GameSort},
         level::{Level, LevelForm},
+        pagination::{Page, PageError, PageQuery},
     },
-    response::{ErrorResponse, ResponseBody},
+    response::{AppError, FieldError},
 };
 
-/// Queries the database and fetches all the registered games.
+/// Queries the database and fetches a page of the registered games.
 ///
 /// # Errors
 ///
 /// This function fails if:
+/// - the cursor of the page is invalid.
 /// - an error occurred during execution.
 ///
-pub fn find_all(pool: &Pool) -> Result<Vec<Game>, ErrorResponse> {
-    match Game::find_all(&mut pool.get().unwrap()) {
+pub fn find_all(page: &PageQuery<GameSort>, conn: &mut Connection) -> Result<Page<Game>, AppError> {
+    match Game::find_page(page, conn) {
         Ok(games) => Ok(games),
-        Err(_) => Err(ResponseBody::internal_error("Cannot fetch games")),
+        Err(PageError::InvalidCursor) => Err(AppError::BadRequest("Invalid cursor".to_string())),
+        Err(_) => Err(AppError::Internal("Cannot fetch games".to_string())),
     }
 }
 
@@ -31,12 +37,12 @@ pub fn find_all(pool: &Pool) -> Result<Vec<Game>, ErrorResponse> {
 /// - an error occurred during execution.
 /// - could not find game with given id.
 ///
-pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<Game, ErrorResponse> {
-    match Game::find_by_id(id, &mut pool.get().unwrap()) {
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<Game, AppError> {
+    match Game::find_by_id(id, conn) {
         Ok(game) => Ok(game),
-        Err(_) => Err(ResponseBody::not_found_error(&format!(
+        Err(_) => Err(AppError::NotFound(format!(
             "Game with id '{}' not found",
-            id.to_string()
+            id
         ))),
     }
 }
@@ -47,26 +53,31 @@ pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<Game, ErrorResponse> {
 /// # Errors
 ///
 /// This function fails if:
+/// - a game with the same name already exists.
 /// - an error occurred during execution.
 ///
-pub fn insert(new_game: GameDTO, pool: &Pool) -> Result<Game, ErrorResponse> {
-    match Game::insert(new_game, &mut pool.get().unwrap()) {
+pub fn insert(new_game: GameDTO, conn: &mut Connection) -> Result<Game, AppError> {
+    match Game::insert(new_game, conn) {
         Ok(game) => {
             let level = LevelForm {
                 name: "Level 1".to_owned(),
                 game_id: game.id,
+                sort_direction: None,
+                score_unit: None,
+                aggregation: None,
+                min_score: None,
+                max_score: None,
+                max_submissions: None,
+                submission_window: None,
             };
 
-            match Level::insert(level, &mut pool.get().unwrap()) {
+            match Level::insert(level, conn) {
                 Ok(_) => Ok(game),
-                Err(_) => Err(ResponseBody::internal_error(
-                    "Could not add level to newly created game",
-                )),
+                Err(err) => Err(err.into()),
             }
         }
-        Err(_) => Err(ResponseBody::internal_error(
-            "Could not add new game in database",
-        )),
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(name_in_use()),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -77,18 +88,20 @@ pub fn insert(new_game: GameDTO, pool: &Pool) -> Result<Game, ErrorResponse> {
 /// This function fails if:
 /// - an error occurred during execution.
 /// - no game could be found with the given id.
+/// - a game with the same name already exists.
 ///
-pub fn update(id: Uuid, updated_game: GameDTO, pool: &Pool) -> Result<Game, ErrorResponse> {
-    if !game_exisits(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn update(id: Uuid, updated_game: GameDTO, conn: &mut Connection) -> Result<Game, AppError> {
+    if !game_exisits(id, conn) {
+        return Err(AppError::NotFound(format!(
             "Game with id '{}' not found",
-            id.to_string()
+            id
         )));
     }
 
-    match Game::update(id, updated_game, &mut pool.get().unwrap()) {
+    match Game::update(id, updated_game, conn) {
         Ok(game) => Ok(game),
-        Err(_) => Err(ResponseBody::internal_error("Could not update game")),
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(name_in_use()),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -100,25 +113,121 @@ pub fn update(id: Uuid, updated_game: GameDTO, pool: &Pool) -> Result<Game, Erro
 /// - an error occurred during execution.
 /// - no game could be found with the given id.
 ///
-pub fn delete(id: Uuid, pool: &Pool) -> Result<usize, ErrorResponse> {
-    if !game_exisits(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn delete(id: Uuid, conn: &mut Connection) -> Result<usize, AppError> {
+    if !game_exisits(id, conn) {
+        return Err(AppError::NotFound(format!(
             "Game with id '{}' not found",
-            id.to_string()
+            id
         )));
     }
 
-    match Game::delete(id, &mut pool.get().unwrap()) {
+    match Game::delete(id, conn) {
         Ok(result) => Ok(result),
-        Err(_) => Err(ResponseBody::internal_error(
-            "Error occurred when deleting game",
-        )),
+        Err(_) => Err(AppError::Internal("Error occurred when deleting game".to_string())),
+    }
+}
+
+/// Restores a deleted game with the given id, together with the levels, users and scores that were deleted with
+/// the game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - an error occurred during execution.
+/// - no deleted game could be found with the given id.
+///
+pub fn restore(id: Uuid, conn: &mut Connection) -> Result<Game, AppError> {
+    match Game::restore(id, conn) {
+        Ok(game) => Ok(game),
+        Err(Error::NotFound) => Err(AppError::NotFound(format!(
+            "Deleted game with id '{}' not found",
+            id
+        ))),
+        Err(_) => Err(AppError::Internal("Error occurred when restoring game".to_string())),
     }
 }
 
 /// Checks if a game exists in the database with the given id.
-pub fn game_exisits(id: Uuid, pool: &Pool) -> bool {
-    let game = Game::find_by_id(id, &mut pool.get().unwrap());
+pub fn game_exisits(id: Uuid, conn: &mut Connection) -> bool {
+    let game = Game::find_by_id(id, conn);
 
     game.is_ok()
 }
+
+/// The error returned when the name of a game is already used by another game.
+fn name_in_use() -> AppError {
+    AppError::Conflict("A game with this name already exists".to_string())
+}
+
+/// Sets the owner of a new or updated game. Only admins can choose the owner of a game, a new game created by
+/// another dashboard user is owned by that user.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - a dashboard user that is not an admin sets the owner.
+/// - the owner is empty or longer than 255 characters.
+///
+pub fn assign_owner(actor: &Actor, game: &mut GameDTO, is_new: bool) -> Result<(), AppError> {
+    let Actor::User(user) = actor else {
+        return Ok(());
+    };
+
+    match &game.owner {
+        Some(_) if !user.has_any_role(&[Role::Admin]) => {
+            Err(AppError::Forbidden("Only admins can change the owner of a game".to_string()))
+        }
+        Some(owner) if owner.is_empty() || owner.len() > 255 => Err(AppError::Validation(
+            "Invalid game".to_string(),
+            vec![FieldError::new("owner", "must be between 1 and 255 characters")],
+        )),
+        None if is_new => {
+            game.owner = Some(user.sub.clone());
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks if the actor can manage the game with the given id. Admins can manage every game, game owners only the
+/// games they own. Game clients and players are not checked, their access is restricted by their API key.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - the dashboard user is not an admin and doesn't own the game.
+///
+pub fn authorize_owner(actor: &Actor, game_id: Uuid, conn: &mut Connection) -> Result<(), AppError> {
+    authorize(actor, game_id, &[Role::Admin], conn)
+}
+
+/// Checks if the actor can moderate the users and scores of the game with the given id. Admins and moderators can
+/// moderate every game, game owners only the games they own.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - the dashboard user is not an admin or moderator and doesn't own the game.
+///
+pub fn authorize_moderator(actor: &Actor, game_id: Uuid, conn: &mut Connection) -> Result<(), AppError> {
+    authorize(actor, game_id, &[Role::Admin, Role::Moderator], conn)
+}
+
+/// Checks if the dashboard user has one of the given roles, which apply to every game, or owns the game.
+fn authorize(actor: &Actor, game_id: Uuid, global_roles: &[Role], conn: &mut Connection) -> Result<(), AppError> {
+    let Actor::User(user) = actor else {
+        return Ok(());
+    };
+    if user.has_any_role(global_roles) {
+        return Ok(());
+    }
+
+    let game = find_by_id(game_id, conn)?;
+    if user.has_any_role(&[Role::GameOwner]) && game.owner.as_deref() == Some(user.sub.as_str()) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("You are not allowed to manage this game".to_string()))
+    }
+}
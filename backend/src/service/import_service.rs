@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        import::{ImportReport, ImportRowResult, ImportStatus, NewImportedScore, ScoreImportRow},
+        level::Level,
+        score::{Score, ScoreDto, ScoreForm},
+        user::User,
+    },
+    response::{AppError, FieldError},
+};
+
+use super::{
+    game_service,
+    name_service::{self, NameFilter},
+    score_service,
+};
+
+/// The maximum number of rows in a single import.
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+/// The maximum size in bytes of the body of an import.
+pub const MAX_IMPORT_SIZE: usize = 10 * 1024 * 1024;
+
+/// The formats scores can be imported from, determined by the content type of the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// A CSV file with a header row, using the fields of [`ScoreImportRow`] as column names.
+    Csv,
+    /// A JSON array of [`ScoreImportRow`] objects.
+    Json,
+}
+
+/// The fields that identify a score submitted at a known moment, used to skip scores that were already imported.
+type ScoreKey = (Uuid, Option<Uuid>, Option<String>, i32, NaiveDateTime);
+
+impl ImportFormat {
+    /// Determines the format from the content type of the request.
+    ///
+    /// # Errors
+    /// - If the content type is missing or not supported.
+    pub fn from_content_type(content_type: Option<&str>) -> Result<ImportFormat, AppError> {
+        let mime = content_type
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_lowercase());
+
+        match mime.as_deref() {
+            Some("text/csv") => Ok(ImportFormat::Csv),
+            Some("application/json") => Ok(ImportFormat::Json),
+            _ => Err(AppError::BadRequest(
+                "Content type must be either 'text/csv' or 'application/json'".to_string(),
+            )),
+        }
+    }
+}
+
+/// Parses the rows of an import. A row that cannot be parsed is returned as an error, so it can be reported
+/// without rejecting the other rows.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the body is not a CSV file with a header row, or not a JSON array.
+/// - the body contains more than [`MAX_IMPORT_ROWS`] rows.
+///
+pub fn parse_rows(format: ImportFormat, body: &[u8]) -> Result<Vec<Result<ScoreImportRow, String>>, AppError> {
+    let rows: Vec<Result<ScoreImportRow, String>> = match format {
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body);
+            if reader.headers().is_err() {
+                return Err(AppError::BadRequest("Body must be a CSV file with a header row".to_string()));
+            }
+
+            reader
+                .deserialize::<ScoreImportRow>()
+                .map(|row| row.map_err(|err| err.to_string()))
+                .collect()
+        }
+        ImportFormat::Json => {
+            let Ok(values) = serde_json::from_slice::<Vec<serde_json::Value>>(body) else {
+                return Err(AppError::BadRequest("Body must be a JSON array of scores".to_string()));
+            };
+
+            values
+                .into_iter()
+                .map(|value| serde_json::from_value::<ScoreImportRow>(value).map_err(|err| err.to_string()))
+                .collect()
+        }
+    };
+
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(AppError::BadRequest(format!(
+            "An import can contain at most {} rows",
+            MAX_IMPORT_ROWS
+        )));
+    }
+
+    Ok(rows)
+}
+
+/// Validates the rows of an import for the game with the given id and stores the accepted scores. Levels and
+/// users are looked up by id or name within the game, user names are compared like player names. Rows are
+/// validated like scores submitted from the dashboard, without the submission limits of the levels. Returns the
+/// report of every row together with the stored scores, nothing is stored for a dry run.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no game could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn import_scores(
+    game_id: Uuid,
+    rows: Vec<Result<ScoreImportRow, String>>,
+    dry_run: bool,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<(ImportReport, Vec<ScoreDto>), AppError> {
+    let game = game_service::find_by_id(game_id, conn)?;
+    let (levels, users) = match (Level::find_by_game(&game, conn), User::find_by_game(&game, conn)) {
+        (Ok(levels), Ok(users)) => (levels, users),
+        _ => return Err(AppError::Internal("Cannot fetch levels and users of game".to_string())),
+    };
+
+    let level_ids: Vec<Uuid> = levels.iter().map(|level| level.id).collect();
+    let moments: Vec<NaiveDateTime> = rows
+        .iter()
+        .filter_map(|row| row.as_ref().ok().and_then(|row| row.created_at))
+        .collect();
+    let mut known: HashSet<ScoreKey> = match Score::find_by_levels_submitted_at(&level_ids, &moments, conn) {
+        Ok(scores) => scores
+            .into_iter()
+            .filter_map(|score| {
+                Some((score.level_id?, score.user_id, score.username, score.highscore, score.created_at))
+            })
+            .collect(),
+        Err(_) => return Err(AppError::Internal("Cannot fetch existing scores".to_string())),
+    };
+
+    let resolver = Resolver::new(&levels, &users);
+    let mut results = Vec::with_capacity(rows.len());
+    let mut accepted = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        let number = index + 1;
+        let new_score = match row {
+            Ok(row) => resolver.prepare(row, names),
+            Err(message) => Err(AppError::Validation(message, vec![])),
+        };
+
+        match new_score {
+            Ok(new_score) => {
+                let key = new_score.created_at.map(|moment| {
+                    (
+                        new_score.level_id,
+                        new_score.user_id,
+                        new_score.username.clone(),
+                        new_score.highscore,
+                        moment,
+                    )
+                });
+                if key.is_some_and(|key| !known.insert(key)) {
+                    let mut result = ImportRowResult::new(number, ImportStatus::Skipped);
+                    result.message = Some("Score already exists".to_string());
+                    results.push(result);
+                } else {
+                    results.push(ImportRowResult::new(number, ImportStatus::Accepted));
+                    accepted.push((index, new_score));
+                }
+            }
+            Err(err) => {
+                let (message, errors) = err.into_message();
+                let mut result = ImportRowResult::new(number, ImportStatus::Rejected);
+                result.message = Some(message);
+                result.errors = errors;
+                results.push(result);
+            }
+        }
+    }
+
+    if dry_run || accepted.is_empty() {
+        return Ok((ImportReport::new(results), vec![]));
+    }
+
+    let (indexes, new_scores): (Vec<usize>, Vec<NewImportedScore>) = accepted.into_iter().unzip();
+    let inserted = Score::insert_many(&new_scores, conn)?;
+
+    let scores = indexes
+        .into_iter()
+        .zip(inserted)
+        .map(|(index, score)| {
+            results[index].score_id = Some(score.id);
+            let level = score.level_id.and_then(|id| resolver.levels.get(&id).map(|level| (*level).clone()));
+            let user = score.user_id.and_then(|id| resolver.users.get(&id).map(|user| (*user).clone()));
+
+            (score, level, user).into()
+        })
+        .collect();
+
+    Ok((ImportReport::new(results), scores))
+}
+
+/// Looks up the levels and users of a game by id or name.
+struct Resolver<'a> {
+    levels: HashMap<Uuid, &'a Level>,
+    level_names: HashMap<&'a str, Vec<&'a Level>>,
+    users: HashMap<Uuid, &'a User>,
+    user_names: HashMap<String, &'a User>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(levels: &'a [Level], users: &'a [User]) -> Self {
+        let mut level_names: HashMap<&str, Vec<&Level>> = HashMap::new();
+        for level in levels {
+            level_names.entry(level.name.as_str()).or_default().push(level);
+        }
+
+        Resolver {
+            levels: levels.iter().map(|level| (level.id, level)).collect(),
+            level_names,
+            users: users.iter().map(|user| (user.id, user)).collect(),
+            user_names: users
+                .iter()
+                .map(|user| (name_service::canonicalize(&user.name), user))
+                .collect(),
+        }
+    }
+
+    /// Resolves the level and user of the row and validates the score.
+    fn prepare(&self, row: ScoreImportRow, names: &NameFilter) -> Result<NewImportedScore, AppError> {
+        let level = self.level(&row.level)?;
+        let user = match row.user.as_deref() {
+            Some(user) => Some(self.user(user)?),
+            None => None,
+        };
+
+        let mut new_score = ScoreForm {
+            username: row.username,
+            highscore: row.score,
+            is_hidden: row.is_hidden.unwrap_or_default(),
+            level_id: level.id,
+            user_id: user.map(|user| user.id),
+        };
+        new_score.validate()?;
+        score_service::check_username(&mut new_score, names)?;
+        score_service::validate_bounds(&new_score, level)?;
+
+        Ok(NewImportedScore {
+            username: new_score.username,
+            highscore: new_score.highscore,
+            is_hidden: new_score.is_hidden,
+            level_id: new_score.level_id,
+            user_id: new_score.user_id,
+            created_at: row.created_at,
+            achieved_at: row.created_at,
+        })
+    }
+
+    fn level(&self, value: &str) -> Result<&'a Level, AppError> {
+        if let Some(level) = value.parse::<Uuid>().ok().and_then(|id| self.levels.get(&id)) {
+            return Ok(level);
+        }
+
+        match self.level_names.get(value).map(Vec::as_slice) {
+            Some([level]) => Ok(level),
+            Some(_) => Err(invalid_row("level", "matches multiple levels, use the id instead")),
+            None => Err(invalid_row("level", "does not exist in this game")),
+        }
+    }
+
+    fn user(&self, value: &str) -> Result<&'a User, AppError> {
+        if let Some(user) = value.parse::<Uuid>().ok().and_then(|id| self.users.get(&id)) {
+            return Ok(user);
+        }
+
+        match self.user_names.get(&name_service::canonicalize(value)) {
+            Some(user) => Ok(user),
+            None => Err(invalid_row("user", "does not exist in this game")),
+        }
+    }
+}
+
+/// The error of a row with an invalid field.
+fn invalid_row(field: &str, message: &str) -> AppError {
+    AppError::Validation("Invalid score".to_string(), vec![FieldError::new(field, message)])
+}
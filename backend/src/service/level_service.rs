@@ -1,9 +1,12 @@
 use uuid::Uuid;
 
 use crate::{
-    config::db::Pool,
-    models::level::{Level, LevelForm},
-    response::{ErrorResponse, ResponseBody},
+    config::db::Connection,
+    models::{
+        level::{Level, LevelForm, LevelSort},
+        pagination::{Page, PageError, PageQuery},
+    },
+    response::{AppError, FieldError},
 };
 
 use super::game_service;
@@ -15,10 +18,10 @@ use super::game_service;
 /// This function fails if:
 /// - an error occurred during execution.
 ///
-pub fn find_all(pool: &Pool) -> Result<Vec<Level>, ErrorResponse> {
-    match Level::find_all(&mut pool.get().unwrap()) {
+pub fn find_all(conn: &mut Connection) -> Result<Vec<Level>, AppError> {
+    match Level::find_all(conn) {
         Ok(levels) => Ok(levels),
-        Err(_) => Err(ResponseBody::internal_error("Cannot fetch levels")),
+        Err(_) => Err(AppError::Internal("Cannot fetch levels".to_string())),
     }
 }
 
@@ -30,38 +33,42 @@ pub fn find_all(pool: &Pool) -> Result<Vec<Level>, ErrorResponse> {
 /// - an error occurred during execution.
 /// - could not find level with given id.
 ///
-pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<Level, ErrorResponse> {
-    match Level::find_by_id(id, &mut pool.get().unwrap()) {
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<Level, AppError> {
+    match Level::find_by_id(id, conn) {
         Ok(level) => Ok(level),
-        Err(_) => Err(ResponseBody::not_found_error(&format!(
+        Err(_) => Err(AppError::NotFound(format!(
             "Level with id '{}' not found",
-            id.to_string()
+            id
         ))),
     }
 }
 
-/// Queries the database and fetches the registered levels by the given game.
+/// Queries the database and fetches a page of the registered levels by the given game.
 ///
 /// # Errors
 ///
 /// This function fails if:
 /// - could not find game with given id.
+/// - the cursor of the page is invalid.
 /// - an error occurred during execution.
 ///
-pub fn find_by_game(game_id: Uuid, pool: &Pool) -> Result<Vec<Level>, ErrorResponse> {
-    let game = game_service::find_by_id(game_id, pool);
+pub fn find_by_game(
+    game_id: Uuid,
+    page: &PageQuery<LevelSort>,
+    conn: &mut Connection,
+) -> Result<Page<Level>, AppError> {
+    let game = game_service::find_by_id(game_id, conn);
     if game.is_err() {
-        return Err(ResponseBody::not_found_error(&format!(
+        return Err(AppError::NotFound(format!(
             "Game with id '{}' not found",
-            game_id.to_string()
+            game_id
         )));
     }
 
-    match Level::find_by_game(&game?, &mut pool.get().unwrap()) {
+    match Level::find_page_by_game(&game?, page, conn) {
         Ok(levels) => Ok(levels),
-        Err(_) => Err(ResponseBody::internal_error(
-            "Cannot add a new level in database",
-        )),
+        Err(PageError::InvalidCursor) => Err(AppError::BadRequest("Invalid cursor".to_string())),
+        Err(_) => Err(AppError::Internal("Cannot fetch levels".to_string())),
     }
 }
 
@@ -70,14 +77,15 @@ pub fn find_by_game(game_id: Uuid, pool: &Pool) -> Result<Vec<Level>, ErrorRespo
 /// # Errors
 ///
 /// This function fails if:
+/// - the validation rules of the level are invalid.
 /// - an error occurred during execution.
 ///
-pub fn insert(new_level: LevelForm, pool: &Pool) -> Result<Level, ErrorResponse> {
-    match Level::insert(new_level, &mut pool.get().unwrap()) {
+pub fn insert(new_level: LevelForm, conn: &mut Connection) -> Result<Level, AppError> {
+    validate(&new_level)?;
+
+    match Level::insert(new_level, conn) {
         Ok(level) => Ok(level),
-        Err(_) => Err(ResponseBody::internal_error(
-            "Cannot add a new level in database",
-        )),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -88,18 +96,21 @@ pub fn insert(new_level: LevelForm, pool: &Pool) -> Result<Level, ErrorResponse>
 /// This function fails if:
 /// - an error occurred during execution.
 /// - no level could be found with the given id.
+/// - the validation rules of the level are invalid.
 ///
-pub fn update(id: Uuid, updated_level: LevelForm, pool: &Pool) -> Result<Level, ErrorResponse> {
-    if !level_exists(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn update(id: Uuid, updated_level: LevelForm, conn: &mut Connection) -> Result<Level, AppError> {
+    if !level_exists(id, conn) {
+        return Err(AppError::NotFound(format!(
             "Level with id '{}' not found",
-            id.to_string()
+            id
         )));
     }
 
-    match Level::update(id, updated_level, &mut pool.get().unwrap()) {
+    validate(&updated_level)?;
+
+    match Level::update(id, updated_level, conn) {
         Ok(level) => Ok(level),
-        Err(_) => Err(ResponseBody::internal_error("Could not update level")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -111,21 +122,77 @@ pub fn update(id: Uuid, updated_level: LevelForm, pool: &Pool) -> Result<Level,
 /// - an error occurred during execution.
 /// - no level could be found with the given id.
 ///
-pub fn delete(id: Uuid, pool: &Pool) -> Result<usize, ErrorResponse> {
-    if !level_exists(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn delete(id: Uuid, conn: &mut Connection) -> Result<usize, AppError> {
+    if !level_exists(id, conn) {
+        return Err(AppError::NotFound(format!(
             "Level with id '{}' not found",
-            id.to_string()
+            id
         )));
     }
 
-    match Level::delete(id, &mut pool.get().unwrap()) {
+    match Level::delete(id, conn) {
         Ok(results) => Ok(results),
-        Err(_) => Err(ResponseBody::internal_error("Could not delete level")),
+        Err(_) => Err(AppError::Internal("Could not delete level".to_string())),
+    }
+}
+
+/// Restores a deleted level with the given id, together with the scores that were deleted with the level.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - an error occurred during execution.
+/// - no deleted level could be found with the given id.
+/// - the game of the level is deleted.
+///
+pub fn restore(id: Uuid, conn: &mut Connection) -> Result<Level, AppError> {
+    let Ok(level) = Level::find_deleted_by_id(id, conn) else {
+        return Err(AppError::NotFound(format!(
+            "Deleted level with id '{}' not found",
+            id
+        )));
+    };
+    if !game_service::game_exisits(level.game_id, conn) {
+        return Err(AppError::Conflict("Cannot restore level, its game is deleted".to_string()));
+    }
+
+    match Level::restore(id, conn) {
+        Ok(level) => Ok(level),
+        Err(_) => Err(AppError::Internal("Could not restore level".to_string())),
+    }
+}
+
+/// Checks if the score bounds and submission limit of the level are consistent.
+fn validate(level: &LevelForm) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+
+    if let (Some(min_score), Some(max_score)) = (level.min_score, level.max_score)
+        && min_score > max_score
+    {
+        errors.push(FieldError::new("max_score", "must be greater than or equal to min_score"));
+    }
+    if level.max_submissions.is_some_and(|max| max < 1) {
+        errors.push(FieldError::new("max_submissions", "must be at least 1"));
+    }
+    match (level.max_submissions, level.submission_window) {
+        (Some(_), None) => errors.push(FieldError::new(
+            "submission_window",
+            "is required when max_submissions is set",
+        )),
+        (_, Some(window)) if window < 1 => {
+            errors.push(FieldError::new("submission_window", "must be at least 1 second"))
+        }
+        _ => {}
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation("Invalid level".to_string(), errors))
     }
 }
 
 /// Checks if a level exists in the database with the given id.
-pub fn level_exists(id: Uuid, pool: &Pool) -> bool {
-    Level::find_by_id(id, &mut pool.get().unwrap()).is_ok()
+pub fn level_exists(id: Uuid, conn: &mut Connection) -> bool {
+    Level::find_by_id(id, conn).is_ok()
 }
@@ -1,6 +1,17 @@
+pub mod api_key_service;
+pub mod archive_service;
+pub mod audit_service;
+pub mod export_service;
 pub mod game_service;
+pub mod import_service;
 pub mod level_service;
+pub mod name_service;
 pub mod oauth2_service;
+pub mod player_service;
+pub mod purge_service;
 pub mod score_service;
+pub mod season_service;
+pub mod signature_service;
 pub mod stats_service;
+pub mod tournament_service;
 pub mod user_service;
\ No newline at end of file
@@ -0,0 +1,135 @@
+use std::{collections::HashSet, fs};
+
+use log::info;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{skeleton, MixedScript};
+
+use crate::{
+    config::{db::Connection, Config},
+    models::user::User,
+    response::{AppError, FieldError},
+};
+
+/// The maximum number of characters of a name after normalization.
+const MAX_NAME_LENGTH: usize = 50;
+
+/// Normalizes the names of players and checks them against the configured list of blocked words.
+pub struct NameFilter {
+    /// The canonical form of every blocked word.
+    blocked_words: HashSet<String>,
+}
+
+impl NameFilter {
+    /// Creates the filter from the words in the configuration and the words in the configured file, which contains
+    /// a single word per line. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Errors
+    /// - If the file with blocked words could not be read.
+    pub fn from_config(config: &Config) -> Result<NameFilter, String> {
+        let mut words = config.name_blocklist.clone();
+        if let Some(path) = &config.name_blocklist_file {
+            let content = fs::read_to_string(path)
+                .map_err(|err| format!("Cannot read name blocklist '{}': {}", path, err))?;
+            words.extend(content.lines().map(str::to_string));
+        }
+
+        let blocked_words: HashSet<String> = words
+            .iter()
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty() && !word.starts_with('#'))
+            .map(|word| collapse(&canonicalize(word)))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        info!("Loaded {} blocked words for player names", blocked_words.len());
+        Ok(NameFilter { blocked_words })
+    }
+
+    /// Normalizes the given name and checks if it can be shown on a leaderboard. Returns the normalized name.
+    ///
+    /// # Errors
+    /// - If the name is empty or too long after normalization.
+    /// - If the name contains control characters or mixes scripts.
+    /// - If the name contains a blocked word.
+    pub fn check(&self, field: &str, name: &str) -> Result<String, AppError> {
+        let name = normalize(name);
+        let message = if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            Some("must be between 1 and 50 characters")
+        } else if name.chars().any(char::is_control) {
+            Some("cannot contain control characters")
+        } else if !name.as_str().is_single_script() {
+            Some("cannot mix characters of different scripts")
+        } else if self.contains_blocked_word(&name) {
+            Some("contains a blocked word")
+        } else {
+            None
+        };
+
+        match message {
+            Some(message) => Err(AppError::Validation(
+                "Invalid name".to_string(),
+                vec![FieldError::new(field, message)],
+            )),
+            None => Ok(name),
+        }
+    }
+
+    /// Checks if a word of the name, or the name without separators, is a blocked word. Matching whole words
+    /// instead of substrings prevents blocking innocent names that happen to contain a blocked word.
+    fn contains_blocked_word(&self, name: &str) -> bool {
+        let name = canonicalize(name);
+
+        self.blocked_words.contains(&collapse(&name))
+            || name
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| self.blocked_words.contains(word))
+    }
+}
+
+/// Normalizes a name to NFKC, trims it and collapses every sequence of whitespace into a single space.
+pub fn normalize(name: &str) -> String {
+    name.nfkc()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the canonical form of a name, which is the same for names that only differ in case or in characters
+/// that look alike, e.g. `Bob`, `BOB` and `B0b`.
+pub fn canonicalize(name: &str) -> String {
+    skeleton(&normalize(name).to_lowercase())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Fills in the canonical name of users created before names were canonicalized.
+///
+/// # Errors
+/// - If the users could not be fetched.
+/// - If users have a name that looks like another user in the same game, these users are listed so they can be
+///   renamed.
+pub fn backfill_canonical_names(conn: &mut Connection) -> Result<(), String> {
+    let users = User::find_without_canonical_name(conn)
+        .map_err(|err| format!("Cannot fetch users without a canonical name, reason {}", err))?;
+
+    let conflicts: Vec<String> = users
+        .into_iter()
+        .filter(|(id, name)| User::set_canonical_name(*id, &canonicalize(name), conn).is_err())
+        .map(|(id, name)| format!("'{}' ({})", name, id))
+        .collect();
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Users with a name that looks like another user in the same game must be renamed first: {}",
+            conflicts.join(", ")
+        ))
+    }
+}
+
+/// Removes every character that is not alphanumeric, so `b.a.d` matches `bad`.
+fn collapse(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).collect()
+}
@@ -1,27 +1,249 @@
 use std::{
-    fs::File,
-    io::{prelude::*, BufReader},
+    str::FromStr,
+    time::{Duration, Instant},
 };
 
-use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{
+    jwk::{Jwk, JwkSet},
+    Algorithm, Validation,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::config::Config;
+
+/// The minimum time between two fetches of the JWKS that are triggered by a token with an unknown key id, so
+/// tokens with random key ids cannot be used to flood the authorization server with requests.
+pub const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The maximum time a request to the authorization server may take, so an unresponsive server cannot hold the
+/// lock of the JWKS cache and block every request that needs a key.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The JSON Web Key Set of the authorization server, held in memory and shared between requests.
+pub struct JwksCache {
+    url: String,
+    client: reqwest::Client,
+    keys: RwLock<JwkSet>,
+    /// The moment of the last fetch, also used to make sure only one fetch runs at the same time.
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl JwksCache {
+    /// Creates an empty cache for the JWKS at the given url, which is fetched with the given client.
+    pub fn new(url: &str, client: reqwest::Client) -> Self {
+        JwksCache {
+            url: url.to_string(),
+            client,
+            keys: RwLock::new(JwkSet { keys: vec![] }),
+            last_fetch: Mutex::new(None),
+        }
+    }
+
+    /// Fetches the JWKS from the authorization server and replaces the cached keys.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if:
+    /// - the JWKS could not be fetched.
+    /// - the content of the JWKS could not be parsed to a [`JwkSet`].
+    ///
+    pub async fn refresh(&self) -> Result<(), String> {
+        let mut last_fetch = self.last_fetch.lock().await;
+        self.fetch(&mut last_fetch).await
+    }
+
+    /// Finds the key with the given key id. When no key is found, the JWKS is fetched again in case the
+    /// authorization server rotated its keys, at most once every [`MIN_REFETCH_INTERVAL`]. A token without a key
+    /// id can only be verified when the JWKS contains a single key.
+    pub async fn find(&self, kid: Option<&str>) -> Option<Jwk> {
+        if let Some(key) = self.find_cached(kid).await {
+            return Some(key);
+        }
+
+        let mut last_fetch = self.last_fetch.lock().await;
+
+        // Another request may have fetched the keys while waiting for the lock.
+        if let Some(key) = self.find_cached(kid).await {
+            return Some(key);
+        }
+        if last_fetch.is_some_and(|fetched_at| fetched_at.elapsed() < MIN_REFETCH_INTERVAL) {
+            return None;
+        }
+
+        info!("No JWK found with key id '{}', fetching JWKS", kid.unwrap_or_default());
+        if let Err(err) = self.fetch(&mut last_fetch).await {
+            warn!("{}", err);
+            return None;
+        }
+
+        self.find_cached(kid).await
+    }
+
+    /// Finds the key with the given key id in the cached keys.
+    async fn find_cached(&self, kid: Option<&str>) -> Option<Jwk> {
+        let keys = self.keys.read().await;
+
+        match kid {
+            Some(kid) => keys.find(kid).cloned(),
+            None if keys.keys.len() == 1 => keys.keys.first().cloned(),
+            None => None,
+        }
+    }
+
+    /// Fetches the JWKS, the caller must hold the `last_fetch` lock.
+    async fn fetch(&self, last_fetch: &mut Option<Instant>) -> Result<(), String> {
+        *last_fetch = Some(Instant::now());
+
+        let response = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| format!("Could not fetch JWKS from {}, reason {}", self.url, err))?;
+        let keys = response
+            .json::<JwkSet>()
+            .await
+            .map_err(|err| format!("Could not parse JWKS from {}, reason {}", self.url, err))?;
+
+        info!("Fetched JWKS with {} keys", keys.keys.len());
+        *self.keys.write().await = keys;
 
-use crate::JWK_FILE_PATH;
+        Ok(())
+    }
+}
+
+/// The fields of an OpenID Connect discovery document that are used to validate access tokens.
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryDocument {
+    pub issuer: String,
+    pub jwks_uri: String,
+    #[serde(default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+}
+
+/// The authorization server that issues the access tokens, together with the rules access tokens are validated
+/// with.
+pub struct AuthProvider {
+    /// The expected `iss` claim, not validated when no issuer is configured.
+    pub issuer: Option<String>,
+    pub audience: String,
+    pub algorithms: Vec<Algorithm>,
+    pub jwks: JwksCache,
+}
+
+impl AuthProvider {
+    /// Creates the provider from the configuration. When an issuer is configured, its discovery document is
+    /// fetched to find the JWKS url and the supported algorithms, values set in the configuration take
+    /// precedence.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if:
+    /// - the HTTP client could not be created.
+    /// - the discovery document could not be fetched or parsed.
+    /// - the issuer of the discovery document doesn't match the configured issuer.
+    ///
+    pub async fn from_config(config: &Config) -> Result<AuthProvider, String> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|err| format!("Could not create HTTP client, reason {}", err))?;
+
+        let Some(issuer_url) = &config.oauth_issuer_url else {
+            return Ok(AuthProvider {
+                issuer: None,
+                audience: config.oauth_client_id.clone(),
+                algorithms: algorithms_or_default(config.oauth_algorithms.clone()),
+                jwks: JwksCache::new(config.oauth_jwks_url.as_deref().unwrap_or_default(), client),
+            });
+        };
+
+        let document = discover(&client, issuer_url).await?;
+        let algorithms = if config.oauth_algorithms.is_empty() {
+            document
+                .id_token_signing_alg_values_supported
+                .iter()
+                .filter_map(|algorithm| Algorithm::from_str(algorithm).ok())
+                .filter(|algorithm| !matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512))
+                .collect()
+        } else {
+            config.oauth_algorithms.clone()
+        };
+
+        Ok(AuthProvider {
+            issuer: Some(document.issuer),
+            audience: config.oauth_client_id.clone(),
+            algorithms: algorithms_or_default(algorithms),
+            jwks: JwksCache::new(config.oauth_jwks_url.as_deref().unwrap_or(&document.jwks_uri), client),
+        })
+    }
 
-/// Reads the contents of the JWKs file.
-/// 
+    /// Creates the validation rules for an access token signed with the given algorithm. The `exp`, `nbf`,
+    /// `aud` and, when configured, `iss` claims are validated.
+    ///
+    /// # Errors
+    /// - If the algorithm is not allowed.
+    pub fn validation(&self, algorithm: Algorithm) -> Result<Validation, String> {
+        if !self.algorithms.contains(&algorithm) {
+            return Err(format!("Algorithm {:?} is not allowed", algorithm));
+        }
+
+        let mut validation = Validation::new(algorithm);
+        validation.validate_nbf = true;
+        validation.set_audience(&[&self.audience]);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+            validation.set_required_spec_claims(&["exp", "aud", "iss"]);
+        } else {
+            validation.set_required_spec_claims(&["exp", "aud"]);
+        }
+
+        Ok(validation)
+    }
+}
+
+/// Fetches the OpenID Connect discovery document of the given issuer with the given client.
+///
 /// # Errors
-/// 
+///
 /// This function fails if:
-/// - The JWKS file cannot be opened
-/// - Content of the JWKS file cannot be read
-/// - Content of the JWKS file cannot be serialized to a [`Jwk`] struct
-/// 
-pub fn get_jwk_tokens() -> Result<Option<Jwk>, String> {
-    let file = File::open(JWK_FILE_PATH).map_err(|_| "Cannot open JWK file".to_string())?;
-    let mut buffer_reader = BufReader::new(file);
-    let mut contents = String::new();
-    buffer_reader.read_to_string(&mut contents).map_err(|_| "Cannot read content of JWK file".to_string())?;
-
-    let tokens: JwkSet = serde_json::from_str(&contents).map_err(|_| "Cannot create structs from JWK content".to_string())?;
-    Ok(tokens.keys.get(0).cloned())
+/// - the discovery document could not be fetched or parsed.
+/// - the issuer of the discovery document doesn't match the given issuer.
+///
+pub async fn discover(client: &reqwest::Client, issuer_url: &str) -> Result<DiscoveryDocument, String> {
+    let issuer_url = issuer_url.trim_end_matches('/');
+    let url = format!("{}/.well-known/openid-configuration", issuer_url);
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| format!("Could not fetch discovery document from {}, reason {}", url, err))?;
+    let document = response
+        .json::<DiscoveryDocument>()
+        .await
+        .map_err(|err| format!("Could not parse discovery document from {}, reason {}", url, err))?;
+
+    if document.issuer.trim_end_matches('/') != issuer_url {
+        return Err(format!(
+            "Issuer '{}' of the discovery document doesn't match '{}'",
+            document.issuer, issuer_url
+        ));
+    }
+
+    info!("Discovered OpenID Connect provider {}", document.issuer);
+    Ok(document)
+}
+
+/// Returns the given algorithms, or `RS256` when no algorithms are given.
+fn algorithms_or_default(algorithms: Vec<Algorithm>) -> Vec<Algorithm> {
+    if algorithms.is_empty() {
+        vec![Algorithm::RS256]
+    } else {
+        algorithms
+    }
 }
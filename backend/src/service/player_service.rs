@@ -0,0 +1,577 @@
+use std::{num::NonZeroU32, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use diesel::result::Error;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use log::warn;
+use ring::{
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use uuid::Uuid;
+
+use crate::{
+    config::{db::Connection, Config},
+    models::{
+        api_key::GameClient,
+        player::{
+            GuestForm, LoginForm, MergeForm, MergeResult, NewRefreshToken, Player, PlayerClaims, PlayerSession,
+            RefreshForm, RefreshToken, RegisterForm, UpgradeForm,
+        },
+        score::Score,
+        user::{NewPlayer, User},
+    },
+    response::{AppError, FieldError},
+};
+
+use super::{
+    api_key_service::{self, authorize_game, hash_secret},
+    name_service::{self, NameFilter},
+    season_service, user_service,
+};
+
+/// The `iss` claim of the access tokens issued to players.
+const PLAYER_TOKEN_ISSUER: &str = "babs";
+
+/// The `aud` claim of the access tokens issued to players, which keeps them apart from the tokens of the
+/// authorization server.
+const PLAYER_TOKEN_AUDIENCE: &str = "babs-player";
+
+/// The number of PBKDF2 iterations passwords are hashed with.
+const PASSWORD_ITERATIONS: u32 = 100_000;
+
+/// The prefix of a stored password hash, followed by the iterations, the salt and the hash.
+const PASSWORD_SCHEME: &str = "pbkdf2_sha256";
+
+/// Signs and verifies the access tokens of players. The tokens are signed with HS256 using the configured
+/// player token secret.
+pub struct PlayerTokenIssuer {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    access_token_ttl: Duration,
+    refresh_token_ttl: Duration,
+}
+
+impl PlayerTokenIssuer {
+    /// Creates the issuer from the configuration. When no secret is configured, a random secret is generated.
+    ///
+    /// # Errors
+    /// - If no random secret could be generated.
+    pub fn from_config(config: &Config) -> Result<PlayerTokenIssuer, String> {
+        let secret = match &config.player_token_secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                warn!("No player_token_secret configured, players are signed out when the server restarts");
+                random_bytes().map_err(|_| "Could not generate player token secret".to_string())?.to_vec()
+            }
+        };
+
+        Ok(PlayerTokenIssuer {
+            encoding_key: EncodingKey::from_secret(&secret),
+            decoding_key: DecodingKey::from_secret(&secret),
+            access_token_ttl: config.player_token_ttl,
+            refresh_token_ttl: config.player_refresh_token_ttl,
+        })
+    }
+
+    /// Verifies the given access token and returns the player it was issued to.
+    ///
+    /// # Errors
+    /// - If the token is invalid or expired.
+    pub fn verify(&self, token: &str) -> Result<Player, AppError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_audience(&[PLAYER_TOKEN_AUDIENCE]);
+        validation.set_issuer(&[PLAYER_TOKEN_ISSUER]);
+        validation.set_required_spec_claims(&["exp", "aud", "iss", "sub"]);
+
+        match jsonwebtoken::decode::<PlayerClaims>(token, &self.decoding_key, &validation) {
+            Ok(token) => Ok(Player {
+                user_id: token.claims.sub,
+                game_id: token.claims.game_id,
+            }),
+            Err(_) => Err(AppError::Unauthorized("Invalid player token".to_string())),
+        }
+    }
+
+    /// Signs a new access token for the given user.
+    fn sign(&self, user: &User) -> Result<String, AppError> {
+        let now = Utc::now().timestamp();
+        let claims = PlayerClaims {
+            sub: user.id,
+            game_id: user.game_id,
+            iss: PLAYER_TOKEN_ISSUER.to_string(),
+            aud: PLAYER_TOKEN_AUDIENCE.to_string(),
+            iat: now,
+            exp: now + self.access_token_ttl.as_secs() as i64,
+        };
+
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+            .map_err(|_| AppError::Internal("Could not sign player token".to_string()))
+    }
+}
+
+/// Registers a new player in the given game and signs the player in.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - neither a password nor a device id is given.
+/// - the game client belongs to another game.
+/// - the name is invalid or already taken, or the device id is already registered.
+/// - an error occurred during execution.
+///
+pub fn register(
+    form: RegisterForm,
+    client: Option<&GameClient>,
+    issuer: &PlayerTokenIssuer,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<PlayerSession, AppError> {
+    authorize_game(client, form.game_id)?;
+    if form.password.is_none() && form.device_id.is_none() {
+        return Err(AppError::Validation(
+            "A password or device id is required".to_string(),
+            vec![
+                FieldError::new("device_id", "is required without password"),
+                FieldError::new("password", "is required without device_id"),
+            ],
+        ));
+    }
+    let (name, canonical_name) = user_service::check_name(form.game_id, &form.name, None, names, conn)?;
+
+    let password_hash = match &form.password {
+        Some(password) => Some(hash_password(password)?),
+        None => None,
+    };
+    let new_player = NewPlayer {
+        name,
+        game_id: form.game_id,
+        password_hash,
+        device_id_hash: form.device_id.as_deref().map(hash_secret),
+        is_guest: false,
+        canonical_name,
+    };
+
+    match User::insert_player(new_player, conn) {
+        Ok(user) => create_session(user, issuer, conn),
+        Err(err) => match AppError::from(err) {
+            AppError::Conflict(_) => Err(AppError::Conflict("Name or device is already registered".to_string())),
+            err => Err(err),
+        },
+    }
+}
+
+/// Creates an anonymous guest in the given game with a generated device token and signs the guest in. The guest
+/// can later choose a name and password with [`upgrade`], or be merged into an existing player with [`merge`].
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the game client belongs to another game.
+/// - could not find the game with the given id.
+/// - an error occurred during execution.
+///
+pub fn create_guest(
+    form: GuestForm,
+    client: Option<&GameClient>,
+    issuer: &PlayerTokenIssuer,
+    conn: &mut Connection,
+) -> Result<PlayerSession, AppError> {
+    authorize_game(client, form.game_id)?;
+
+    let device_token = match random_bytes() {
+        Ok(bytes) => URL_SAFE_NO_PAD.encode(bytes),
+        Err(_) => return Err(AppError::Internal("Could not generate device token".to_string())),
+    };
+    let device_id_hash = hash_secret(&device_token);
+    let name = format!("Guest-{}", &device_id_hash[..8]);
+    let new_guest = NewPlayer {
+        canonical_name: name_service::canonicalize(&name),
+        name,
+        game_id: form.game_id,
+        password_hash: None,
+        device_id_hash: Some(device_id_hash),
+        is_guest: true,
+    };
+
+    match User::insert_player(new_guest, conn) {
+        Ok(user) => create_session_with_device_token(user, Some(device_token), issuer, conn),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Turns the signed in guest into a named player with the given name and password. The device token of the guest
+/// keeps working.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the signed in player is not a guest.
+/// - the name is invalid or already taken.
+/// - an error occurred during execution.
+///
+pub fn upgrade(
+    form: UpgradeForm,
+    player: &Player,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<User, AppError> {
+    let guest = find_player(player, conn)?;
+    if !guest.is_guest {
+        return Err(AppError::BadRequest("Only guests can be upgraded".to_string()));
+    }
+    let (name, canonical) = user_service::check_name(guest.game_id, &form.name, Some(guest.id), names, conn)?;
+
+    match User::upgrade_guest(guest.id, &name, &canonical, &hash_password(&form.password)?, conn) {
+        Ok(user) => Ok(user),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Merges the guest with the given device token into the signed in player. Every score of the guest is moved to
+/// the player, after which the guest is deleted. On levels that rank only the best score per user, only the best
+/// score of both is kept.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the device token doesn't belong to a guest of the same game.
+/// - the signed in player is a guest itself.
+/// - the guest or the player no longer exists.
+/// - an error occurred during execution.
+///
+pub fn merge(form: MergeForm, player: &Player, conn: &mut Connection) -> Result<MergeResult, AppError> {
+    let target = find_player(player, conn)?;
+    if target.is_guest {
+        return Err(AppError::BadRequest("Guests cannot be merged into another guest".to_string()));
+    }
+
+    let guest = match User::find_by_device_id_hash(target.game_id, &hash_secret(&form.device_token), conn) {
+        Ok(guest) if guest.is_guest => guest,
+        _ => return Err(AppError::NotFound("No guest found with the given device token".to_string())),
+    };
+
+    let reset_at = season_service::last_reset(target.game_id, conn)?;
+
+    match Score::merge_users(&guest, &target, reset_at, conn) {
+        Ok(moved_scores) => Ok(MergeResult { user: target, moved_scores }),
+        Err(Error::NotFound) => Err(AppError::NotFound("The guest or player no longer exists".to_string())),
+        Err(_) => Err(AppError::Internal("Could not merge guest".to_string())),
+    }
+}
+
+/// Signs a player in with either a name and password or a device id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the game client belongs to another game.
+/// - the credentials are missing or invalid.
+/// - an error occurred during execution.
+///
+pub fn login(
+    form: LoginForm,
+    client: Option<&GameClient>,
+    issuer: &PlayerTokenIssuer,
+    conn: &mut Connection,
+) -> Result<PlayerSession, AppError> {
+    authorize_game(client, form.game_id)?;
+
+    let user = match (&form.name, &form.password, &form.device_id) {
+        (Some(name), Some(password), _) => match User::find_by_canonical_name_with_password(
+            form.game_id,
+            &name_service::canonicalize(name),
+            conn,
+        ) {
+            Ok(user) if user.password_hash.as_deref().is_some_and(|hash| verify_password(password, hash)) => user,
+            _ => return Err(AppError::Unauthorized("Invalid credentials".to_string())),
+        },
+        (_, _, Some(device_id)) => match User::find_by_device_id_hash(form.game_id, &hash_secret(device_id), conn) {
+            Ok(user) => user,
+            Err(_) => return Err(AppError::Unauthorized("Invalid credentials".to_string())),
+        },
+        _ => {
+            return Err(AppError::BadRequest(
+                "A name and password or a device id is required".to_string(),
+            ))
+        }
+    };
+
+    create_session(user, issuer, conn)
+}
+
+/// Exchanges a refresh token for a new access token and refresh token. The used refresh token is revoked, so
+/// every refresh token can only be used once.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the refresh token is invalid, expired or already used.
+/// - the player belongs to another game than the game client.
+/// - an error occurred during execution.
+///
+pub fn refresh(
+    form: RefreshForm,
+    client: Option<&GameClient>,
+    issuer: &PlayerTokenIssuer,
+    conn: &mut Connection,
+) -> Result<PlayerSession, AppError> {
+    let Ok(current) = RefreshToken::find_active_by_hash(&hash_secret(&form.refresh_token), conn) else {
+        return Err(AppError::Unauthorized("Invalid refresh token".to_string()));
+    };
+    api_key_service::authorize_user(client, current.user_id, conn)?;
+
+    let user = match User::find_by_id(current.user_id, conn) {
+        Ok(user) => user,
+        Err(_) => return Err(AppError::Unauthorized("Invalid refresh token".to_string())),
+    };
+    let secret = generate_refresh_token()?;
+    let new_token = new_refresh_token(user.id, &secret, issuer);
+
+    match RefreshToken::rotate(current.id, new_token, conn) {
+        Ok(true) => new_session(user, secret, None, issuer),
+        Ok(false) => Err(AppError::Unauthorized("Invalid refresh token".to_string())),
+        Err(_) => Err(AppError::Internal("Could not refresh player token".to_string())),
+    }
+}
+
+/// Revokes the given refresh token, signing the player out once the access token expires.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the refresh token is invalid, expired or already used.
+/// - the player belongs to another game than the game client.
+/// - an error occurred during execution.
+///
+pub fn logout(form: RefreshForm, client: Option<&GameClient>, conn: &mut Connection) -> Result<(), AppError> {
+    let Ok(current) = RefreshToken::find_active_by_hash(&hash_secret(&form.refresh_token), conn) else {
+        return Err(AppError::Unauthorized("Invalid refresh token".to_string()));
+    };
+    api_key_service::authorize_user(client, current.user_id, conn)?;
+
+    match RefreshToken::revoke(current.id, conn) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(AppError::Internal("Could not revoke refresh token".to_string())),
+    }
+}
+
+/// Checks if the given user is a registered player, whose scores can only be submitted with an access token.
+pub fn is_registered(user: &User) -> bool {
+    user.password_hash.is_some() || user.device_id_hash.is_some()
+}
+
+/// Fetches the user of the signed in player.
+fn find_player(player: &Player, conn: &mut Connection) -> Result<User, AppError> {
+    match User::find_by_id(player.user_id, conn) {
+        Ok(user) => Ok(user),
+        Err(_) => Err(AppError::Unauthorized("Invalid player token".to_string())),
+    }
+}
+
+/// Signs a new access token and stores a new refresh token for the given user.
+fn create_session(user: User, issuer: &PlayerTokenIssuer, conn: &mut Connection) -> Result<PlayerSession, AppError> {
+    create_session_with_device_token(user, None, issuer, conn)
+}
+
+/// Signs a new access token and stores a new refresh token for the given user, returning the given device token
+/// as part of the session.
+fn create_session_with_device_token(
+    user: User,
+    device_token: Option<String>,
+    issuer: &PlayerTokenIssuer,
+    conn: &mut Connection,
+) -> Result<PlayerSession, AppError> {
+    let secret = generate_refresh_token()?;
+
+    match RefreshToken::insert(new_refresh_token(user.id, &secret, issuer), conn) {
+        Ok(_) => new_session(user, secret, device_token, issuer),
+        Err(_) => Err(AppError::Internal("Could not create player session".to_string())),
+    }
+}
+
+/// Signs a new access token for the given user and returns it together with the given refresh token.
+fn new_session(
+    user: User,
+    refresh_token: String,
+    device_token: Option<String>,
+    issuer: &PlayerTokenIssuer,
+) -> Result<PlayerSession, AppError> {
+    Ok(PlayerSession {
+        access_token: issuer.sign(&user)?,
+        token_type: "Bearer",
+        expires_in: issuer.access_token_ttl.as_secs(),
+        refresh_token,
+        device_token,
+        user,
+    })
+}
+
+/// Creates the database representation of a refresh token with the given secret.
+fn new_refresh_token(user_id: Uuid, secret: &str, issuer: &PlayerTokenIssuer) -> NewRefreshToken {
+    NewRefreshToken {
+        token_hash: hash_secret(secret),
+        user_id,
+        expires_at: (Utc::now() + issuer.refresh_token_ttl).naive_utc(),
+    }
+}
+
+/// Generates a new random refresh token.
+fn generate_refresh_token() -> Result<String, AppError> {
+    match random_bytes() {
+        Ok(bytes) => Ok(URL_SAFE_NO_PAD.encode(bytes)),
+        Err(_) => Err(AppError::Internal("Could not generate refresh token".to_string())),
+    }
+}
+
+fn random_bytes() -> Result<[u8; 32], ring::error::Unspecified> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new().fill(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+/// Hashes the password with PBKDF2-HMAC-SHA256 and a random salt. The hash is stored as
+/// `pbkdf2_sha256${iterations}${salt}${hash}`, so the iterations can be raised without invalidating existing hashes.
+fn hash_password(password: &str) -> Result<String, AppError> {
+    let mut salt = [0u8; 16];
+    if SystemRandom::new().fill(&mut salt).is_err() {
+        return Err(AppError::Internal("Could not hash password".to_string()));
+    }
+
+    let mut hash = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PASSWORD_ITERATIONS).unwrap(),
+        &salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+
+    Ok(format!(
+        "{}${}${}${}",
+        PASSWORD_SCHEME,
+        PASSWORD_ITERATIONS,
+        URL_SAFE_NO_PAD.encode(salt),
+        URL_SAFE_NO_PAD.encode(hash)
+    ))
+}
+
+/// Checks the password against a hash created by [`hash_password`].
+fn verify_password(password: &str, stored: &str) -> bool {
+    let parts: Vec<&str> = stored.split('$').collect();
+    let [PASSWORD_SCHEME, iterations, salt, hash] = parts[..] else {
+        return false;
+    };
+    let (Some(iterations), Ok(salt), Ok(hash)) = (
+        iterations.parse().ok().and_then(NonZeroU32::new),
+        URL_SAFE_NO_PAD.decode(salt),
+        URL_SAFE_NO_PAD.decode(hash),
+    ) else {
+        return false;
+    };
+
+    pbkdf2::verify(pbkdf2::PBKDF2_HMAC_SHA256, iterations, &salt, password.as_bytes(), &hash).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::db::test_connection, models::fixtures};
+
+    fn issuer(access_token_ttl: Duration, refresh_token_ttl: Duration) -> PlayerTokenIssuer {
+        PlayerTokenIssuer {
+            encoding_key: EncodingKey::from_secret(b"secret"),
+            decoding_key: DecodingKey::from_secret(b"secret"),
+            access_token_ttl,
+            refresh_token_ttl,
+        }
+    }
+
+    fn refresh_form(session: &PlayerSession) -> RefreshForm {
+        RefreshForm {
+            refresh_token: session.refresh_token.clone(),
+        }
+    }
+
+    #[test]
+    fn verifies_a_hashed_password() {
+        let hash = hash_password("correct horse").unwrap();
+
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("battery staple", &hash));
+        assert_ne!(hash, hash_password("correct horse").unwrap(), "every hash has its own salt");
+    }
+
+    #[test]
+    fn rejects_malformed_password_hashes() {
+        let hash = hash_password("correct horse").unwrap();
+
+        assert!(!verify_password("correct horse", ""));
+        assert!(!verify_password("correct horse", &hash.replacen(PASSWORD_SCHEME, "bcrypt", 1)));
+        assert!(!verify_password("correct horse", &hash.replacen("$100000$", "$0$", 1)));
+        assert!(!verify_password("correct horse", &format!("{}$extra", hash)));
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn verifies_a_signed_access_token() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let user = fixtures::user(&game, "Alice", &mut conn);
+        let issuer = issuer(Duration::from_secs(900), Duration::from_secs(3600));
+
+        let player = issuer.verify(&issuer.sign(&user).unwrap()).unwrap();
+
+        assert_eq!(player.user_id, user.id);
+        assert_eq!(player.game_id, game.id);
+    }
+
+    #[test]
+    fn rejects_an_expired_access_token() {
+        let issuer = issuer(Duration::from_secs(900), Duration::from_secs(3600));
+        let expired_at = Utc::now().timestamp() - 3600;
+        let claims = PlayerClaims {
+            sub: Uuid::new_v4(),
+            game_id: Uuid::new_v4(),
+            iss: PLAYER_TOKEN_ISSUER.to_string(),
+            aud: PLAYER_TOKEN_AUDIENCE.to_string(),
+            iat: expired_at - 900,
+            exp: expired_at,
+        };
+        let token = jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &issuer.encoding_key).unwrap();
+
+        assert!(matches!(issuer.verify(&token), Err(AppError::Unauthorized(_))));
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn rejects_a_reused_refresh_token() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let user = fixtures::user(&game, "Alice", &mut conn);
+        let issuer = issuer(Duration::from_secs(900), Duration::from_secs(3600));
+        let session = create_session(user, &issuer, &mut conn).unwrap();
+
+        let refreshed = refresh(refresh_form(&session), None, &issuer, &mut conn).unwrap();
+        let reused = refresh(refresh_form(&session), None, &issuer, &mut conn);
+
+        assert!(matches!(reused, Err(AppError::Unauthorized(_))));
+        assert!(refresh(refresh_form(&refreshed), None, &issuer, &mut conn).is_ok());
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn rejects_an_expired_refresh_token() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let user = fixtures::user(&game, "Alice", &mut conn);
+        let issuer = issuer(Duration::from_secs(900), Duration::ZERO);
+        let session = create_session(user, &issuer, &mut conn).unwrap();
+
+        let result = refresh(refresh_form(&session), None, &issuer, &mut conn);
+
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+}
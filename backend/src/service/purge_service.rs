@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use diesel::Connection as _;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        game::Game, level::Level, score::Score, score_attempt::ScoreAttempt, submission_nonce::SubmissionNonce,
+        user::User,
+    },
+};
+
+use super::signature_service::MAX_SIGNATURE_AGE;
+
+/// Permanently removes the games, levels, users and scores that were deleted longer than the given retention
+/// ago. Returns the number of removed rows, not counting the rows removed by the database together with their
+/// parent.
+///
+/// # Errors
+/// - If the deleted data could not be removed.
+pub fn purge_deleted(retention: Duration, conn: &mut Connection) -> Result<usize, String> {
+    let retention = chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::MAX);
+    let Some(before) = Utc::now().naive_utc().checked_sub_signed(retention) else {
+        return Ok(0);
+    };
+
+    conn.transaction(|conn| {
+        let scores = Score::purge(before, conn)?;
+        let users = User::purge(before, conn)?;
+        let levels = Level::purge(before, conn)?;
+        let games = Game::purge(before, conn)?;
+
+        Ok(scores + users + levels + games)
+    })
+    .map_err(|err: diesel::result::Error| format!("Cannot purge deleted data, reason {}", err))
+}
+
+/// Removes the nonces of signed score submissions that are too old to be replayed, because the signature of such a
+/// submission has already expired, and the score attempts that no longer count towards a submission limit.
+///
+/// # Errors
+/// - If the nonces or attempts could not be removed.
+pub fn purge_expired(conn: &mut Connection) -> Result<usize, String> {
+    let before = Utc::now().naive_utc() - chrono::Duration::seconds(MAX_SIGNATURE_AGE * 2);
+
+    let nonces = SubmissionNonce::delete_before(before, conn)
+        .map_err(|err| format!("Cannot purge submission nonces, reason {}", err))?;
+    let attempts =
+        ScoreAttempt::purge(conn).map_err(|err| format!("Cannot purge score attempts, reason {}", err))?;
+
+    Ok(nonces + attempts)
+}
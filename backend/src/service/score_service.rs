@@ -1,38 +1,60 @@
 use std::str::FromStr;
 
+use chrono::Utc;
+use diesel::result::{DatabaseErrorKind, Error};
 use uuid::Uuid;
 
 use crate::{
-    config::db::Pool,
-    models::score::{Score, ScoreDto, ScoreForm},
-    response::{ErrorResponse, ResponseBody},
+    config::db::Connection,
+    models::{
+        api_key::GameClient,
+        audit_log::Actor,
+        auth::Role,
+        leaderboard::{
+            Leaderboard, LeaderboardEntry, LeaderboardPeriod, LeaderboardQuery, PeriodWindow,
+            DEFAULT_LEADERBOARD_LIMIT, MAX_LEADERBOARD_LIMIT,
+        },
+        level::{Level, ScoreUnit},
+        pagination::{Page, PageError, PageQuery},
+        player::Player,
+        score::{RankFilter, Score, ScoreDto, ScoreForm, ScoreSort, ScoreSubmission, SubmissionSignature},
+        score_attempt::ScoreAttempt,
+    },
+    response::{AppError, FieldError},
 };
 
-use super::{game_service, level_service, user_service};
+use super::{
+    game_service, level_service, name_service::NameFilter, player_service, season_service, signature_service,
+    user_service,
+};
 
-/// Queries the database and fetches all the registered scores from a game.
+/// Queries the database and fetches a page of the registered scores from a game.
 ///
 /// # Errors
 ///
 /// This function fails if:
 /// - could not find game with given id.
+/// - the cursor of the page is invalid.
 /// - an error occurred during execution.
 ///
-pub fn find_all(game_id: Uuid, pool: &Pool) -> Result<Vec<ScoreDto>, ErrorResponse> {
-    let game: Result<crate::models::game::Game, ErrorResponse> =
-        game_service::find_by_id(game_id, pool);
+pub fn find_all(
+    game_id: Uuid,
+    page: &PageQuery<ScoreSort>,
+    conn: &mut Connection,
+) -> Result<Page<ScoreDto>, AppError> {
+    let game: Result<crate::models::game::Game, AppError> =
+        game_service::find_by_id(game_id, conn);
     if game.is_err() {
-        return Err(ResponseBody::not_found_error(&format!(
+        return Err(AppError::NotFound(format!(
             "Game with id '{}' not found",
-            game_id.to_string()
+            game_id
         )));
     }
 
-    match Score::find_all(&game?, &mut pool.get().unwrap()) {
+    match Score::find_page_by_game(&game?, page, conn) {
         Ok(scores) => Ok(scores),
-        Err(_) => Err(ErrorResponse::internal_error(
-            "Error while fetching scores occurred",
-        )),
+        Err(PageError::InvalidCursor) => Err(AppError::BadRequest("Invalid cursor".to_string())),
+        Err(_) => Err(AppError::Internal("Error while fetching scores occurred".to_string())),
     }
 }
 
@@ -43,16 +65,34 @@ pub fn find_all(game_id: Uuid, pool: &Pool) -> Result<Vec<ScoreDto>, ErrorRespon
 /// This function fails if:
 /// - could not find score with given id.
 ///
-pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<ScoreDto, ErrorResponse> {
-    match Score::find_by_id(id, &mut pool.get().unwrap()) {
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<ScoreDto, AppError> {
+    match Score::find_by_id(id, conn) {
         Ok(score) => Ok(score),
-        Err(_) => Err(ResponseBody::not_found_error(&format!(
+        Err(_) => Err(AppError::NotFound(format!(
             "Score with id '{}' not found",
-            id.to_string()
+            id
         ))),
     }
 }
 
+/// Checks if the actor can moderate the score. The game of the score is resolved through its level, scores without
+/// a level can only be moderated by admins and moderators.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the dashboard user is not allowed to moderate the game of the score.
+///
+pub fn authorize_moderator(actor: &Actor, score: &ScoreDto, conn: &mut Connection) -> Result<(), AppError> {
+    match (&score.level, actor) {
+        (Some(level), _) => game_service::authorize_moderator(actor, level.game_id, conn),
+        (None, Actor::User(user)) if !user.has_any_role(&[Role::Admin, Role::Moderator]) => {
+            Err(AppError::Forbidden("You are not allowed to manage this score".to_string()))
+        }
+        (None, _) => Ok(()),
+    }
+}
+
 /// Queries the database and fetches the registered scores by the given level.
 ///
 /// # Errors
@@ -64,24 +104,106 @@ pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<ScoreDto, ErrorResponse> {
 pub fn find_by_level(
     level_id: Uuid,
     include_hidden: bool,
-    pool: &Pool,
-) -> Result<Vec<ScoreDto>, ErrorResponse> {
-    let level = level_service::find_by_id(level_id, pool);
+    conn: &mut Connection,
+) -> Result<Vec<ScoreDto>, AppError> {
+    let level = level_service::find_by_id(level_id, conn);
     if level.is_err() {
-        return Err(ResponseBody::not_found_error(&format!(
+        return Err(AppError::NotFound(format!(
             "Level with id '{}' not found",
-            level_id.to_string()
+            level_id
         )));
     }
 
-    match Score::find_by_level(&level?, include_hidden, &mut pool.get().unwrap()) {
+    match Score::find_by_level(&level?, include_hidden, conn) {
         Ok(score) => Ok(score),
-        Err(_) => Err(ResponseBody::internal_error(
-            "An error occured when trying to fetch scores",
-        )),
+        Err(_) => Err(AppError::Internal("An error occured when trying to fetch scores".to_string())),
     }
 }
 
+/// Queries the database and builds the ranked leaderboard of the given level, using the sort direction and
+/// aggregation configured on the level. When a `user_id` is given in the query, the returned window is centred
+/// around the best score of that user and the `offset` is ignored. A daily, weekly or monthly leaderboard only
+/// ranks the scores submitted within the current period, or within the past period of the given key. The season
+/// leaderboard only ranks the scores submitted after the last archived season of the game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the timezone or the key of the period is invalid.
+/// - could not find level with given id.
+/// - the given user has no score on the level.
+/// - an error occurred during execution.
+///
+pub fn leaderboard(
+    level_id: Uuid,
+    query: LeaderboardQuery,
+    conn: &mut Connection,
+) -> Result<Leaderboard, AppError> {
+    let window = PeriodWindow::resolve(
+        query.period,
+        query.period_key.as_deref(),
+        query.timezone.as_deref(),
+        Utc::now(),
+    )
+    .map_err(AppError::BadRequest)?;
+
+    let level = level_service::find_by_id(level_id, conn)?;
+    let reset_at = match query.period {
+        LeaderboardPeriod::Season => season_service::last_reset(level.game_id, conn)?,
+        _ => None,
+    };
+    let filter = match &window {
+        Some(window) => RankFilter {
+            include_hidden: query.hidden,
+            since: Some(window.starts_at),
+            until: Some(window.ends_at),
+        },
+        None => RankFilter {
+            include_hidden: query.hidden,
+            since: reset_at,
+            until: None,
+        },
+    };
+
+    let Ok(lookup) = Score::lookup_rank(&level, &filter, query.user_id, conn) else {
+        return Err(AppError::Internal("An error occurred when trying to fetch the leaderboard".to_string()));
+    };
+    let total = lookup.total;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+        .clamp(1, MAX_LEADERBOARD_LIMIT);
+
+    let offset = match (query.user_id, lookup.position) {
+        (Some(_), Some(position)) => (position - 1 - limit / 2).min(total - limit).max(0),
+        (Some(user_id), None) => {
+            return Err(AppError::NotFound(format!(
+                "User with id '{}' has no score on level '{}'",
+                user_id, level_id
+            )))
+        }
+        (None, _) => query.offset.unwrap_or(0).max(0),
+    };
+
+    let entries = match Score::find_ranked_by_level(&level, &filter, offset, Some(limit), conn) {
+        Ok(scores) => scores
+            .into_iter()
+            .map(|ranked| LeaderboardEntry::new(ranked, query.ranking, total))
+            .collect(),
+        Err(_) => {
+            return Err(AppError::Internal("An error occurred when trying to fetch the leaderboard".to_string()))
+        }
+    };
+
+    Ok(Leaderboard {
+        total,
+        ranking: query.ranking,
+        window,
+        reset_at,
+        entries,
+    })
+}
+
 /// Queries the database and fetches the registered scores by the given user.
 ///
 /// # Errors
@@ -93,38 +215,124 @@ pub fn find_by_level(
 pub fn find_by_user(
     user_id: Uuid,
     include_hidden: bool,
-    pool: &Pool,
-) -> Result<Vec<ScoreDto>, ErrorResponse> {
-    let user = user_service::find_by_id(user_id, pool);
+    conn: &mut Connection,
+) -> Result<Vec<ScoreDto>, AppError> {
+    let user = user_service::find_by_id(user_id, conn);
     if user.is_err() {
-        return Err(ResponseBody::not_found_error(&format!(
+        return Err(AppError::NotFound(format!(
             "User with id '{}' not found",
-            user_id.to_string()
+            user_id
         )));
     }
 
-    match Score::find_by_user(&user?, include_hidden, &mut pool.get().unwrap()) {
+    match Score::find_by_user(&user?, include_hidden, conn) {
         Ok(score) => Ok(score),
-        Err(_) => Err(ResponseBody::internal_error(
-            "An error occurred when trying to fetch scores",
+        Err(_) => Err(AppError::Internal("An error occurred when trying to fetch scores".to_string())),
+    }
+}
+
+/// Binds a score submitted by a game client to the signed in player. Without a player, the score can only be
+/// submitted for users that are not registered players, so anyone with the API key cannot submit scores in the
+/// name of a player.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the user of the score is not the signed in player.
+/// - no player is signed in and the user of the score is a registered player.
+/// - could not find the user of the score.
+///
+pub fn bind_player(user_id: Option<Uuid>, player: Option<&Player>, conn: &mut Connection) -> Result<Option<Uuid>, AppError> {
+    match (user_id, player) {
+        (Some(user_id), Some(player)) if user_id != player.user_id => Err(AppError::Forbidden(
+            "Scores can only be submitted for the signed in player".to_string(),
         )),
+        (_, Some(player)) => Ok(Some(player.user_id)),
+        (Some(user_id), None) => {
+            let user = user_service::find_by_id(user_id, conn)?;
+            if player_service::is_registered(&user) {
+                return Err(AppError::Forbidden(
+                    "Scores of a registered player require the access token of the player".to_string(),
+                ));
+            }
+
+            Ok(Some(user_id))
+        }
+        (None, None) => Ok(None),
     }
 }
 
-/// Inserts a new score object and into the database.
+/// Inserts a new score object and into the database. Scores submitted by a game client must be signed when the
+/// game has a signing secret.
 ///
 /// # Errors
 ///
 /// This function fails if:
+/// - the signature of the submission is missing or invalid.
+/// - the score is outside the bounds of the level.
+/// - the username is invalid or contains a blocked word.
+/// - the user exceeded the number of submissions allowed on the level.
 /// - an error occurred during execution.
 ///
-pub fn insert(new_score: ScoreForm, pool: &Pool) -> Result<ScoreDto, ErrorResponse> {
-    match Score::insert(new_score, &mut pool.get().unwrap()) {
+pub fn insert(
+    mut new_score: ScoreForm,
+    signature: Option<&SubmissionSignature>,
+    client: Option<&GameClient>,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<ScoreDto, AppError> {
+    verify_signature(&new_score, signature, client, conn)?;
+    check_username(&mut new_score, names)?;
+    validate_submission(&new_score, conn)?;
+
+    match Score::insert(new_score, conn) {
         Ok(score) => Ok(score),
-        Err(err) => Err(ResponseBody::internal_error(&format!(
-            "Error saving new score, {}",
-            err
-        ))),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Stores the score as the personal best of its user on the level. An existing score of the user is only
+/// replaced when the new score is better according to the sort direction of the level. Scores submitted by a game
+/// client must be signed when the game has a signing secret.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the score has no user.
+/// - the signature of the submission is missing or invalid.
+/// - the score is outside the bounds of the level.
+/// - the username is invalid or contains a blocked word.
+/// - the user exceeded the number of submissions allowed on the level.
+/// - could not find the level or user of the score.
+/// - an error occurred during execution.
+///
+pub fn upsert_best(
+    mut new_score: ScoreForm,
+    signature: Option<&SubmissionSignature>,
+    client: Option<&GameClient>,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<ScoreSubmission, AppError> {
+    verify_signature(&new_score, signature, client, conn)?;
+    check_username(&mut new_score, names)?;
+
+    let user_id = match new_score.user_id {
+        Some(user_id) => user_id,
+        None => {
+            return Err(AppError::BadRequest("A user is required to submit a personal best".to_string()))
+        }
+    };
+
+    let level = validate_submission(&new_score, conn)?;
+    let user = user_service::find_by_id(user_id, conn)?;
+    let reset_at = season_service::last_reset(level.game_id, conn)?;
+
+    match Score::upsert_best(new_score, level, user, reset_at, conn) {
+        Ok((score, is_personal_best)) => Ok(ScoreSubmission {
+            score,
+            personal_best: Some(is_personal_best),
+        }),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -135,18 +343,29 @@ pub fn insert(new_score: ScoreForm, pool: &Pool) -> Result<ScoreDto, ErrorRespon
 /// This function fails if:
 /// - an error occurred during execution.
 /// - no score could be find with the given id.
+/// - the score is outside the bounds of the level.
+/// - the username is invalid or contains a blocked word.
 ///
-pub fn update(id: Uuid, updated_score: ScoreForm, pool: &Pool) -> Result<ScoreDto, ErrorResponse> {
-    if !score_exists(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn update(
+    id: Uuid,
+    mut updated_score: ScoreForm,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<ScoreDto, AppError> {
+    if !score_exists(id, conn) {
+        return Err(AppError::NotFound(format!(
             "Score with id '{}' not found",
-            id.to_string()
+            id
         )));
     }
+    check_username(&mut updated_score, names)?;
+
+    let level = level_service::find_by_id(updated_score.level_id, conn)?;
+    validate_bounds(&updated_score, &level)?;
 
-    match Score::update(id, updated_score, &mut pool.get().unwrap()) {
+    match Score::update(id, updated_score, conn) {
         Ok(score) => Ok(score),
-        Err(_) => Err(ResponseBody::internal_error("Error while updating score")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -158,21 +377,128 @@ pub fn update(id: Uuid, updated_score: ScoreForm, pool: &Pool) -> Result<ScoreDt
 /// - an error occurred during execution.
 /// - no score could be found with the given id.
 ///
-pub fn delete(ids: String, pool: &Pool) -> Result<usize, ErrorResponse> {
-    let score_ids = ids
-        .split(',')
+pub fn delete(ids: String, conn: &mut Connection) -> Result<usize, AppError> {
+    match Score::delete_many(parse_ids(&ids), conn) {
+        Ok(result) => Ok(result),
+        Err(_) => Err(AppError::Internal("Error while deleting score".to_string())),
+    }
+}
+
+/// Restores a deleted score with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - an error occurred during execution.
+/// - no deleted score could be found with the given id.
+/// - the level or the user of the score is deleted.
+/// - the score is a personal best and its user has another personal best on the level.
+///
+pub fn restore(id: Uuid, conn: &mut Connection) -> Result<ScoreDto, AppError> {
+    let Ok(score) = Score::find_deleted_by_id(id, conn) else {
+        return Err(AppError::NotFound(format!(
+            "Deleted score with id '{}' not found",
+            id
+        )));
+    };
+    if score.level_id.is_some_and(|level_id| !level_service::level_exists(level_id, conn)) {
+        return Err(AppError::Conflict("Cannot restore score, its level is deleted".to_string()));
+    }
+    if score.user_id.is_some_and(|user_id| !user_service::user_exists(user_id, conn)) {
+        return Err(AppError::Conflict("Cannot restore score, its user is deleted".to_string()));
+    }
+
+    match Score::restore(id, conn) {
+        Ok(score) => Ok(score),
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(AppError::Conflict(
+            "Cannot restore score, its user has another personal best on the level".to_string(),
+        )),
+        Err(_) => Err(AppError::Internal("Error while restoring score".to_string())),
+    }
+}
+
+/// Parses a comma separated list of score ids, ignoring the values that are not valid ids.
+pub fn parse_ids(ids: &str) -> Vec<Uuid> {
+    ids.split(',')
         .filter_map(|s| Uuid::from_str(s).ok())
-        .collect::<Vec<Uuid>>();
+        .collect()
+}
 
-    match Score::delete_many(score_ids, &mut pool.get().unwrap()) {
-        Ok(result) => Ok(result),
-        Err(_) => Err(ResponseBody::internal_error("Error while deleting score")),
+/// Verifies the signature of a score submitted by a game client. Scores submitted by dashboard users are
+/// trusted and don't need to be signed.
+fn verify_signature(
+    new_score: &ScoreForm,
+    signature: Option<&SubmissionSignature>,
+    client: Option<&GameClient>,
+    conn: &mut Connection,
+) -> Result<(), AppError> {
+    match client {
+        Some(_) => signature_service::verify_submission(new_score, signature, conn),
+        None => Ok(()),
+    }
+}
+
+/// Validates a newly submitted score against the bounds and submission limit of its level, returning the level
+/// of the score. A valid submission on a level with a submission limit is recorded as an attempt, also when it
+/// doesn't become a new personal best.
+fn validate_submission(new_score: &ScoreForm, conn: &mut Connection) -> Result<Level, AppError> {
+    let level = level_service::find_by_id(new_score.level_id, conn)?;
+    validate_bounds(new_score, &level)?;
+
+    let (Some(max_submissions), Some(window)) = (level.max_submissions, level.submission_window) else {
+        return Ok(level);
+    };
+
+    match ScoreAttempt::record(&level, new_score, window, max_submissions, conn) {
+        Ok(true) => Ok(level),
+        Ok(false) => Err(AppError::TooManyRequests(format!(
+            "Only {} scores can be submitted every {} seconds on this level",
+            max_submissions, window
+        ))),
+        Err(_) => Err(AppError::Internal("Cannot count submitted scores".to_string())),
+    }
+}
+
+/// Normalizes the free text username of the score and checks it against the blocked words.
+pub fn check_username(score: &mut ScoreForm, names: &NameFilter) -> Result<(), AppError> {
+    if let Some(username) = &score.username {
+        score.username = Some(names.check("username", username)?);
+    }
+
+    Ok(())
+}
+
+/// Checks if the score is within the minimum and maximum score of its level, and isn't a negative duration on a
+/// level that is timed in milliseconds.
+pub fn validate_bounds(score: &ScoreForm, level: &Level) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+
+    if level.score_unit == ScoreUnit::Milliseconds && score.highscore < 0 {
+        errors.push(FieldError::new("score", "must be greater than or equal to 0 milliseconds"));
+    }
+    if let Some(min_score) = level.min_score.filter(|min| score.highscore < *min) {
+        errors.push(FieldError::new(
+            "score",
+            &format!("must be greater than or equal to {}", min_score),
+        ));
+    }
+    if let Some(max_score) = level.max_score.filter(|max| score.highscore > *max) {
+        errors.push(FieldError::new(
+            "score",
+            &format!("must be less than or equal to {}", max_score),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation("Invalid score".to_string(), errors))
     }
 }
 
 /// Checks if a score exists in the database with the given id.
-pub fn score_exists(id: Uuid, pool: &Pool) -> bool {
-    let score = Score::find_by_id(id, &mut pool.get().unwrap());
+pub fn score_exists(id: Uuid, conn: &mut Connection) -> bool {
+    let score = Score::find_by_id(id, conn);
 
     score.is_ok()
 }
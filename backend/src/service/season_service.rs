@@ -0,0 +1,225 @@
+use chrono::NaiveDateTime;
+use diesel::{result::Error, Connection as _};
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        game::Game,
+        level::Level,
+        score::{RankFilter, Score},
+        season::{NewSeasonStanding, Season, SeasonForm, SeasonStanding, StandingQuery},
+    },
+    response::{AppError, FieldError},
+};
+
+use super::game_service;
+
+/// Queries the database and fetches the seasons of the given game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - an error occurred during execution.
+///
+pub fn find_by_game(game_id: Uuid, conn: &mut Connection) -> Result<Vec<Season>, AppError> {
+    let game = game_service::find_by_id(game_id, conn)?;
+
+    match Season::find_by_game(&game, conn) {
+        Ok(seasons) => Ok(seasons),
+        Err(_) => Err(AppError::Internal("Cannot fetch seasons".to_string())),
+    }
+}
+
+/// Queries the database and fetches the season with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no season could be found with the given id.
+///
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<Season, AppError> {
+    match Season::find_by_id(id, conn) {
+        Ok(season) => Ok(season),
+        Err(_) => Err(AppError::NotFound(format!("Season with id '{}' not found", id))),
+    }
+}
+
+/// Adds a new season to the given game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - the season doesn't end after it starts.
+/// - the season overlaps with another season of the game.
+/// - an error occurred during execution.
+///
+pub fn insert(new_season: SeasonForm, conn: &mut Connection) -> Result<Season, AppError> {
+    game_service::find_by_id(new_season.game_id, conn)?;
+    validate(&new_season, None, conn)?;
+
+    match Season::insert(new_season, conn) {
+        Ok(season) => Ok(season),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Updates the season with the given id. A season cannot be changed once its standings are archived, or be moved
+/// to another game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no season could be found with the given id.
+/// - the season is already archived.
+/// - the season doesn't end after it starts.
+/// - the season overlaps with another season of the game.
+/// - an error occurred during execution.
+///
+pub fn update(id: Uuid, updated_season: SeasonForm, conn: &mut Connection) -> Result<Season, AppError> {
+    let season = find_by_id(id, conn)?;
+    if season.archived_at.is_some() {
+        return Err(AppError::Conflict("An archived season cannot be changed".to_string()));
+    }
+    if updated_season.game_id != season.game_id {
+        return Err(AppError::BadRequest("A season cannot be moved to another game".to_string()));
+    }
+
+    validate(&updated_season, Some(id), conn)?;
+
+    match Season::update(id, updated_season, conn) {
+        Ok(season) => Ok(season),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Removes the season with the given id, the scores of the season are kept. An archived season cannot be removed,
+/// so its standings remain available.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no season could be found with the given id.
+/// - the season has been archived.
+/// - an error occurred during execution.
+///
+pub fn delete(id: Uuid, conn: &mut Connection) -> Result<usize, AppError> {
+    let season = find_by_id(id, conn)?;
+    if season.archived_at.is_some() {
+        return Err(AppError::BadRequest("An archived season cannot be deleted".to_string()));
+    }
+
+    match Season::delete(id, conn) {
+        Ok(results) => Ok(results),
+        Err(_) => Err(AppError::Internal("Could not delete season".to_string())),
+    }
+}
+
+/// Queries the database and fetches the archived standings of the season with the given id. The standings are
+/// empty while the season has not been archived.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no season could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn standings(id: Uuid, query: StandingQuery, conn: &mut Connection) -> Result<Vec<SeasonStanding>, AppError> {
+    find_by_id(id, conn)?;
+
+    match SeasonStanding::find_by_season(id, query.level_id, conn) {
+        Ok(standings) => Ok(standings),
+        Err(_) => Err(AppError::Internal("Cannot fetch standings".to_string())),
+    }
+}
+
+/// Fetches the end of the last archived season of the given game, from which the live leaderboards rank the
+/// scores. Returns nothing when no season of the game has been archived.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - an error occurred during execution.
+///
+pub fn last_reset(game_id: Uuid, conn: &mut Connection) -> Result<Option<NaiveDateTime>, AppError> {
+    match Season::last_reset(game_id, conn) {
+        Ok(reset) => Ok(reset),
+        Err(_) => Err(AppError::Internal("Cannot fetch seasons of game".to_string())),
+    }
+}
+
+/// Archives the final standings of every season that has ended. Every season is archived in its own
+/// transaction, so a season is either archived with all its standings or not at all. Returns the number of
+/// archived seasons.
+///
+/// # Errors
+/// - If the standings of a season could not be archived.
+pub fn archive_ended(conn: &mut Connection) -> Result<usize, String> {
+    let mut archived = 0;
+
+    loop {
+        let season = conn
+            .transaction(|conn| {
+                let Some(season) = Season::lock_next_ended(conn)? else {
+                    return Ok(None);
+                };
+
+                let standings = final_standings(&season, conn)?;
+                Season::archive(season.id, &standings, conn).map(Some)
+            })
+            .map_err(|err: Error| format!("Cannot archive season standings, reason {}", err))?;
+
+        match season {
+            Some(_) => archived += 1,
+            None => return Ok(archived),
+        }
+    }
+}
+
+/// Ranks the scores submitted during the season on every level of the game, like the live leaderboards rank
+/// them. Hidden scores are not ranked.
+fn final_standings(season: &Season, conn: &mut Connection) -> Result<Vec<NewSeasonStanding>, Error> {
+    let game = Game::find_by_id(season.game_id, conn)?;
+    let mut standings = Vec::new();
+
+    for level in Level::find_by_game(&game, conn)? {
+        let filter = RankFilter {
+            include_hidden: false,
+            since: Some(season.starts_at),
+            until: Some(season.ends_at),
+        };
+
+        for ranked in Score::find_ranked_by_level(&level, &filter, 0, None, conn)? {
+            let score = ranked.score;
+            standings.push(NewSeasonStanding {
+                season_id: season.id,
+                level_id: level.id,
+                rank: ranked.competition_rank,
+                score_id: Some(score.id),
+                user_id: score.user.as_ref().map(|user| user.id),
+                name: score.user.map(|user| user.name).or(score.username),
+                score: score.score,
+            });
+        }
+    }
+
+    Ok(standings)
+}
+
+/// Checks if the season ends after it starts and doesn't overlap with another season of its game.
+fn validate(season: &SeasonForm, except: Option<Uuid>, conn: &mut Connection) -> Result<(), AppError> {
+    if season.ends_at <= season.starts_at {
+        return Err(AppError::Validation(
+            "Invalid season".to_string(),
+            vec![FieldError::new("ends_at", "must be after starts_at")],
+        ));
+    }
+
+    match Season::overlaps(season.game_id, season.starts_at, season.ends_at, except, conn) {
+        Ok(false) => Ok(()),
+        Ok(true) => Err(AppError::Conflict("The season overlaps with another season of the game".to_string())),
+        Err(_) => Err(AppError::Internal("Cannot fetch seasons of game".to_string())),
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::Utc;
+use diesel::result::{DatabaseErrorKind, Error};
+use ring::{
+    hmac,
+    rand::{SecureRandom, SystemRandom},
+};
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        game::Game,
+        score::{ScoreForm, SubmissionSignature},
+        submission_nonce::SubmissionNonce,
+    },
+    response::AppError,
+};
+
+use super::{game_service, level_service};
+
+/// The maximum number of seconds between signing a submission and receiving it. Nonces are kept for twice this
+/// period and then removed by the purge task, after that a replayed submission is rejected because of its
+/// timestamp.
+pub const MAX_SIGNATURE_AGE: i64 = 300;
+
+/// The maximum length of a nonce.
+const MAX_NONCE_LENGTH: usize = 64;
+
+/// Generates a new signing secret for the game with the given id. After this, every score submitted for the
+/// game must be signed with the returned secret.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no game could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn generate_secret(game_id: Uuid, conn: &mut Connection) -> Result<String, AppError> {
+    game_service::find_by_id(game_id, conn)?;
+
+    let mut bytes = [0u8; 32];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        return Err(AppError::Internal("Could not generate signing secret".to_string()));
+    }
+
+    let secret = to_hex(&bytes);
+    match Game::set_signing_secret(game_id, Some(secret.clone()), conn) {
+        Ok(_) => Ok(secret),
+        Err(_) => Err(AppError::Internal("Could not save signing secret".to_string())),
+    }
+}
+
+/// Removes the signing secret of the game with the given id, after which score submissions no longer need to be
+/// signed.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no game could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn disable(game_id: Uuid, conn: &mut Connection) -> Result<Game, AppError> {
+    game_service::find_by_id(game_id, conn)?;
+
+    match Game::set_signing_secret(game_id, None, conn) {
+        Ok(game) => Ok(game),
+        Err(_) => Err(AppError::Internal("Could not remove signing secret".to_string())),
+    }
+}
+
+/// Verifies the signature of a submitted score when the game of the score has a signing secret. The nonce of a
+/// valid signature is stored, so the same submission cannot be used again.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no level could be found for the score.
+/// - the game requires a signature and none is given.
+/// - the signature is invalid or expired.
+/// - the nonce was already used.
+/// - an error occurred during execution.
+///
+pub fn verify_submission(
+    score: &ScoreForm,
+    signature: Option<&SubmissionSignature>,
+    conn: &mut Connection,
+) -> Result<(), AppError> {
+    let level = level_service::find_by_id(score.level_id, conn)?;
+    let game = game_service::find_by_id(level.game_id, conn)?;
+    let Some(secret) = game.signing_secret else {
+        return Ok(());
+    };
+
+    let Some(signature) = signature else {
+        return Err(AppError::BadRequest("Score submissions of this game must be signed".to_string()));
+    };
+
+    if signature.nonce.is_empty() || signature.nonce.len() > MAX_NONCE_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Nonce must be between 1 and {} characters",
+            MAX_NONCE_LENGTH
+        )));
+    }
+    if signature.nonce.contains(':') {
+        return Err(AppError::BadRequest("Nonce cannot contain a colon".to_string()));
+    }
+
+    let now = Utc::now();
+    if (now.timestamp() - signature.timestamp).abs() > MAX_SIGNATURE_AGE {
+        return Err(AppError::Unauthorized("Signature expired".to_string()));
+    }
+
+    let message = format!(
+        "{}:{}:{}:{}:{}:{}:{}",
+        score.level_id,
+        score.user_id.map(|id| id.to_string()).unwrap_or_default(),
+        score.highscore,
+        score.is_hidden,
+        signature.timestamp,
+        signature.nonce,
+        score.username.as_deref().unwrap_or_default()
+    );
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let is_valid = from_hex(&signature.signature)
+        .is_some_and(|tag| hmac::verify(&key, message.as_bytes(), &tag).is_ok());
+
+    if !is_valid {
+        return Err(AppError::Unauthorized("Invalid signature".to_string()));
+    }
+
+    let nonce = SubmissionNonce {
+        game_id: game.id,
+        nonce: signature.nonce.clone(),
+    };
+
+    match SubmissionNonce::insert(nonce, conn) {
+        Ok(_) => Ok(()),
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            Err(AppError::Unauthorized("Nonce already used".to_string()))
+        }
+        Err(_) => Err(AppError::Internal("Could not verify signature".to_string())),
+    }
+}
+
+/// Encodes the given bytes as a lowercase hex string.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes a hex string, returns `None` if the string is not valid hex.
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(value.get(index..index + 2)?, 16).ok())
+        .collect()
+}
@@ -1,9 +1,9 @@
 use uuid::Uuid;
 
 use crate::{
-    config::db::Pool,
+    config::db::Connection,
     models::{game::Game, score::Score, user::User},
-    response::{ErrorResponse, ResponseBody},
+    response::AppError,
 };
 
 use super::game_service;
@@ -15,12 +15,10 @@ use super::game_service;
 /// This function fails if:
 /// - an error occured during execution.
 ///
-pub fn count_games(pool: &Pool) -> Result<i64, ErrorResponse> {
-    match Game::count(&mut pool.get().unwrap()) {
+pub fn count_games(conn: &mut Connection) -> Result<i64, AppError> {
+    match Game::count(conn) {
         Ok(count) => Ok(count),
-        Err(_) => Err(ResponseBody::internal_error(
-            "Cannot count games in database",
-        )),
+        Err(_) => Err(AppError::Internal("Cannot count games in database".to_string())),
     }
 }
 
@@ -33,46 +31,42 @@ pub fn count_games(pool: &Pool) -> Result<i64, ErrorResponse> {
 /// - no game was found with the given id.
 /// - an error occurred during execution.
 ///
-pub fn count_scores(game_id: Option<Uuid>, pool: &Pool) -> Result<i64, ErrorResponse> {
+pub fn count_scores(game_id: Option<Uuid>, conn: &mut Connection) -> Result<i64, AppError> {
     let mut game: Option<Game> = None;
     if let Some(id) = game_id {
-        let fetched_game = game_service::find_by_id(id, pool);
+        let fetched_game = game_service::find_by_id(id, conn);
         if fetched_game.is_err() {
-            return Err(ResponseBody::not_found_error(&format!(
+            return Err(AppError::NotFound(format!(
                 "Game with id '{}' not found",
-                id.to_string()
+                id
             )));
         }
 
         game = Some(fetched_game?)
     }
 
-    match Score::count(&game, &mut pool.get().unwrap()) {
+    match Score::count(&game, conn) {
         Ok(count) => Ok(count),
-        Err(_) => Err(ResponseBody::internal_error(
-            "Cannot count scores in database",
-        )),
+        Err(_) => Err(AppError::Internal("Cannot count scores in database".to_string())),
     }
 }
 
-pub fn count_users(game_id: Option<Uuid>, pool: &Pool) -> Result<i64, ErrorResponse> {
+pub fn count_users(game_id: Option<Uuid>, conn: &mut Connection) -> Result<i64, AppError> {
     let mut game: Option<Game> = None;
     if let Some(id) = game_id {
-        let fetched_game = game_service::find_by_id(id, pool);
+        let fetched_game = game_service::find_by_id(id, conn);
         if fetched_game.is_err() {
-            return Err(ResponseBody::not_found_error(&format!(
+            return Err(AppError::NotFound(format!(
                 "Game with id '{}' not found",
-                id.to_string()
+                id
             )));
         }
 
         game = Some(fetched_game?)
     }
 
-    match User::count(&game, &mut pool.get().unwrap()) {
+    match User::count(&game, conn) {
         Ok(count) => Ok(count),
-        Err(_) => Err(ResponseBody::internal_error(
-            "Cannot count users in database",
-        )),
+        Err(_) => Err(AppError::Internal("Cannot count users in database".to_string())),
     }
 }
@@ -0,0 +1,548 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use diesel::result::{DatabaseErrorKind, Error};
+use uuid::Uuid;
+
+use crate::{
+    config::db::Connection,
+    models::{
+        api_key::GameClient,
+        level::{Level, SortDirection},
+        player::Player,
+        tournament::{
+            NewTournament, Tournament, TournamentDto, TournamentForm, TournamentScoring, TournamentStanding,
+        },
+        user::User,
+    },
+    response::{AppError, FieldError},
+};
+
+use super::{api_key_service, game_service, player_service, user_service};
+
+/// Queries the database and fetches the tournaments of the given game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - an error occurred during execution.
+///
+pub fn find_by_game(game_id: Uuid, conn: &mut Connection) -> Result<Vec<Tournament>, AppError> {
+    let game = game_service::find_by_id(game_id, conn)?;
+
+    match Tournament::find_by_game(&game, conn) {
+        Ok(tournaments) => Ok(tournaments),
+        Err(_) => Err(AppError::Internal("Cannot fetch tournaments".to_string())),
+    }
+}
+
+/// Queries the database and fetches the tournament with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+///
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<Tournament, AppError> {
+    match Tournament::find_by_id(id, conn) {
+        Ok(tournament) => Ok(tournament),
+        Err(_) => Err(AppError::NotFound(format!("Tournament with id '{}' not found", id))),
+    }
+}
+
+/// Queries the database and fetches the tournament with the given id, together with its levels, prizes and
+/// number of entrants.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn find_dto_by_id(id: Uuid, conn: &mut Connection) -> Result<TournamentDto, AppError> {
+    let tournament = find_by_id(id, conn)?;
+    to_dto(tournament, conn)
+}
+
+/// Adds a new tournament to the given game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - could not find game with given id.
+/// - the tournament doesn't end after it starts.
+/// - one of the levels doesn't belong to the game, or the levels have different sort directions.
+/// - two prizes are awarded to the same rank.
+/// - an error occurred during execution.
+///
+pub fn insert(form: TournamentForm, conn: &mut Connection) -> Result<TournamentDto, AppError> {
+    let (level_ids, sort_direction) = validate(&form, conn)?;
+
+    let new_tournament = NewTournament {
+        name: form.name,
+        game_id: form.game_id,
+        starts_at: form.starts_at,
+        ends_at: form.ends_at,
+        max_entrants: form.max_entrants,
+        scoring: form.scoring,
+        sort_direction,
+    };
+
+    match Tournament::insert(new_tournament, &level_ids, &form.prizes, conn) {
+        Ok(tournament) => to_dto(tournament, conn),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Updates the tournament with the given id, replacing its levels and prizes. A tournament cannot be moved to
+/// another game.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+/// - the tournament doesn't end after it starts.
+/// - one of the levels doesn't belong to the game, or the levels have different sort directions.
+/// - two prizes are awarded to the same rank.
+/// - more users already joined than the new maximum number of entrants.
+/// - an error occurred during execution.
+///
+pub fn update(id: Uuid, form: TournamentForm, conn: &mut Connection) -> Result<TournamentDto, AppError> {
+    let tournament = find_by_id(id, conn)?;
+    if form.game_id != tournament.game_id {
+        return Err(AppError::BadRequest("A tournament cannot be moved to another game".to_string()));
+    }
+
+    let (level_ids, sort_direction) = validate(&form, conn)?;
+    if let Some(max_entrants) = form.max_entrants
+        && count_entrants(id, conn)? > i64::from(max_entrants)
+    {
+        return Err(AppError::Conflict(
+            "More users already joined the tournament than the maximum number of entrants".to_string(),
+        ));
+    }
+
+    let updated_tournament = NewTournament {
+        name: form.name,
+        game_id: form.game_id,
+        starts_at: form.starts_at,
+        ends_at: form.ends_at,
+        max_entrants: form.max_entrants,
+        scoring: form.scoring,
+        sort_direction,
+    };
+
+    match Tournament::update(id, updated_tournament, &level_ids, &form.prizes, conn) {
+        Ok(tournament) => to_dto(tournament, conn),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Removes the tournament with the given id, together with its entrants. The scores of the tournament are kept.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn delete(id: Uuid, conn: &mut Connection) -> Result<usize, AppError> {
+    find_by_id(id, conn)?;
+
+    match Tournament::delete(id, conn) {
+        Ok(results) => Ok(results),
+        Err(_) => Err(AppError::Internal("Could not delete tournament".to_string())),
+    }
+}
+
+/// Registers a user as an entrant of the tournament with the given id. A signed in player joins as themselves,
+/// a game client can only register a registered player with the access token of that player. Users can join
+/// until the tournament ends.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+/// - no user is given, or the user doesn't belong to the game of the tournament.
+/// - the user is another user than the signed in player, or a registered player without an access token.
+/// - the tournament has ended or is full.
+/// - the user already joined the tournament.
+/// - an error occurred during execution.
+///
+pub fn join(
+    id: Uuid,
+    user_id: Option<Uuid>,
+    client: Option<&GameClient>,
+    player: Option<&Player>,
+    conn: &mut Connection,
+) -> Result<TournamentDto, AppError> {
+    let tournament = match Tournament::lock(id, conn) {
+        Ok(tournament) => tournament,
+        Err(_) => return Err(AppError::NotFound(format!("Tournament with id '{}' not found", id))),
+    };
+    api_key_service::authorize_game(client, tournament.game_id)?;
+
+    let user = entrant(user_id, client, player, conn)?;
+    if user.game_id != tournament.game_id {
+        return Err(AppError::BadRequest("User does not belong to the game of the tournament".to_string()));
+    }
+    if tournament.ends_at <= Utc::now().naive_utc() {
+        return Err(AppError::Conflict("The tournament has ended".to_string()));
+    }
+    match Tournament::has_entrant(id, user.id, conn) {
+        Ok(false) => {}
+        Ok(true) => return Err(AppError::Conflict("User already joined the tournament".to_string())),
+        Err(_) => return Err(AppError::Internal("Cannot fetch entrants of tournament".to_string())),
+    }
+    if let Some(max_entrants) = tournament.max_entrants
+        && count_entrants(id, conn)? >= i64::from(max_entrants)
+    {
+        return Err(AppError::Conflict("The tournament is full".to_string()));
+    }
+
+    match Tournament::join(id, user.id, conn) {
+        Ok(_) => to_dto(tournament, conn),
+        Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            Err(AppError::Conflict("User already joined the tournament".to_string()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Queries the database and fetches the users that joined the tournament with the given id.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn entrants(id: Uuid, conn: &mut Connection) -> Result<Vec<User>, AppError> {
+    find_by_id(id, conn)?;
+
+    match Tournament::entrants(id, conn) {
+        Ok(users) => Ok(users),
+        Err(_) => Err(AppError::Internal("Cannot fetch entrants".to_string())),
+    }
+}
+
+/// Ranks the entrants of the tournament with the given id. Only the scores the entrants achieved on the levels
+/// of the tournament during the tournament count, the best score on every level is combined according to the
+/// scoring of the tournament and ranked in the sort direction of the tournament. Entrants without a score are not
+/// ranked.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - no tournament could be found with the given id.
+/// - an error occurred during execution.
+///
+pub fn standings(id: Uuid, conn: &mut Connection) -> Result<Vec<TournamentStanding>, AppError> {
+    let tournament = find_by_id(id, conn)?;
+
+    let (entrants, prizes, scores) = match (
+        Tournament::entrants(id, conn),
+        Tournament::prizes(id, conn),
+        Tournament::find_scores(&tournament, conn),
+    ) {
+        (Ok(entrants), Ok(prizes), Ok(scores)) => (entrants, prizes, scores),
+        _ => return Err(AppError::Internal("Cannot fetch standings of tournament".to_string())),
+    };
+
+    let direction = tournament.sort_direction;
+
+    let mut bests: HashMap<(Uuid, Uuid), i32> = HashMap::new();
+    for score in scores {
+        bests
+            .entry((score.user_id, score.level_id))
+            .and_modify(|best| {
+                if direction.is_better(score.score, *best) {
+                    *best = score.score;
+                }
+            })
+            .or_insert(score.score);
+    }
+
+    let mut totals: HashMap<Uuid, (i64, i64)> = HashMap::new();
+    for ((user_id, _), best) in bests {
+        let best = i64::from(best);
+        totals
+            .entry(user_id)
+            .and_modify(|(levels, total)| {
+                *levels += 1;
+                *total = match tournament.scoring {
+                    TournamentScoring::Sum => *total + best,
+                    TournamentScoring::Best => better(direction, best, *total),
+                };
+            })
+            .or_insert((1, best));
+    }
+
+    let names: HashMap<Uuid, String> = entrants.into_iter().map(|user| (user.id, user.name)).collect();
+    let mut ranked: Vec<(Uuid, &String, i64, i64)> = totals
+        .into_iter()
+        .filter_map(|(user_id, (levels, total))| Some((user_id, names.get(&user_id)?, levels, total)))
+        .collect();
+
+    // Entrants with a score on more levels are ranked first when the scores are added up, so an entrant can't
+    // get ahead on a lower-is-better tournament by skipping levels.
+    let counts_levels = tournament.scoring == TournamentScoring::Sum;
+    ranked.sort_by(|a, b| {
+        let levels = if counts_levels { b.2.cmp(&a.2) } else { std::cmp::Ordering::Equal };
+        let score = match direction {
+            SortDirection::HigherIsBetter => b.3.cmp(&a.3),
+            SortDirection::LowerIsBetter => a.3.cmp(&b.3),
+        };
+
+        levels.then(score).then_with(|| a.1.cmp(b.1))
+    });
+
+    let prizes: HashMap<i64, String> = prizes
+        .into_iter()
+        .map(|prize| (i64::from(prize.rank), prize.description))
+        .collect();
+    let mut standings: Vec<TournamentStanding> = Vec::with_capacity(ranked.len());
+    for (index, (user_id, name, levels, total)) in ranked.into_iter().enumerate() {
+        let rank = match standings.last() {
+            Some(previous) if previous.score == total && (!counts_levels || previous.levels == levels) => {
+                previous.rank
+            }
+            _ => index as i64 + 1,
+        };
+
+        standings.push(TournamentStanding {
+            rank,
+            user_id,
+            name: name.clone(),
+            score: total,
+            levels,
+            prize: prizes.get(&rank).cloned(),
+        });
+    }
+
+    Ok(standings)
+}
+
+/// Determines the user that joins a tournament, like the user of a submitted score.
+fn entrant(
+    user_id: Option<Uuid>,
+    client: Option<&GameClient>,
+    player: Option<&Player>,
+    conn: &mut Connection,
+) -> Result<User, AppError> {
+    let user_id = match (user_id, player) {
+        (Some(user_id), Some(player)) if user_id != player.user_id => {
+            return Err(AppError::Forbidden("Players can only join a tournament themselves".to_string()));
+        }
+        (_, Some(player)) => player.user_id,
+        (Some(user_id), None) => user_id,
+        (None, None) => {
+            return Err(AppError::Validation(
+                "Invalid entry".to_string(),
+                vec![FieldError::new("user_id", "is required without a player access token")],
+            ));
+        }
+    };
+
+    let user = user_service::find_by_id(user_id, conn)?;
+    if client.is_some() && player.is_none() && player_service::is_registered(&user) {
+        return Err(AppError::Forbidden(
+            "A registered player can only join a tournament with the access token of the player".to_string(),
+        ));
+    }
+
+    Ok(user)
+}
+
+/// Checks if the tournament ends after it starts, its levels belong to its game and have the same sort
+/// direction, and every prize has its own rank. Returns the ids of the levels without duplicates, and their sort
+/// direction.
+fn validate(form: &TournamentForm, conn: &mut Connection) -> Result<(Vec<Uuid>, SortDirection), AppError> {
+    let mut errors = Vec::new();
+    if form.ends_at <= form.starts_at {
+        errors.push(FieldError::new("ends_at", "must be after starts_at"));
+    }
+
+    let mut level_ids = form.level_ids.clone();
+    let mut seen = HashSet::new();
+    level_ids.retain(|id| seen.insert(*id));
+
+    let game = game_service::find_by_id(form.game_id, conn)?;
+    let levels = match Level::find_by_game(&game, conn) {
+        Ok(levels) => levels,
+        Err(_) => return Err(AppError::Internal("Cannot fetch levels of game".to_string())),
+    };
+    let directions: Vec<SortDirection> = level_ids
+        .iter()
+        .filter_map(|id| levels.iter().find(|level| level.id == *id))
+        .map(|level| level.sort_direction)
+        .collect();
+
+    if directions.len() != level_ids.len() {
+        errors.push(FieldError::new("level_ids", "must be levels of the game"));
+    } else if directions.windows(2).any(|pair| pair[0] != pair[1]) {
+        errors.push(FieldError::new("level_ids", "must have the same sort direction"));
+    }
+
+    let mut ranks = HashSet::new();
+    if !form.prizes.iter().all(|prize| ranks.insert(prize.rank)) {
+        errors.push(FieldError::new("prizes", "must each have a different rank"));
+    }
+
+    if errors.is_empty() {
+        Ok((level_ids, directions.first().copied().unwrap_or_default()))
+    } else {
+        Err(AppError::Validation("Invalid tournament".to_string(), errors))
+    }
+}
+
+/// Adds the levels, prizes and number of entrants to the tournament.
+fn to_dto(tournament: Tournament, conn: &mut Connection) -> Result<TournamentDto, AppError> {
+    match (
+        Tournament::level_ids(tournament.id, conn),
+        Tournament::prizes(tournament.id, conn),
+        Tournament::count_entrants(tournament.id, conn),
+    ) {
+        (Ok(level_ids), Ok(prizes), Ok(entrants)) => Ok(TournamentDto {
+            tournament,
+            level_ids,
+            prizes,
+            entrants,
+        }),
+        _ => Err(AppError::Internal("Cannot fetch details of tournament".to_string())),
+    }
+}
+
+fn count_entrants(id: Uuid, conn: &mut Connection) -> Result<i64, AppError> {
+    match Tournament::count_entrants(id, conn) {
+        Ok(count) => Ok(count),
+        Err(_) => Err(AppError::Internal("Cannot count entrants of tournament".to_string())),
+    }
+}
+
+/// Returns the better of the two scores.
+fn better(direction: SortDirection, a: i64, b: i64) -> i64 {
+    match direction {
+        SortDirection::HigherIsBetter => a.max(b),
+        SortDirection::LowerIsBetter => a.min(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDateTime, TimeDelta};
+    use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    use super::*;
+    use crate::{
+        config::db::test_connection,
+        models::{fixtures, game::Game, level::Aggregation},
+        schema::level,
+    };
+
+    /// Inserts a tournament on the given levels that runs from an hour ago until an hour from now, and lets the
+    /// given users join it.
+    fn tournament(
+        game: &Game,
+        levels: &[&Level],
+        scoring: TournamentScoring,
+        entrants: &[&User],
+        conn: &mut Connection,
+    ) -> Tournament {
+        let form = TournamentForm {
+            name: "Tournament".to_string(),
+            game_id: game.id,
+            starts_at: fixtures::now() - TimeDelta::hours(1),
+            ends_at: fixtures::now() + TimeDelta::hours(1),
+            max_entrants: None,
+            scoring,
+            level_ids: levels.iter().map(|level| level.id).collect(),
+            prizes: Vec::new(),
+        };
+        let tournament = insert(form, conn).unwrap().tournament;
+        for user in entrants {
+            Tournament::join(tournament.id, user.id, conn).unwrap();
+        }
+
+        tournament
+    }
+
+    /// Fetches the standings of the tournament as `(name, rank, score, levels)`.
+    fn ranks(tournament: &Tournament, conn: &mut Connection) -> Vec<(String, i64, i64, i64)> {
+        standings(tournament.id, conn)
+            .unwrap()
+            .into_iter()
+            .map(|standing| (standing.name, standing.rank, standing.score, standing.levels))
+            .collect()
+    }
+
+    fn standing(name: &str, rank: i64, score: i64, levels: i64) -> (String, i64, i64, i64) {
+        (name.to_string(), rank, score, levels)
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn ranks_the_sum_of_every_level_or_the_single_best_score() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let first = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::AllSubmissions, &mut conn);
+        let second = fixtures::level(&game, SortDirection::HigherIsBetter, Aggregation::AllSubmissions, &mut conn);
+        let alice = fixtures::user(&game, "Alice", &mut conn);
+        let bob = fixtures::user(&game, "Bob", &mut conn);
+        let carol = fixtures::user(&game, "Carol", &mut conn);
+        let now = fixtures::now();
+        let scores: [(&Level, &User, i32, NaiveDateTime); 5] = [
+            (&first, &alice, 100, now),
+            (&first, &alice, 50, now),
+            (&second, &alice, 30, now),
+            (&first, &bob, 120, now),
+            (&second, &carol, 130, now),
+        ];
+        for (level, user, highscore, achieved_at) in scores {
+            fixtures::score(level, Some(user), highscore, achieved_at, &mut conn);
+        }
+        // Achieved before the tournament started, so it doesn't count.
+        fixtures::score(&second, Some(&bob), 500, now - TimeDelta::hours(2), &mut conn);
+
+        let entrants = [&alice, &bob, &carol];
+        let sum = tournament(&game, &[&first, &second], TournamentScoring::Sum, &entrants, &mut conn);
+        let best = tournament(&game, &[&first, &second], TournamentScoring::Best, &entrants, &mut conn);
+
+        // Alice and Carol have the same sum, but Alice has a score on more levels.
+        assert_eq!(
+            ranks(&sum, &mut conn),
+            vec![standing("Alice", 1, 130, 2), standing("Carol", 2, 130, 1), standing("Bob", 3, 120, 1)]
+        );
+        assert_eq!(
+            ranks(&best, &mut conn),
+            vec![standing("Carol", 1, 130, 1), standing("Bob", 2, 120, 1), standing("Alice", 3, 100, 2)]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a test database in TEST_DATABASE_URL"]
+    fn shares_the_rank_of_tied_entrants_in_the_sort_direction_of_the_tournament() {
+        let mut conn = test_connection();
+        let game = fixtures::game(&mut conn);
+        let level = fixtures::level(&game, SortDirection::LowerIsBetter, Aggregation::AllSubmissions, &mut conn);
+        let dave = fixtures::user(&game, "Dave", &mut conn);
+        let erin = fixtures::user(&game, "Erin", &mut conn);
+        let frank = fixtures::user(&game, "Frank", &mut conn);
+        for (user, highscore) in [(&frank, 90), (&erin, 80), (&dave, 80), (&dave, 120)] {
+            fixtures::score(&level, Some(user), highscore, fixtures::now(), &mut conn);
+        }
+        let tournament = tournament(&game, &[&level], TournamentScoring::Sum, &[&dave, &erin, &frank], &mut conn);
+
+        // Changing the level afterwards doesn't change the order of the standings.
+        diesel::update(level::table.find(level.id))
+            .set(level::sort_direction.eq(SortDirection::HigherIsBetter))
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_eq!(
+            ranks(&tournament, &mut conn),
+            vec![standing("Dave", 1, 80, 1), standing("Erin", 1, 80, 1), standing("Frank", 3, 90, 1)]
+        );
+    }
+}
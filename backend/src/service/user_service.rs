@@ -1,33 +1,42 @@
 use uuid::Uuid;
 
 use crate::{
-    config::db::Pool,
-    models::user::{User, UserForm},
-    response::{ErrorResponse, ResponseBody},
+    config::db::Connection,
+    models::{
+        pagination::{Page, PageError, PageQuery},
+        user::{User, UserForm, UserSort},
+    },
+    response::AppError,
 };
 
-use super::game_service;
+use super::{game_service, name_service::{self, NameFilter}};
 
-/// Queries the database and fetches the registered users in a game.
+/// Queries the database and fetches a page of the registered users in a game.
 ///
 /// # Errors
 ///
 /// This function fails if:
 /// - an error occurred during execution.
+/// - the cursor of the page is invalid.
 /// - no game could be found with the given id.
 ///
-pub fn find_by_game(game_id: Uuid, pool: &Pool) -> Result<Vec<User>, ErrorResponse> {
-    let game = game_service::find_by_id(game_id, pool);
+pub fn find_by_game(
+    game_id: Uuid,
+    page: &PageQuery<UserSort>,
+    conn: &mut Connection,
+) -> Result<Page<User>, AppError> {
+    let game = game_service::find_by_id(game_id, conn);
     if game.is_err() {
-        return Err(ResponseBody::not_found_error(&format!(
+        return Err(AppError::NotFound(format!(
             "Game with id '{}' not found",
-            game_id.to_string()
+            game_id
         )));
     }
 
-    match User::find_by_game(&game?, &mut pool.get().unwrap()) {
+    match User::find_page_by_game(&game?, page, conn) {
         Ok(users) => Ok(users),
-        Err(_) => Err(ResponseBody::internal_error("Cannot fetch users")),
+        Err(PageError::InvalidCursor) => Err(AppError::BadRequest("Invalid cursor".to_string())),
+        Err(_) => Err(AppError::Internal("Cannot fetch users".to_string())),
     }
 }
 
@@ -39,12 +48,12 @@ pub fn find_by_game(game_id: Uuid, pool: &Pool) -> Result<Vec<User>, ErrorRespon
 /// - an error occurred during execution.
 /// - no user could be found with the given id.
 ///
-pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<User, ErrorResponse> {
-    match User::find_by_id(id, &mut pool.get().unwrap()) {
+pub fn find_by_id(id: Uuid, conn: &mut Connection) -> Result<User, AppError> {
+    match User::find_by_id(id, conn) {
         Ok(game) => Ok(game),
-        Err(_) => Err(ResponseBody::not_found_error(&format!(
+        Err(_) => Err(AppError::NotFound(format!(
             "User with id '{}' not found",
-            id.to_string()
+            id
         ))),
     }
 }
@@ -54,15 +63,16 @@ pub fn find_by_id(id: Uuid, pool: &Pool) -> Result<User, ErrorResponse> {
 /// # Errors
 ///
 /// This function fails if:
+/// - the name is invalid or already taken in the game.
 /// - an error occurred during execution.
 ///
-pub fn insert(new_user: UserForm, pool: &Pool) -> Result<User, ErrorResponse> {
-    match User::insert(new_user, &mut pool.get().unwrap()) {
+pub fn insert(mut new_user: UserForm, names: &NameFilter, conn: &mut Connection) -> Result<User, AppError> {
+    let canonical;
+    (new_user.name, canonical) = check_name(new_user.game_id, &new_user.name, None, names, conn)?;
+
+    match User::insert(new_user, &canonical, conn) {
         Ok(score) => Ok(score),
-        Err(err) => Err(ResponseBody::internal_error(&format!(
-            "Error saving new user, {}",
-            err
-        ))),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -73,18 +83,22 @@ pub fn insert(new_user: UserForm, pool: &Pool) -> Result<User, ErrorResponse> {
 /// This function fails if:
 /// - an error occurred during execution.
 /// - no user could be found with the given id.
+/// - the name is invalid or already taken in the game.
 ///
-pub fn update(id: Uuid, updated_user: UserForm, pool: &Pool) -> Result<User, ErrorResponse> {
-    if !user_exists(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn update(id: Uuid, mut updated_user: UserForm, names: &NameFilter, conn: &mut Connection) -> Result<User, AppError> {
+    if !user_exists(id, conn) {
+        return Err(AppError::NotFound(format!(
             "User with id '{}' not found",
             id
         )));
     }
 
-    match User::update(id, updated_user, &mut pool.get().unwrap()) {
+    let canonical;
+    (updated_user.name, canonical) = check_name(updated_user.game_id, &updated_user.name, Some(id), names, conn)?;
+
+    match User::update(id, updated_user, &canonical, conn) {
         Ok(level) => Ok(level),
-        Err(_) => Err(ResponseBody::internal_error("Could not update user")),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -96,21 +110,74 @@ pub fn update(id: Uuid, updated_user: UserForm, pool: &Pool) -> Result<User, Err
 /// - an error occurred during execution.
 /// - no user could be found with the given id.
 ///
-pub fn delete(id: Uuid, pool: &Pool) -> Result<usize, ErrorResponse> {
-    if !user_exists(id, pool) {
-        return Err(ResponseBody::not_found_error(&format!(
+pub fn delete(id: Uuid, conn: &mut Connection) -> Result<usize, AppError> {
+    if !user_exists(id, conn) {
+        return Err(AppError::NotFound(format!(
             "User with id '{}' not found",
-            id.to_string()
+            id
         )));
     }
 
-    match User::delete(id, &mut pool.get().unwrap()) {
+    match User::delete(id, conn) {
         Ok(results) => Ok(results),
-        Err(_) => Err(ResponseBody::internal_error("Could not delete user")),
+        Err(_) => Err(AppError::Internal("Could not delete user".to_string())),
+    }
+}
+
+/// Restores a deleted user with the given id, together with the scores that were deleted with the user.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - an error occurred during execution.
+/// - no deleted user could be found with the given id.
+/// - the game of the user is deleted.
+///
+pub fn restore(id: Uuid, conn: &mut Connection) -> Result<User, AppError> {
+    let Ok(user) = User::find_deleted_by_id(id, conn) else {
+        return Err(AppError::NotFound(format!(
+            "Deleted user with id '{}' not found",
+            id
+        )));
+    };
+    if !game_service::game_exisits(user.game_id, conn) {
+        return Err(AppError::Conflict("Cannot restore user, its game is deleted".to_string()));
+    }
+
+    match User::restore(id, conn) {
+        Ok(user) => Ok(user),
+        Err(_) => Err(AppError::Internal("Could not restore user".to_string())),
+    }
+}
+
+/// Normalizes the given name and checks if it can be used by a user in the given game. Names are unique per game,
+/// ignoring case and characters that look alike. Returns the normalized and the canonical name.
+///
+/// # Errors
+///
+/// This function fails if:
+/// - the name is invalid or contains a blocked word.
+/// - another user than the given user already has a name that looks the same.
+/// - an error occurred during execution.
+///
+pub fn check_name(
+    game_id: Uuid,
+    name: &str,
+    except: Option<Uuid>,
+    names: &NameFilter,
+    conn: &mut Connection,
+) -> Result<(String, String), AppError> {
+    let name = names.check("name", name)?;
+    let canonical = name_service::canonicalize(&name);
+
+    match User::canonical_name_exists(game_id, &canonical, except, conn) {
+        Ok(true) => Err(AppError::Conflict(format!("Name '{}' is already taken", name))),
+        Ok(false) => Ok((name, canonical)),
+        Err(_) => Err(AppError::Internal("Cannot check name".to_string())),
     }
 }
 
 /// Checks if a user exists in the database with the given id.
-pub fn user_exists(id: Uuid, pool: &Pool) -> bool {
-    User::find_by_id(id, &mut pool.get().unwrap()).is_ok()
+pub fn user_exists(id: Uuid, conn: &mut Connection) -> bool {
+    User::find_by_id(id, conn).is_ok()
 }